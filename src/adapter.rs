@@ -1,11 +1,20 @@
 use clap::Parser;
+use crossbeam::channel::{self, Sender};
 mod device;
-pub use device::rdk as hw_specific;
+use device::rdk::interface::{ThunderClient, ThunderTokenSource};
+use device::rdk::RdkPlatform;
+use device::simulator::SimulatorPlatform;
+use device::DabPlatform;
 mod dab;
+use dab::mqtt_client::{MqttSecurity, MqttSettings};
 use dab::structs::RequestTypes;
 use dab::structs::SharedMap;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::thread;
+use std::time::Duration;
+
+const THUNDER_TOKEN_ENV: &str = "DAB_THUNDER_TOKEN";
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -13,27 +22,68 @@ struct Opt {
     /// The MQTT broker host name or IP (default: localhost)
     #[clap(short, long, value_parser, value_name = "MQTT_HOST")]
     broker: Option<String>,
-    /// The MQTT broker port (default: 1883)
+    /// The MQTT broker port (default: 1883, or 8883 with TLS)
     #[clap(short, long, value_parser, value_name = "MQTT_PORT")]
     port: Option<u16>,
+    /// Fallback MQTT broker URI, tried in order when the main broker is unreachable (can be repeated)
+    #[clap(long = "fallback-broker", value_parser, value_name = "MQTT_URI")]
+    fallback_brokers: Vec<String>,
+    /// Connect to the MQTT broker using TLS (default: enabled when a certificate is given)
+    #[clap(long, value_parser, value_name = "MQTT_TLS")]
+    mqtt_tls: Option<bool>,
+    /// CA certificate file (PEM) used to verify the MQTT broker
+    #[clap(long, value_parser, value_name = "FILE")]
+    mqtt_ca_file: Option<String>,
+    /// Client certificate file (PEM) for mutual TLS with the MQTT broker
+    #[clap(long, value_parser, value_name = "FILE")]
+    mqtt_client_cert: Option<String>,
+    /// Client private key file (PEM) for mutual TLS with the MQTT broker
+    #[clap(long, value_parser, value_name = "FILE")]
+    mqtt_client_key: Option<String>,
+    /// Password of the encrypted client private key
+    #[clap(long, value_parser, value_name = "PASSWORD")]
+    mqtt_client_key_password: Option<String>,
+    /// ALPN protocol offered to the MQTT broker (can be repeated)
+    #[clap(long, value_parser, value_name = "PROTOCOL")]
+    mqtt_alpn: Vec<String>,
+    /// User name for the MQTT broker authentication
+    #[clap(long, value_parser, value_name = "USERNAME")]
+    mqtt_username: Option<String>,
+    /// Password for the MQTT broker authentication
+    #[clap(long, value_parser, value_name = "PASSWORD")]
+    mqtt_password: Option<String>,
     /// The device host name or IP (default: localhost)
     #[clap(short, long, value_parser, value_name = "DEVICE")]
     device: Option<String>,
+    /// The Thunder JSON-RPC endpoint, http or https (default: http://<DEVICE>:9998/jsonrpc)
+    #[clap(long, value_parser, value_name = "URL")]
+    thunder_url: Option<String>,
+    /// File holding the Thunder SecurityAgent token (default: the DAB_THUNDER_TOKEN environment variable, or a token from the SecurityAgent when Thunder is local)
+    #[clap(long, value_parser, value_name = "FILE")]
+    thunder_token_file: Option<String>,
+    /// The device platform: rdk, or simulator for an in-memory virtual device (default: rdk)
+    #[clap(long, value_parser = ["rdk", "simulator"], value_name = "PLATFORM")]
+    platform: Option<String>,
+    /// Directory where output/image may write the images sent to a file destination (default: file destinations are refused)
+    #[clap(long, value_parser, value_name = "DIR")]
+    image_upload_dir: Option<String>,
     /// Print the version information
     #[clap(short, long, value_parser, value_name = "VERSION")]
     version: bool,
     /// To exit based on path file (/opt/dab-enable) status.
     #[clap(short, long, value_parser, value_name = "RETIRE")]
     retire: Option<bool>,
+    /// Time in seconds after which a request that is still being processed is answered with an error (default: 30)
+    #[clap(long, value_parser, value_name = "SECONDS")]
+    request_timeout: Option<u64>,
     /// Print RDK messages to stdout
     #[clap(long, value_parser, value_name = "DEBUG")]
     debug: Option<bool>,
 }
 
-fn fd_monitor_thread() {
+fn fd_monitor_thread(shutdown: Sender<()>) {
     use notify::{event, Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
     use std::path::Path;
-    use std::process;
     use std::time::Duration;
 
     static MONITORPATH: &str = "/opt";
@@ -69,21 +119,109 @@ fn fd_monitor_thread() {
     }
     println!("Clean-Up triggered.");
     let _ = watcher.unwatch(&monitor_path);
-    process::exit(0x00);
+    let _ = shutdown.send(());
+}
+
+fn signal_monitor_thread(shutdown: Sender<()>) {
+    use tokio::runtime::Runtime;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let mut sigterm = signal(SignalKind::terminate()).unwrap();
+        let mut sigint = signal(SignalKind::interrupt()).unwrap();
+        tokio::select! {
+            _ = sigterm.recv() => {},
+            _ = sigint.recv() => {},
+        }
+    });
+    let _ = shutdown.send(());
+}
+
+// Broker credentials may be kept in a file, so they don't show up in the command line.
+fn load_mqtt_security() -> MqttSecurity {
+    let config_path = "/etc/dab/mqtt.json";
+
+    if let Ok(json_file) = device::rdk::interface::read_platform_config_json(config_path) {
+        match serde_json::from_str::<MqttSecurity>(&json_file) {
+            Ok(security) => {
+                println!("Loaded MQTT connection settings from: {}", config_path);
+                return security;
+            }
+            Err(error) => {
+                eprintln!("Error while parsing {}: {}", config_path, error);
+            }
+        }
+    }
+    MqttSecurity::default()
 }
 
 pub fn main() {
     let opt = Opt::parse();
     let mqtt_host = opt.broker.unwrap_or(String::from("localhost"));
-    let mqtt_port = opt.port.unwrap_or(1883);
+    let fallback_brokers = opt.fallback_brokers;
+    let mqtt_security = load_mqtt_security().merge(MqttSecurity {
+        tls: opt.mqtt_tls,
+        ca_file: opt.mqtt_ca_file,
+        client_cert: opt.mqtt_client_cert,
+        client_key: opt.mqtt_client_key,
+        client_key_password: opt.mqtt_client_key_password,
+        alpn: if opt.mqtt_alpn.is_empty() {
+            None
+        } else {
+            Some(opt.mqtt_alpn)
+        },
+        username: opt.mqtt_username,
+        password: opt.mqtt_password,
+    });
+    let mqtt_port = opt
+        .port
+        .unwrap_or(if mqtt_security.tls_enabled() { 8883 } else { 1883 });
     let device_ip = opt.device.unwrap_or(String::from("localhost"));
+    let thunder_url = opt.thunder_url;
+    let thunder_token_file = opt.thunder_token_file;
+    let platform_name = opt.platform.unwrap_or(String::from("rdk"));
     let create_retire_thread = opt.retire.unwrap_or(false);
     let debug = opt.debug.unwrap_or(false);
+    let request_timeout = Duration::from_secs(opt.request_timeout.unwrap_or(30));
+    let image_upload_dir = opt.image_upload_dir.map(PathBuf::from);
 
     println!("DAB<->RDK Adapter ({:?} - {:?})", env!("VERGEN_BUILD_SEMVER"), env!("VERGEN_GIT_SHA_SHORT"));
     
     // Initialize the device
-    hw_specific::interface::init(&device_ip, debug);
+    let platform: Box<dyn DabPlatform> = match platform_name.as_str() {
+        "simulator" => {
+            println!("Using the simulated device");
+            Box::new(SimulatorPlatform::new().with_upload_dir(image_upload_dir))
+        }
+        _ => {
+            let thunder = match thunder_url {
+                Some(url) => ThunderClient::new(&url, debug),
+                None => ThunderClient::for_device(&device_ip, debug),
+            };
+            match thunder {
+                Ok(thunder) => {
+                    let token_source = if let Some(path) = thunder_token_file {
+                        ThunderTokenSource::File(path.into())
+                    } else if std::env::var_os(THUNDER_TOKEN_ENV).is_some() {
+                        ThunderTokenSource::Env(THUNDER_TOKEN_ENV.to_string())
+                    } else if thunder.is_local() {
+                        ThunderTokenSource::SecurityAgent
+                    } else {
+                        ThunderTokenSource::None
+                    };
+                    Box::new(
+                        RdkPlatform::new(thunder.with_token_source(token_source))
+                            .with_upload_dir(image_upload_dir),
+                    )
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
 
     // Register the handlers
     let mut handlers: SharedMap = HashMap::new();
@@ -157,13 +295,55 @@ pub fn main() {
         RequestTypes::VoiceSendTextRequest,
     );
     handlers.insert("version".to_string(), RequestTypes::VersionRequest);
+    #[cfg(feature = "2_1")]
+    {
+        handlers.insert(
+            "applications/install".to_string(),
+            RequestTypes::ApplicationInstallRequest,
+        );
+        handlers.insert(
+            "applications/uninstall".to_string(),
+            RequestTypes::ApplicationUninstallRequest,
+        );
+        handlers.insert(
+            "applications/clear-data".to_string(),
+            RequestTypes::ApplicationClearDataRequest,
+        );
+        handlers.insert(
+            "system/power-mode/get".to_string(),
+            RequestTypes::SystemPowerModeGetRequest,
+        );
+        handlers.insert(
+            "system/power-mode/set".to_string(),
+            RequestTypes::SystemPowerModeSetRequest,
+        );
+    }
+
+    let (shutdown_tx, shutdown_rx) = channel::bounded(1);
 
     if create_retire_thread {
+        let shutdown_tx = shutdown_tx.clone();
         let _handle = thread::Builder::new()
             .name("ExitPathMonitor".to_string())
             .spawn(move || {
-                fd_monitor_thread();
+                fd_monitor_thread(shutdown_tx);
             });
     }
-    dab::run(mqtt_host, mqtt_port, handlers);
+    let _handle = thread::Builder::new()
+        .name("SignalMonitor".to_string())
+        .spawn(move || {
+            signal_monitor_thread(shutdown_tx);
+        });
+    dab::run(
+        platform,
+        MqttSettings {
+            broker: mqtt_host,
+            port: mqtt_port,
+            fallback_brokers,
+            security: mqtt_security,
+        },
+        handlers,
+        request_timeout,
+        shutdown_rx,
+    );
 }
@@ -1,133 +1,228 @@
 use serde_json::Value;
+pub mod app_telemetry;
 pub mod device_telemetry;
+pub mod dispatcher;
 pub mod mqtt_client;
 pub mod structs;
-use crate::device::rdk as hw_specific;
-use mqtt_client::{MqttClient, MqttMessage};
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::device::DabPlatform;
+use crossbeam::channel::Receiver;
+use mqtt_client::{MqttClient, MqttMessage, MqttSettings, PresencePayload, PresenceStatus};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use structs::{
-    DabError, DabResponse, DiscoveryResponse, ErrorResponse, Messages, NotificationLevel,
-    RequestTypes, SharedMap, TelemetryMessage,
+    DabError, DabResponse, DeviceStatus, DiscoveryResponse, ErrorResponse, Messages,
+    NotificationLevel, PresenceState, RequestTypes, SharedMap, TelemetryMessage,
 };
 
+use app_telemetry::AppTelemetry;
 use device_telemetry::DeviceTelemetry;
+use dispatcher::{Dispatcher, Request};
 
-fn call_function(json_str: String, request_type: RequestTypes) -> Result<String, DabError> {
+pub fn call_function(
+    platform: &dyn DabPlatform,
+    json_str: String,
+    request_type: RequestTypes,
+) -> Result<String, DabError> {
     match request_type {
         RequestTypes::OperationsListRequest => {
             let dab_request: structs::OperationsListRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::operations::list::process(dab_request)
+            platform.operations_list(dab_request)
         }
         RequestTypes::ApplicationListRequest => {
             let dab_request: structs::ApplicationListRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::applications::list::process(dab_request)
+            platform.applications_list(dab_request)
         }
         RequestTypes::ApplicationLaunchRequest => {
             let dab_request: structs::LaunchApplicationRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::applications::launch::process(dab_request)
+            platform.applications_launch(dab_request)
         }
         RequestTypes::ApplicationLaunchWithContentRequest => {
             let dab_request: structs::LaunchApplicationWithContentRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::applications::launch_with_content::process(dab_request)
+            platform.applications_launch_with_content(dab_request)
         }
         RequestTypes::ApplicationGetStateRequest => {
             let dab_request: structs::GetApplicationStateRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::applications::get_state::process(dab_request)
+            platform.applications_get_state(dab_request)
         }
         RequestTypes::ApplicationExitRequest => {
             let dab_request: structs::ExitApplicationRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::applications::exit::process(dab_request)
+            platform.applications_exit(dab_request)
         }
         RequestTypes::DeviceInfoRequest => {
             let dab_request: structs::DeviceInfoRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::device::info::process(dab_request)
+            platform.device_info(dab_request)
         }
         RequestTypes::SystemRestartRequest => {
             let dab_request: structs::RestartRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::system::restart::process(dab_request)
+            platform.system_restart(dab_request)
         }
         RequestTypes::SystemSettingsListRequest => {
             let dab_request: structs::ListSystemSettingsRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::system::settings::list::process(dab_request)
+            platform.system_settings_list(dab_request)
         }
         RequestTypes::SystemSettingsGetRequest => {
             let dab_request: structs::GetSystemSettingsRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::system::settings::get::process(dab_request)
+            platform.system_settings_get(dab_request)
         }
         RequestTypes::SystemSettingsSetRequest => {
             let dab_request: structs::SetSystemSettingsRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::system::settings::set::process(dab_request)
+            platform.system_settings_set(dab_request)
         }
         RequestTypes::InputKeyListRequest => {
             let dab_request: structs::KeyListRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::input::key::list::process(dab_request)
+            platform.input_key_list(dab_request)
         }
         RequestTypes::InputKeyPressRequest => {
             let dab_request: structs::KeyPressRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::input::key_press::process(dab_request)
+            platform.input_key_press(dab_request)
         }
         RequestTypes::InputLongKeyPressRequest => {
             let dab_request: structs::LongKeyPressRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::input::long_key_press::process(dab_request)
+            platform.input_long_key_press(dab_request)
         }
         RequestTypes::OutputImageRequest => {
             let dab_request: structs::CaptureScreenshotRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::output::image::process(dab_request)
+            platform.output_image(dab_request)
         }
         RequestTypes::HealthCheckGetRequest => {
             let dab_request: structs::HealthCheckRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::health_check::get::process(dab_request)
+            platform.health_check_get(dab_request)
         }
         RequestTypes::VoiceListRequest => {
             let dab_request: structs::VoiceListRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::voice::list::process(dab_request)
+            platform.voice_list(dab_request)
         }
         RequestTypes::VoiceSetRequest => {
             let dab_request: structs::SetVoiceSystemRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::voice::set::process(dab_request)
+            platform.voice_set(dab_request)
         }
         RequestTypes::VoiceSendAudioRequest => {
             let dab_request: structs::SendAudioRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::voice::send_audio::process(dab_request)
+            platform.voice_send_audio(dab_request)
         }
         RequestTypes::VoiceSendTextRequest => {
             let dab_request: structs::SendTextRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::voice::send_text::process(dab_request)
+            platform.voice_send_text(dab_request)
         }
         RequestTypes::VersionRequest => {
             let dab_request: structs::VersionRequest =
                 serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
-            hw_specific::version::process(dab_request)
+            platform.version(dab_request)
+        }
+        #[cfg(feature = "2_1")]
+        RequestTypes::ApplicationInstallRequest => {
+            let dab_request: structs::InstallApplicationRequest =
+                serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
+            platform.applications_install(dab_request)
+        }
+        #[cfg(feature = "2_1")]
+        RequestTypes::ApplicationUninstallRequest => {
+            let dab_request: structs::UninstallApplicationRequest =
+                serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
+            platform.applications_uninstall(dab_request)
+        }
+        #[cfg(feature = "2_1")]
+        RequestTypes::ApplicationClearDataRequest => {
+            let dab_request: structs::ClearApplicationDataRequest =
+                serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
+            platform.applications_clear_data(dab_request)
+        }
+        #[cfg(feature = "2_1")]
+        RequestTypes::SystemPowerModeGetRequest => {
+            let dab_request: structs::GetPowerModeRequest =
+                serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
+            platform.system_power_mode_get(dab_request)
+        }
+        #[cfg(feature = "2_1")]
+        RequestTypes::SystemPowerModeSetRequest => {
+            let dab_request: structs::SetPowerModeRequest =
+                serde_json::from_str(&json_str).map_err(|e| DabError::Err400(e.to_string()))?;
+            platform.system_power_mode_set(dab_request)
         }
     }
 }
 
-pub fn run(mqtt_server: String, mqtt_port: u16, mut function_map: SharedMap) {
+fn get_status_payload(
+    platform: &dyn DabPlatform,
+    status: PresenceState,
+    device_id: &str,
+    ip_address: &str,
+) -> String {
+    serde_json::to_string(&DeviceStatus {
+        status,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        deviceId: device_id.to_string(),
+        ip: ip_address.to_string(),
+        adapterVersion: env!("VERGEN_BUILD_SEMVER").to_string(),
+        dabVersions: platform.get_supported_versions(),
+    })
+    .unwrap()
+}
+
+fn publish_message(
+    mqtt_client: &MqttClient,
+    device_id: &str,
+    ip_address: &str,
+    level: NotificationLevel,
+    message: String,
+) {
+    let payload = serde_json::to_string(&Messages {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        level,
+        ip: ip_address.to_string(),
+        message,
+    })
+    .unwrap();
+
+    mqtt_client.publish(MqttMessage {
+        function_topic: "dab/".to_string() + device_id + "/messages",
+        response_topic: "".to_string(),
+        correlation_data: vec![0],
+        payload,
+    });
+}
+
+pub fn run(
+    platform: Box<dyn DabPlatform>,
+    mqtt_settings: MqttSettings,
+    mut function_map: SharedMap,
+    request_timeout: Duration,
+    shutdown: Receiver<()>,
+) {
+    let platform: Arc<dyn DabPlatform> = Arc::from(platform);
+
     // Get the device ID
-    let device_id = match hw_specific::interface::get_device_id() {
+    let device_id = match platform.get_device_id() {
         Ok(id) => id,
         Err(_) => {
-            println!("RDK: Error getting device ID");
+            println!("Error getting device ID");
             // Without a valid Device ID; DAB functionality cannot progress.
             // Exit now so that systemd can restart it again.
             std::process::exit(0x00);
@@ -135,42 +230,77 @@ pub fn run(mqtt_server: String, mqtt_port: u16, mut function_map: SharedMap) {
     };
     println!("DAB Device ID: {}", device_id);
 
-    // Connect to the MQTT broker
-    let mut mqtt_client = MqttClient::new(mqtt_server, mqtt_port);
+    let ip_address = platform.get_ip_address();
+
+    // Connect to the MQTT broker, registering the retained presence records of the device
+    let status_payload = |status: PresenceState| -> PresencePayload {
+        let platform = platform.clone();
+        let device_id = device_id.clone();
+        let ip_address = ip_address.clone();
+        Arc::new(move || get_status_payload(platform.as_ref(), status, &device_id, &ip_address))
+    };
+    let presence = PresenceStatus {
+        topic: "dab/".to_string() + &device_id + "/status",
+        online: status_payload(PresenceState::online),
+        offline: status_payload(PresenceState::offline),
+    };
+
+    // On graceful shutdown, report the device as offline before exiting. The
+    // connection may still be retried meanwhile; there is nothing to report then.
+    let connected: Arc<Mutex<Option<MqttClient>>> = Arc::new(Mutex::new(None));
+    let mqtt_client_shutdown = connected.clone();
+    thread::spawn(move || {
+        let _ = shutdown.recv();
+        println!("Shutting down.");
+        if let Some(mqtt_client) = mqtt_client_shutdown.lock().unwrap().as_ref() {
+            mqtt_client.disconnect();
+        }
+        std::process::exit(0x00);
+    });
+
+    let mut mqtt_client = MqttClient::new(mqtt_settings, presence);
     mqtt_client.start();
+    *connected.lock().unwrap() = Some(mqtt_client.clone());
+
     // subscribe to all topics starting with `dab/<device-id>/`
     mqtt_client.subscribe("dab/".to_string() + &device_id + "/#");
     mqtt_client.subscribe("dab/discovery".to_string());
 
     // Broadcast a message to dab/<device-id>/messages topic:
+    publish_message(
+        &mqtt_client,
+        &device_id,
+        &ip_address,
+        NotificationLevel::info,
+        "DAB started successfully".to_string(),
+    );
 
-    let now = SystemTime::now();
-    let unix_time = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
-
-    let ip_address = hw_specific::interface::get_ip_address();
-
-    let payload = serde_json::to_string(&Messages {
-        timestamp: unix_time,
-        level: NotificationLevel::info,
-        ip: ip_address.clone(),
-        message: "DAB started successfully".to_string(),
-    })
-    .unwrap();
-
-    let mut zero_vector: Vec<u8> = Vec::new();
-    zero_vector.push(0);
-
-    let msg_tx = MqttMessage {
-        function_topic: "dab/".to_string() + &device_id.clone() + "/messages",
-        response_topic: "".to_string(),
-        correlation_data: zero_vector,
-        payload: payload.clone(),
-    };
-    mqtt_client.publish(msg_tx);
+    let (notifier_client, notifier_device_id, notifier_ip) =
+        (mqtt_client.clone(), device_id.clone(), ip_address.clone());
+    platform.set_notifier(Arc::new(move |level, message| {
+        publish_message(&notifier_client, &notifier_device_id, &notifier_ip, level, message)
+    }));
 
     // Start the device telemetry thread
     let mqtt_client_telemetry = mqtt_client.clone();
-    let mut device_telemetry = DeviceTelemetry::new(mqtt_client_telemetry, device_id.clone());
+    let mut device_telemetry = DeviceTelemetry::new(mqtt_client_telemetry, device_id.clone(), platform.clone());
+    let mut app_telemetry = AppTelemetry::new(mqtt_client.clone(), device_id.clone(), platform.clone());
+
+    // Requests handled by the platform are processed concurrently by the dispatcher
+    let (responder_client, responder_device_id) = (mqtt_client.clone(), device_id.clone());
+    let dispatcher = Dispatcher::new(
+        platform.clone(),
+        Arc::new(move |request: Request, response| {
+            publish_response(
+                &responder_client,
+                &responder_device_id,
+                request.response_topic,
+                request.correlation_data,
+                response,
+            )
+        }),
+        request_timeout,
+    );
 
     // Infinite loop
     loop {
@@ -204,10 +334,17 @@ pub fn run(mqtt_server: String, mqtt_port: u16, mut function_map: SharedMap) {
                     }
 
                     match function_map.get_mut(&operation) {
-                        // If we get the proper handler, then call it
+                        // If we get the proper handler, then dispatch it; the dispatcher publishes the response
                         Some(request_type) => {
                             println!("processing: {}", operation);
-                            call_function(payload.clone(), request_type.clone())
+                            dispatcher.submit(Request {
+                                operation,
+                                request_type: request_type.clone(),
+                                payload,
+                                response_topic,
+                                correlation_data,
+                            });
+                            continue;
                         }
                         // If we can't get the proper handler, then this is a telemetry operation or is not implemented
                         _ => {
@@ -232,6 +369,26 @@ pub fn run(mqtt_server: String, mqtt_port: u16, mut function_map: SharedMap) {
                                     Ok(r) => device_telemetry.device_telemetry_stop_process(r),
                                     Err(e) => Err(e),
                                 }
+                            } else if &operation == "app-telemetry/start" {
+                                let dab_request: Result<
+                                    structs::StartApplicationTelemetryRequest,
+                                    DabError,
+                                > = serde_json::from_str(&payload.clone())
+                                    .map_err(|e| DabError::Err400(e.to_string()));
+                                match dab_request {
+                                    Ok(r) => app_telemetry.app_telemetry_start_process(r),
+                                    Err(e) => Err(e),
+                                }
+                            } else if &operation == "app-telemetry/stop" {
+                                let dab_request: Result<
+                                    structs::StopApplicationTelemetryRequest,
+                                    DabError,
+                                > = serde_json::from_str(&payload.clone())
+                                    .map_err(|e| DabError::Err400(e.to_string()));
+                                match dab_request {
+                                    Ok(r) => app_telemetry.app_telemetry_stop_process(r),
+                                    Err(e) => Err(e),
+                                }
                             } else {
                                 println!("ERROR: {}", operation);
                                 Err(DabError::Err501(operation + " operator not implemented"))
@@ -240,70 +397,13 @@ pub fn run(mqtt_server: String, mqtt_port: u16, mut function_map: SharedMap) {
                     }
                 };
 
-                let payload = match response {
-                    Ok(r) => {
-                        // The request was successful.
-                        let template = DabResponse { status: 200 };
-                        let dab_json =
-                            serde_json::to_value(template).expect("Error serializing DabResponse");
-                        // Parse the JSON string
-                        let mut dab_response: Value =
-                            serde_json::from_str(&r).expect("Error parsing JSON string");
-                        if dab_response.is_object() {
-                            for (key, value) in dab_json.as_object().unwrap() {
-                                dab_response[key] = value.clone();
-                            }
-                        }
-                        dab_response.to_string()
-                    }
-                    Err(e) => match e {
-                        DabError::Err400(msg) => {
-                            // The request was not successful.
-                            serde_json::to_string(&ErrorResponse {
-                                // Bad request. The explanation of the error
-                                // must be included in the error field of the response.
-                                status: 400,
-                                error: msg,
-                            })
-                            .unwrap()
-                        }
-                        DabError::Err500(msg) => {
-                            // The request was not successful.
-                            serde_json::to_string(&ErrorResponse {
-                                // Internal error. The explanation of the error
-                                // must be included in the error field of the response.
-                                status: 500,
-                                error: msg,
-                            })
-                            .unwrap()
-                        }
-                        DabError::Err501(msg) => {
-                            // The request was not successful.
-                            serde_json::to_string(&ErrorResponse {
-                                // Internal error. The explanation of the error
-                                // must be included in the error field of the response.
-                                status: 501,
-                                error: msg,
-                            })
-                            .unwrap()
-                        }
-                    },
-                };
-
-                let msg_tx = MqttMessage {
-                    function_topic: response_topic.clone(),
-                    response_topic: "".to_string(),
-                    correlation_data: correlation_data.clone(),
-                    payload: payload.clone(),
-                };
-                // Publish the response
-                mqtt_client.publish(msg_tx);
-                let limited_payload = if cfg!(debug_assertions) {
-                    payload.clone()
-                } else {
-                    payload.chars().take(255).collect::<String>()
-                };
-                println!("Publishing response: {} {}\n", response_topic.clone().replace(&substring, ""), limited_payload.as_str());
+                publish_response(
+                    &mqtt_client,
+                    &device_id,
+                    response_topic,
+                    correlation_data,
+                    response,
+                );
             }
             Err(err) => {
                 if let Some(msg) = err {
@@ -313,3 +413,80 @@ pub fn run(mqtt_server: String, mqtt_port: u16, mut function_map: SharedMap) {
         }
     }
 }
+
+fn encode_response(response: Result<String, DabError>) -> String {
+    match response {
+        Ok(r) => {
+            // The request was successful.
+            let template = DabResponse { status: 200 };
+            let dab_json = serde_json::to_value(template).expect("Error serializing DabResponse");
+            // Parse the JSON string
+            let mut dab_response: Value =
+                serde_json::from_str(&r).expect("Error parsing JSON string");
+            if dab_response.is_object() {
+                for (key, value) in dab_json.as_object().unwrap() {
+                    dab_response[key] = value.clone();
+                }
+            }
+            dab_response.to_string()
+        }
+        Err(e) => match e {
+            DabError::Err400(msg) => {
+                // The request was not successful.
+                serde_json::to_string(&ErrorResponse {
+                    // Bad request. The explanation of the error
+                    // must be included in the error field of the response.
+                    status: 400,
+                    error: msg,
+                })
+                .unwrap()
+            }
+            DabError::Err500(msg) => {
+                // The request was not successful.
+                serde_json::to_string(&ErrorResponse {
+                    // Internal error. The explanation of the error
+                    // must be included in the error field of the response.
+                    status: 500,
+                    error: msg,
+                })
+                .unwrap()
+            }
+            DabError::Err501(msg) => {
+                // The request was not successful.
+                serde_json::to_string(&ErrorResponse {
+                    // Internal error. The explanation of the error
+                    // must be included in the error field of the response.
+                    status: 501,
+                    error: msg,
+                })
+                .unwrap()
+            }
+        },
+    }
+}
+
+fn publish_response(
+    mqtt_client: &MqttClient,
+    device_id: &str,
+    response_topic: String,
+    correlation_data: Vec<u8>,
+    response: Result<String, DabError>,
+) {
+    let payload = encode_response(response);
+
+    let msg_tx = MqttMessage {
+        function_topic: response_topic.clone(),
+        response_topic: "".to_string(),
+        correlation_data,
+        payload: payload.clone(),
+    };
+    // Publish the response
+    mqtt_client.publish(msg_tx);
+    let limited_payload = if cfg!(debug_assertions) {
+        payload.clone()
+    } else {
+        payload.chars().take(255).collect::<String>()
+    };
+    let substring = "dab/".to_owned() + device_id + "/";
+    println!("Publishing response: {} {}\n", response_topic.replace(&substring, ""), limited_payload.as_str());
+}
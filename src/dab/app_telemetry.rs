@@ -0,0 +1,184 @@
+use crate::dab::device_telemetry::{Session, Sessions, ThreadContext};
+use crate::dab::structs::DabError;
+use crate::dab::structs::StartApplicationTelemetryRequest;
+use crate::dab::structs::StartApplicationTelemetryResponse;
+use crate::dab::structs::StopApplicationTelemetryRequest;
+use crate::dab::structs::StopApplicationTelemetryResponse;
+use crate::dab::{mqtt_client::MqttMessage, MqttClient, TelemetryMessage};
+use crate::device::DabPlatform;
+use serde::Serialize;
+
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const STOPPED: &str = "STOPPED";
+// How long a session waits for its application to start.
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Telemetry sessions of the applications, each publishing the metrics of one
+// application until it is stopped or the application exits.
+pub struct AppTelemetry {
+    sessions: Sessions,
+    mqtt_client: MqttClient,
+    device_id: String,
+    platform: Arc<dyn DabPlatform>,
+}
+
+impl AppTelemetry {
+    pub fn new(
+        mqtt_client: MqttClient,
+        device_id: String,
+        platform: Arc<dyn DabPlatform>,
+    ) -> AppTelemetry {
+        AppTelemetry {
+            sessions: Sessions::default(),
+            mqtt_client,
+            device_id,
+            platform,
+        }
+    }
+
+    pub fn start(&mut self, app_id: &str, period: u64) {
+        let context = Arc::new(ThreadContext {
+            mutex: Mutex::new(true),
+            cond: Condvar::new(),
+        });
+        let thread_context = context.clone();
+        let session_app_id = app_id.to_string();
+        let topic = format!("dab/{}/app-telemetry/metrics/{}", self.device_id, app_id);
+        let mqtt_client = self.mqtt_client.clone();
+        let platform = self.platform.clone();
+
+        thread::spawn(move || {
+            let app_id = session_app_id;
+            let ThreadContext { mutex, cond } = &*thread_context;
+            let publish = |payload: String| {
+                mqtt_client.publish(MqttMessage {
+                    function_topic: topic.clone(),
+                    response_topic: "".to_string(),
+                    correlation_data: vec![0],
+                    payload,
+                });
+            };
+            let started = Instant::now();
+            let mut running = false;
+
+            loop {
+                // A state that can't be read skips the sample; it doesn't mean the
+                // application exited.
+                match platform.get_app_state(&app_id) {
+                    Ok(state) => {
+                        let mut metrics = vec![];
+                        if state != STOPPED {
+                            running = true;
+                            metrics = vec![
+                                ("memory", platform.get_app_memory(&app_id)),
+                                ("cpu", platform.get_app_cpu(&app_id)),
+                            ];
+                        }
+                        if !thread_context.enabled() {
+                            break;
+                        }
+                        for (metric_name, metric_value) in metrics {
+                            // A metric that can't be read is left out.
+                            if let Ok(value) = metric_value {
+                                publish(get_telemetry_payload(metric_name, value as f64));
+                            }
+                        }
+                        publish(get_telemetry_payload("state", state.clone()));
+
+                        // The session ends with the application.
+                        if running && state == STOPPED {
+                            println!("{} exited; stopping its telemetry.", app_id);
+                            break;
+                        }
+                    }
+                    Err(e) => println!("Error getting the state of {}: {:?}", app_id, e),
+                }
+
+                if !running && started.elapsed() >= START_TIMEOUT {
+                    println!("{} didn't start; stopping its telemetry.", app_id);
+                    break;
+                }
+
+                let (_enabled, wait) = cond
+                    .wait_timeout_while(mutex.lock().unwrap(), Duration::from_millis(period), |&mut enabled| enabled)
+                    .unwrap();
+                if !wait.timed_out() {
+                    break;
+                }
+            }
+        });
+
+        // A session already running for the application is stopped.
+        self.sessions.start(app_id, Session { context });
+    }
+
+    // Returns whether the application had a session, including one that already
+    // ended with the application.
+    pub fn stop(&mut self, app_id: &str) -> bool {
+        self.sessions.stop(app_id)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn app_telemetry_start_process(
+        &mut self,
+        _dab_request: StartApplicationTelemetryRequest,
+    ) -> Result<String, DabError> {
+        let mut ResponseOperator = StartApplicationTelemetryResponse::default();
+        if _dab_request.appId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'appId' parameter".to_string(),
+            ));
+        }
+        if _dab_request.duration == 0 {
+            return Err(DabError::Err400(
+                "request missing 'duration' parameter".to_string(),
+            ));
+        }
+
+        self.start(&_dab_request.appId, _dab_request.duration);
+
+        ResponseOperator.duration = _dab_request.duration;
+
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+
+    #[allow(non_snake_case)]
+    pub fn app_telemetry_stop_process(
+        &mut self,
+        _dab_request: StopApplicationTelemetryRequest,
+    ) -> Result<String, DabError> {
+        let ResponseOperator = StopApplicationTelemetryResponse::default();
+        if _dab_request.appId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'appId' parameter".to_string(),
+            ));
+        }
+
+        if !self.stop(&_dab_request.appId) {
+            return Err(DabError::Err400(format!(
+                "App telemetry of {} is not started",
+                _dab_request.appId
+            )));
+        }
+
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+}
+
+fn get_telemetry_payload<T: Serialize>(metric: &str, value: T) -> String {
+    let message = TelemetryMessage {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64,
+        metric: metric.to_string(),
+        value,
+        sessionId: None,
+    };
+    serde_json::to_string(&message).unwrap()
+}
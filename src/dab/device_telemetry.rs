@@ -4,73 +4,184 @@ use crate::dab::structs::StartDeviceTelemetryResponse;
 use crate::dab::structs::StopDeviceTelemetryRequest;
 use crate::dab::structs::StopDeviceTelemetryResponse;
 use crate::dab::{mqtt_client::MqttMessage, MqttClient, TelemetryMessage};
-use crate::hw_specific::interface::get_device_memory;
-use crate::hw_specific::interface::get_device_cpu;
+use crate::device::DabPlatform;
 
 use std::{
-    sync::{Arc, Mutex, Condvar},
+    collections::HashMap,
+    sync::{Arc, Mutex, Condvar, PoisonError},
     thread,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+#[cfg(test)]
+mod tests;
+
+// Sessions sampling within this window share the same metrics.
+const SAMPLE_SHARING_WINDOW: Duration = Duration::from_millis(100);
+
 #[allow(dead_code)]
-struct ThreadContext {
-    mutex: Mutex<bool>,
-    cond: Condvar,
+pub(super) struct ThreadContext {
+    pub(super) mutex: Mutex<bool>,
+    pub(super) cond: Condvar,
 }
 
-#[allow(dead_code)]
+impl ThreadContext {
+    // Whether the session is still running; checked before publishing, as a stopped
+    // session may still be reading the device.
+    pub(super) fn enabled(&self) -> bool {
+        *self.mutex.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// A telemetry thread, running until it is stopped or ends on its own.
+pub(super) struct Session {
+    pub(super) context: Arc<ThreadContext>,
+}
+
+impl Session {
+    // Signals the thread without waiting for it to end, so a thread blocked on the
+    // device doesn't hold up the requests received meanwhile.
+    pub(super) fn stop(self) {
+        let mut enabled = self.context.mutex.lock().unwrap_or_else(PoisonError::into_inner);
+        *enabled = false;
+        self.context.cond.notify_one();
+    }
+}
+
+// Telemetry sessions, by name. A session that ended on its own is kept until it is
+// stopped or started again, so stopping it doesn't depend on the other sessions.
+#[derive(Default)]
+pub(super) struct Sessions {
+    sessions: HashMap<String, Session>,
+}
+
+impl Sessions {
+    // Replaces the session of that name, stopping the previous one.
+    pub(super) fn start(&mut self, name: &str, session: Session) {
+        if let Some(previous) = self.sessions.insert(name.to_string(), session) {
+            previous.stop();
+        }
+    }
+
+    // Returns whether the session existed, running or ended.
+    pub(super) fn stop(&mut self, name: &str) -> bool {
+        match self.sessions.remove(name) {
+            Some(session) => {
+                session.stop();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Device metrics, by name.
+type Metrics = Arc<Vec<(String, f64)>>;
+
+// Samples the device metrics for all the sessions. The sessions tick on multiples
+// of their period from the same epoch, so the ones due at the same time share a
+// sample instead of querying the device again.
+pub struct MetricSampler {
+    epoch: Instant,
+    last: Mutex<Option<(Instant, Metrics)>>,
+}
+
+impl MetricSampler {
+    pub fn new() -> MetricSampler {
+        MetricSampler {
+            epoch: Instant::now(),
+            last: Mutex::new(None),
+        }
+    }
+
+    pub fn sample(&self, platform: &dyn DabPlatform) -> Metrics {
+        // Sessions due at the same time wait for the sample of the first one.
+        let mut last = self.last.lock().unwrap();
+        if let Some((sampled_at, metrics)) = last.as_ref() {
+            if sampled_at.elapsed() < SAMPLE_SHARING_WINDOW {
+                return metrics.clone();
+            }
+        }
+        let metrics = Arc::new(platform.get_device_metrics());
+        *last = Some((Instant::now(), metrics.clone()));
+        metrics
+    }
+
+    // Time until the next tick of the period, skipping the ones closer than half a period.
+    pub fn next_tick(&self, period: u64) -> Duration {
+        let elapsed = self.epoch.elapsed().as_millis() as u64;
+        let mut wait = period - elapsed % period;
+        if wait < period / 2 {
+            wait += period;
+        }
+        Duration::from_millis(wait)
+    }
+}
+
+// Whether the metric is one of the requested ones. Collectors of several metrics
+// are requested by their name, e.g. "network" for "network_rx_eth0".
+fn is_requested(metric: &str, requested: &[String]) -> bool {
+    requested.is_empty()
+        || requested.iter().any(|name| {
+            metric == name || metric.strip_prefix(name.as_str()).is_some_and(|rest| rest.starts_with('_'))
+        })
+}
+
+// Device telemetry sessions, by sessionId; the default session has an empty one.
 pub struct DeviceTelemetry {
-    handle: Option<thread::JoinHandle<()>>,
-    context: Arc<ThreadContext>,
+    sessions: Sessions,
+    sampler: Arc<MetricSampler>,
     mqtt_client: MqttClient,
     device_id: String,
+    platform: Arc<dyn DabPlatform>,
 }
 
-#[allow(dead_code)]
 impl DeviceTelemetry {
-    pub fn new(mqtt_client: MqttClient, device_id: String) -> DeviceTelemetry {
+    pub fn new(
+        mqtt_client: MqttClient,
+        device_id: String,
+        platform: Arc<dyn DabPlatform>,
+    ) -> DeviceTelemetry {
         DeviceTelemetry {
-            handle: None,
-            context: Arc::new(ThreadContext {
-                mutex: Mutex::new(false),
-                cond: Condvar::new()
-            }),
-            mqtt_client: mqtt_client,
-            device_id: device_id,
+            sessions: Sessions::default(),
+            sampler: Arc::new(MetricSampler::new()),
+            mqtt_client,
+            device_id,
+            platform,
         }
     }
 
-    pub fn start<'a>(&'a mut self, period: u64) {
-        // If it is already running, stop the instance before creating a new one
-        self.stop();
-
-        // Start the telemetry thread
-        let mut enabled = self.context.mutex.lock().unwrap();
-        assert!(*enabled == false);
-        *enabled = true;
-        let context = self.context.clone();
-        let device_id = self.device_id.clone();
+    pub fn start(&mut self, session_id: &str, period: u64, metrics: Vec<String>, time_to_live: Option<u64>) {
+        let context = Arc::new(ThreadContext {
+            mutex: Mutex::new(true),
+            cond: Condvar::new(),
+        });
+        let thread_context = context.clone();
+        let name = Some(session_id.to_string()).filter(|name| !name.is_empty());
+        let topic = format!("dab/{}/device-telemetry/metrics", self.device_id);
+        let sampler = self.sampler.clone();
         let mqtt_client = self.mqtt_client.clone();
+        let platform = self.platform.clone();
+        let expiry = time_to_live.map(|time_to_live| Instant::now() + Duration::from_millis(time_to_live));
 
-        self.handle = Some(thread::spawn(move || {
-            let ThreadContext {mutex, cond} = &*context;
+        thread::spawn(move || {
+            let ThreadContext {mutex, cond} = &*thread_context;
 
             loop {
-                let metrics = [("memory", get_device_memory()), ("cpu", get_device_cpu())];
-
                 let zero_vector = vec![0];
 
-                for (metric_name, metric_value) in &metrics {
-                    let value = match metric_value {
-                        Ok(val) => *val,
-                        _ => 0,
-                    };
-
-                    let payload = Self::get_telemetry_payload(metric_name, value).unwrap();
+                let metrics_sampled = sampler.sample(platform.as_ref());
+                if !thread_context.enabled() {
+                    break;
+                }
+                for (metric_name, value) in metrics_sampled.iter() {
+                    if !is_requested(metric_name, &metrics) {
+                        continue;
+                    }
+                    let payload = Self::get_telemetry_payload(metric_name, *value, name.clone()).unwrap();
 
                     let msg_tx = MqttMessage {
-                        function_topic: format!("dab/{}/device-telemetry/metrics", device_id),
+                        function_topic: topic.clone(),
                         response_topic: "".to_string(),
                         correlation_data: zero_vector.clone(),
                         payload,
@@ -79,26 +190,30 @@ impl DeviceTelemetry {
                     mqtt_client.publish(msg_tx);
                 }
 
-                if cond.wait_timeout_while(
-                    mutex.lock().unwrap(),
-                    Duration::from_millis(period),
-                    |&mut enabled| enabled
-                ).unwrap().1.timed_out() == false {
+                let mut wait = sampler.next_tick(period);
+                if let Some(expiry) = expiry {
+                    wait = wait.min(expiry.saturating_duration_since(Instant::now()));
+                }
+                let (_enabled, result) = cond
+                    .wait_timeout_while(mutex.lock().unwrap(), wait, |&mut enabled| enabled)
+                    .unwrap();
+                if !result.timed_out() {
+                    break;
+                }
+                if expiry.is_some_and(|expiry| Instant::now() >= expiry) {
+                    println!("Device telemetry session '{}' expired.", name.as_deref().unwrap_or_default());
                     break;
                 }
             }
-        }));
+        });
+
+        // A session already running under that name is stopped.
+        self.sessions.start(session_id, Session { context });
     }
 
-    pub fn stop(&mut self) {
-        // Stop the telemetry thread
-        let mut enabled = self.context.mutex.lock().unwrap();
-        *enabled = false;
-        if let Some(handle) = self.handle.take() {
-            self.context.cond.notify_one();
-            drop(enabled);
-            handle.join().unwrap();
-        }
+    // Returns whether the session existed, including one that already expired.
+    pub fn stop(&mut self, session_id: &str) -> bool {
+        self.sessions.stop(session_id)
     }
 
     #[allow(non_snake_case)]
@@ -107,10 +222,21 @@ impl DeviceTelemetry {
         _dab_request: StartDeviceTelemetryRequest,
     ) -> Result<String, DabError> {
         let mut ResponseOperator = StartDeviceTelemetryResponse::default();
+        if _dab_request.duration == 0 {
+            return Err(DabError::Err400(
+                "request missing 'duration' parameter".to_string(),
+            ));
+        }
 
-        self.start(_dab_request.duration);
+        self.start(
+            _dab_request.sessionId.as_deref().unwrap_or_default(),
+            _dab_request.duration,
+            _dab_request.metrics.unwrap_or_default(),
+            _dab_request.timeToLive,
+        );
 
         ResponseOperator.duration = _dab_request.duration;
+        ResponseOperator.sessionId = _dab_request.sessionId;
 
         Ok(serde_json::to_string(&ResponseOperator).unwrap())
     }
@@ -122,19 +248,27 @@ impl DeviceTelemetry {
     ) -> Result<String, DabError> {
         let ResponseOperator = StopDeviceTelemetryResponse::default();
 
-        self.stop();
+        // Stopping the default session when it isn't running is still accepted.
+        let session_id = _dab_request.sessionId.as_deref().unwrap_or_default();
+        if !self.stop(session_id) && !session_id.is_empty() {
+            return Err(DabError::Err400(format!(
+                "Device telemetry session '{}' is not started",
+                session_id
+            )));
+        }
 
         Ok(serde_json::to_string(&ResponseOperator).unwrap())
     }
 
-    fn get_telemetry_payload(metric: &str, value: u32) -> Result<String, serde_json::Error> {
+    fn get_telemetry_payload(metric: &str, value: f64, session_id: Option<String>) -> Result<String, serde_json::Error> {
         let message = TelemetryMessage {
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
-                .as_secs(),
+                .as_millis() as u64,
             metric: metric.to_string(),
             value,
+            sessionId: session_id,
         };
         serde_json::to_string(&message)
     }
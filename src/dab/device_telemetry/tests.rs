@@ -0,0 +1,66 @@
+use super::{Session, Sessions, ThreadContext};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// A session whose thread runs until it is stopped, or ends right away.
+fn session(running: bool) -> (Session, Arc<ThreadContext>) {
+    let context = Arc::new(ThreadContext {
+        mutex: Mutex::new(true),
+        cond: Condvar::new(),
+    });
+    let thread_context = context.clone();
+    let handle = thread::spawn(move || {
+        let ThreadContext { mutex, cond } = &*thread_context;
+        if running {
+            let _enabled = cond.wait_while(mutex.lock().unwrap(), |&mut enabled| enabled).unwrap();
+        }
+    });
+    while !running && !handle.is_finished() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    (Session { context: context.clone() }, context)
+}
+
+#[test]
+fn ended_sessions_can_be_stopped_after_another_starts() {
+    let mut sessions = Sessions::default();
+    let (ended, _) = session(false);
+    sessions.start("Netflix", ended);
+    let (running, running_context) = session(true);
+    sessions.start("YouTube", running);
+
+    assert!(sessions.stop("Netflix"));
+    assert!(!sessions.stop("Netflix"));
+    assert!(running_context.enabled());
+    assert!(sessions.stop("YouTube"));
+    assert!(!running_context.enabled());
+}
+
+#[test]
+fn expired_sessions_are_kept_until_stopped_or_restarted() {
+    let mut sessions = Sessions::default();
+    let (expired, _) = session(false);
+    sessions.start("expired", expired);
+    let (other, _) = session(true);
+    sessions.start("other", other);
+    let (default, _) = session(true);
+    sessions.start("", default);
+    assert!(sessions.stop("expired"));
+
+    // Starting a session again replaces it, running or expired.
+    let (expired, _) = session(false);
+    sessions.start("restarted", expired);
+    let (first, first_context) = session(true);
+    sessions.start("restarted", first);
+    let (second, second_context) = session(true);
+    sessions.start("restarted", second);
+    assert!(!first_context.enabled());
+    assert!(second_context.enabled());
+    assert!(sessions.stop("restarted"));
+    assert!(!sessions.stop("restarted"));
+    assert!(!second_context.enabled());
+
+    assert!(sessions.stop("other"));
+    assert!(sessions.stop(""));
+}
@@ -0,0 +1,319 @@
+use super::call_function;
+use super::structs::{DabError, RequestTypes};
+use crate::device::DabPlatform;
+use crossbeam::channel::{self, RecvTimeoutError};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+// Number of requests processed at the same time.
+const WORKER_THREADS: usize = 4;
+// Handler threads alive at the same time, counting the ones still running after
+// their request timed out.
+const MAX_HANDLER_THREADS: usize = 8;
+
+pub struct Request {
+    pub operation: String,
+    pub request_type: RequestTypes,
+    pub payload: String,
+    pub response_topic: String,
+    pub correlation_data: Vec<u8>,
+}
+
+// Answers a request; called once for each submitted request.
+pub type Responder = Arc<dyn Fn(Request, Result<String, DabError>) + Send + Sync>;
+
+struct Job {
+    request: Request,
+    lane: Option<String>,
+    // Counted from the submission, queued time included.
+    timeout: Duration,
+    deadline: Instant,
+}
+
+// Requests sharing a lane conflict with each other and run one at a time, in
+// the order they were received. A lane is present in the map while one of its
+// requests is running; the queue holds the ones waiting for it. The requests
+// free to run wait in `ready` for a worker and a handler thread.
+#[derive(Default)]
+struct Queue {
+    ready: VecDeque<Job>,
+    lanes: HashMap<String, VecDeque<Job>>,
+    handlers: usize,
+}
+
+impl Queue {
+    // Hands the lane over to the next waiting request, or frees it.
+    fn release(&mut self, lane: &str) {
+        match self.lanes.get_mut(lane).and_then(|waiting| waiting.pop_front()) {
+            Some(job) => self.ready.push_back(job),
+            None => {
+                self.lanes.remove(lane);
+            }
+        }
+    }
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+    platform: Arc<dyn DabPlatform>,
+    responder: Responder,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        match self.queue.lock() {
+            Ok(queue) => queue,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn wait<'a>(&self, queue: MutexGuard<'a, Queue>, timeout: Option<Duration>) -> MutexGuard<'a, Queue> {
+        match timeout {
+            Some(timeout) => match self.changed.wait_timeout(queue, timeout) {
+                Ok((queue, _)) => queue,
+                Err(poisoned) => poisoned.into_inner().0,
+            },
+            None => match self.changed.wait(queue) {
+                Ok(queue) => queue,
+                Err(poisoned) => poisoned.into_inner(),
+            },
+        }
+    }
+
+    fn time_out(&self, job: Job) {
+        let error = timeout_error(&job.request.operation, job.timeout);
+        (self.responder)(job.request, Err(error));
+    }
+}
+
+pub struct Dispatcher {
+    shared: Arc<Shared>,
+    request_timeout: Duration,
+}
+
+impl Dispatcher {
+    pub fn new(platform: Arc<dyn DabPlatform>, responder: Responder, request_timeout: Duration) -> Dispatcher {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            changed: Condvar::new(),
+            platform,
+            responder,
+        });
+
+        for idx in 0..WORKER_THREADS {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("DabWorker{}", idx))
+                .spawn(move || worker(shared))
+                .unwrap();
+        }
+        let deadlines = shared.clone();
+        thread::Builder::new()
+            .name("DabDeadlines".to_string())
+            .spawn(move || expire_waiting(deadlines))
+            .unwrap();
+
+        Dispatcher {
+            shared,
+            request_timeout,
+        }
+    }
+
+    pub fn submit(&self, request: Request) {
+        let lane = get_lane(&*self.shared.platform, &request.operation, &request.payload);
+        let timeout = get_deadline(&request, self.request_timeout);
+        let job = Job {
+            request,
+            lane,
+            timeout,
+            deadline: Instant::now() + timeout,
+        };
+
+        let mut queue = self.shared.lock();
+        match &job.lane {
+            // A conflicting request is running; wait for it.
+            Some(lane) if queue.lanes.contains_key(lane) => queue.lanes.get_mut(lane).unwrap().push_back(job),
+            Some(lane) => {
+                queue.lanes.insert(lane.clone(), VecDeque::new());
+                queue.ready.push_back(job);
+            }
+            None => queue.ready.push_back(job),
+        }
+        self.shared.changed.notify_all();
+    }
+}
+
+// App lifecycle and package operations on the same appId, input injection, screen
+// capture, voice, power mode and system settings set requests must not overlap.
+fn get_lane(platform: &dyn DabPlatform, operation: &str, payload: &str) -> Option<String> {
+    match operation {
+        "applications/launch"
+        | "applications/launch-with-content"
+        | "applications/exit"
+        | "applications/install"
+        | "applications/uninstall"
+        | "applications/clear-data" => {
+            let app_id = serde_json::from_str::<Value>(payload)
+                .ok()
+                .and_then(|request| request["appId"].as_str().map(|id| platform.resolve_app_id(id)))
+                .unwrap_or_default()
+                .to_lowercase();
+            Some(format!("applications/{}", app_id))
+        }
+        "input/key-press" | "input/long-key-press" => Some("input".to_string()),
+        "output/image" => Some("output/image".to_string()),
+        "voice/send-audio" | "voice/send-text" => Some("voice".to_string()),
+        "system/power-mode/set" => Some("system/power-mode".to_string()),
+        // A set may roll back the settings it applied, so sets don't interleave.
+        "system/settings/set" => Some("system/settings".to_string()),
+        _ => None,
+    }
+}
+
+// Installing an application includes downloading its package.
+#[cfg(feature = "2_1")]
+const INSTALL_TIMEOUT: Duration = Duration::from_secs(600);
+
+// A long key press lasts as long as requested, on top of the regular deadline.
+fn get_deadline(request: &Request, request_timeout: Duration) -> Duration {
+    match request.request_type {
+        RequestTypes::InputLongKeyPressRequest => {
+            let duration_ms = serde_json::from_str::<Value>(&request.payload)
+                .ok()
+                .and_then(|request| request["durationMs"].as_u64())
+                .unwrap_or(0);
+            request_timeout + Duration::from_millis(duration_ms)
+        }
+        #[cfg(feature = "2_1")]
+        RequestTypes::ApplicationInstallRequest => request_timeout + INSTALL_TIMEOUT,
+        _ => request_timeout,
+    }
+}
+
+fn worker(shared: Arc<Shared>) {
+    loop {
+        let job = {
+            let mut queue = shared.lock();
+            loop {
+                if queue.handlers < MAX_HANDLER_THREADS {
+                    if let Some(job) = queue.ready.pop_front() {
+                        queue.handlers += 1;
+                        break job;
+                    }
+                }
+                queue = shared.wait(queue, None);
+            }
+        };
+        let Job {
+            mut request,
+            lane,
+            timeout,
+            deadline,
+        } = job;
+        let handler = HandlerGuard {
+            shared: shared.clone(),
+            lane,
+        };
+        if Instant::now() >= deadline {
+            drop(handler);
+            let error = timeout_error(&request.operation, timeout);
+            (shared.responder)(request, Err(error));
+            continue;
+        }
+
+        // The handler runs on its own thread, so an overrun can be reported without
+        // waiting for it. Its lane stays busy until the handler actually returns.
+        let (result_tx, result_rx) = channel::bounded(1);
+        let payload = std::mem::take(&mut request.payload);
+        let request_type = request.request_type.clone();
+        thread::spawn(move || {
+            // Released on return and also if the handler panics.
+            let handler = handler;
+            let _ = result_tx.send(call_function(handler.shared.platform.as_ref(), payload, request_type));
+        });
+
+        let response = match result_rx.recv_deadline(deadline) {
+            Ok(response) => response,
+            Err(RecvTimeoutError::Timeout) => Err(timeout_error(&request.operation, timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(DabError::Err500(format!(
+                "{} handler terminated unexpectedly",
+                request.operation
+            ))),
+        };
+        (shared.responder)(request, response);
+    }
+}
+
+fn timeout_error(operation: &str, timeout: Duration) -> DabError {
+    DabError::Err500(format!("{} did not complete within {} ms", operation, timeout.as_millis()))
+}
+
+// Answers the requests still waiting for their lane or for a handler thread when
+// their deadline passes.
+fn expire_waiting(shared: Arc<Shared>) {
+    let mut queue = shared.lock();
+    loop {
+        let now = Instant::now();
+        let mut expired = vec![];
+        for waiting in queue.lanes.values_mut() {
+            for job in std::mem::take(waiting) {
+                if job.deadline <= now {
+                    expired.push(job);
+                } else {
+                    waiting.push_back(job);
+                }
+            }
+        }
+        // A ready request may hold its lane.
+        for job in std::mem::take(&mut queue.ready) {
+            if job.deadline > now {
+                queue.ready.push_back(job);
+                continue;
+            }
+            if let Some(lane) = &job.lane {
+                queue.release(lane);
+            }
+            expired.push(job);
+        }
+        if !expired.is_empty() {
+            drop(queue);
+            for job in expired {
+                shared.time_out(job);
+            }
+            queue = shared.lock();
+            continue;
+        }
+
+        let next = queue
+            .ready
+            .iter()
+            .chain(queue.lanes.values().flatten())
+            .map(|job| job.deadline)
+            .min();
+        queue = shared.wait(queue, next.map(|deadline| deadline.saturating_duration_since(now)));
+    }
+}
+
+// Frees the handler thread and hands its lane over to the next waiting request.
+struct HandlerGuard {
+    shared: Arc<Shared>,
+    lane: Option<String>,
+}
+
+impl Drop for HandlerGuard {
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock();
+        queue.handlers -= 1;
+        if let Some(lane) = &self.lane {
+            queue.release(lane);
+        }
+        self.shared.changed.notify_all();
+    }
+}
@@ -0,0 +1,180 @@
+// Runs requests through the dispatcher on a platform whose launches take a while.
+
+use super::{Dispatcher, Request, MAX_HANDLER_THREADS, WORKER_THREADS};
+use crate::dab::structs::{DabError, LaunchApplicationRequest, RequestTypes};
+use crate::device::DabPlatform;
+use crossbeam::channel::{self, Receiver};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Launches last `delay` and record when they start and end, named after their
+// parameters.
+struct SlowPlatform {
+    delay: Duration,
+    events: Mutex<Vec<String>>,
+    // Launches running now, and at most.
+    running: Mutex<(usize, usize)>,
+}
+
+impl SlowPlatform {
+    fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn max_running(&self) -> usize {
+        self.running.lock().unwrap().1
+    }
+}
+
+impl DabPlatform for SlowPlatform {
+    fn get_device_id(&self) -> Result<String, DabError> {
+        Ok("dispatcher-test".to_string())
+    }
+    fn get_ip_address(&self) -> String {
+        "127.0.0.1".to_string()
+    }
+    // Cobalt is another name of YouTube.
+    fn resolve_app_id(&self, app_id: &str) -> String {
+        if app_id.eq_ignore_ascii_case("Cobalt") {
+            "YouTube".to_string()
+        } else {
+            app_id.to_string()
+        }
+    }
+    fn applications_launch(&self, request: LaunchApplicationRequest) -> Result<String, DabError> {
+        let name = request.parameters.unwrap_or_default().join(" ");
+        {
+            let mut running = self.running.lock().unwrap();
+            running.0 += 1;
+            running.1 = running.1.max(running.0);
+        }
+        self.events.lock().unwrap().push(format!("start {}", name));
+        thread::sleep(self.delay);
+        self.events.lock().unwrap().push(format!("end {}", name));
+        self.running.lock().unwrap().0 -= 1;
+        Ok("{}".to_string())
+    }
+}
+
+type Responses = Receiver<(String, Result<String, DabError>)>;
+
+fn start(delay: Duration, request_timeout: Duration) -> (Arc<SlowPlatform>, Dispatcher, Responses) {
+    let platform = Arc::new(SlowPlatform {
+        delay,
+        events: Mutex::new(vec![]),
+        running: Mutex::new((0, 0)),
+    });
+    let (responses_tx, responses) = channel::unbounded();
+    let dispatcher = Dispatcher::new(
+        platform.clone(),
+        Arc::new(move |request: Request, response| {
+            let name = String::from_utf8(request.correlation_data).unwrap();
+            let _ = responses_tx.send((name, response));
+        }),
+        request_timeout,
+    );
+    (platform, dispatcher, responses)
+}
+
+fn launch(dispatcher: &Dispatcher, app_id: &str, name: &str) {
+    dispatcher.submit(Request {
+        operation: "applications/launch".to_string(),
+        request_type: RequestTypes::ApplicationLaunchRequest,
+        payload: json!({"appId": app_id, "parameters": [name]}).to_string(),
+        response_topic: "dab/dispatcher-test/applications/launch".to_string(),
+        correlation_data: name.as_bytes().to_vec(),
+    });
+}
+
+fn assert_timed_out(response: Result<String, DabError>) {
+    match response {
+        Err(DabError::Err500(error)) => assert!(error.contains("did not complete within"), "{}", error),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+#[test]
+fn requests_of_a_lane_run_one_at_a_time_in_order() {
+    let (platform, dispatcher, responses) = start(Duration::from_millis(100), Duration::from_secs(5));
+    for name in ["youtube-1", "youtube-2", "youtube-3"] {
+        launch(&dispatcher, "YouTube", name);
+    }
+    launch(&dispatcher, "Netflix", "netflix-1");
+
+    for _ in 0..4 {
+        let (_, response) = responses.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(response.unwrap(), "{}");
+    }
+    let events = platform.events();
+    let youtube: Vec<&str> = events
+        .iter()
+        .map(|event| event.as_str())
+        .filter(|event| event.contains("youtube"))
+        .collect();
+    assert_eq!(
+        youtube,
+        ["start youtube-1", "end youtube-1", "start youtube-2", "end youtube-2", "start youtube-3", "end youtube-3"]
+    );
+    // Another appId doesn't wait for the YouTube lane.
+    let position = |event: &str| events.iter().position(|recorded| recorded == event).unwrap();
+    assert!(position("start netflix-1") < position("end youtube-1"), "{:?}", events);
+}
+
+#[test]
+fn aliases_of_an_application_share_its_lane() {
+    let (platform, dispatcher, responses) = start(Duration::from_millis(100), Duration::from_secs(5));
+    launch(&dispatcher, "YouTube", "youtube-1");
+    launch(&dispatcher, "Cobalt", "cobalt-1");
+
+    for _ in 0..2 {
+        let (_, response) = responses.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(response.unwrap(), "{}");
+    }
+    assert_eq!(platform.events(), ["start youtube-1", "end youtube-1", "start cobalt-1", "end cobalt-1"]);
+}
+
+#[test]
+fn queued_requests_time_out_from_their_submission() {
+    let (platform, dispatcher, responses) = start(Duration::from_millis(1000), Duration::from_millis(300));
+    let submitted = Instant::now();
+    launch(&dispatcher, "YouTube", "youtube-1");
+    launch(&dispatcher, "YouTube", "youtube-2");
+
+    let mut answered = vec![];
+    for _ in 0..2 {
+        let (name, response) = responses.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_timed_out(response);
+        answered.push(name);
+    }
+    answered.sort();
+    assert_eq!(answered, ["youtube-1", "youtube-2"]);
+    // Both at their deadline, without waiting for the running launch.
+    assert!(submitted.elapsed() < Duration::from_millis(800), "{:?}", submitted.elapsed());
+
+    // The queued launch never runs.
+    thread::sleep(Duration::from_millis(1000));
+    assert_eq!(platform.events(), ["start youtube-1", "end youtube-1"]);
+}
+
+#[test]
+fn handler_threads_are_capped() {
+    let (platform, dispatcher, responses) = start(Duration::from_millis(1500), Duration::from_millis(100));
+    // Each wave of launches times out, leaving its handlers running, until the cap
+    // is reached; the launches of the last wave are answered without running.
+    let waves = MAX_HANDLER_THREADS / WORKER_THREADS + 1;
+    for wave in 0..waves {
+        for idx in 0..WORKER_THREADS {
+            launch(&dispatcher, &format!("app-{}-{}", wave, idx), &format!("{}-{}", wave, idx));
+        }
+        for _ in 0..WORKER_THREADS {
+            let (_, response) = responses.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_timed_out(response);
+        }
+    }
+
+    assert_eq!(platform.max_running(), MAX_HANDLER_THREADS);
+    let started = platform.events().iter().filter(|event| event.starts_with("start")).count();
+    assert_eq!(started, MAX_HANDLER_THREADS);
+}
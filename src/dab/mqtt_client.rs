@@ -1,7 +1,25 @@
-use crossbeam::channel::{self, Receiver, Sender};
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender};
 use paho_mqtt as mqtt;
 use paho_mqtt::properties::PropertyCode;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+// Reconnection backoff: starts at the minimum delay and doubles on every
+// failed attempt until it reaches the maximum.
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+// Upper bound of messages kept while the broker is unreachable; the oldest
+// ones are dropped first.
+const MAX_QUEUED_MESSAGES: usize = 1000;
+// Time given on shutdown to the messages not sent yet.
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct MqttMessage {
@@ -11,40 +29,302 @@ pub struct MqttMessage {
     pub payload: String,
 }
 
+// The broker the adapter connects to, its fallbacks and the security of the
+// connection.
+pub struct MqttSettings {
+    pub broker: String,
+    pub port: u16,
+    // Tried in the given order when the broker is unreachable.
+    pub fallback_brokers: Vec<String>,
+    pub security: MqttSecurity,
+}
+
+// Authentication and transport security of the broker connection. It may be
+// loaded from /etc/dab/mqtt.json and/or given on the command line.
+#[derive(Clone, Default, Deserialize)]
+pub struct MqttSecurity {
+    pub tls: Option<bool>,
+    pub ca_file: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub client_key_password: Option<String>,
+    pub alpn: Option<Vec<String>>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl MqttSecurity {
+    // Values set in `other` take precedence over the ones in `self`.
+    pub fn merge(self, other: MqttSecurity) -> MqttSecurity {
+        MqttSecurity {
+            tls: other.tls.or(self.tls),
+            ca_file: other.ca_file.or(self.ca_file),
+            client_cert: other.client_cert.or(self.client_cert),
+            client_key: other.client_key.or(self.client_key),
+            client_key_password: other.client_key_password.or(self.client_key_password),
+            alpn: other.alpn.or(self.alpn),
+            username: other.username.or(self.username),
+            password: other.password.or(self.password),
+        }
+    }
+
+    // TLS is enabled explicitly or implied by any certificate option.
+    pub fn tls_enabled(&self) -> bool {
+        self.tls.unwrap_or(
+            self.ca_file.is_some() || self.client_cert.is_some() || self.client_key.is_some(),
+        )
+    }
+
+    fn ssl_options(&self) -> mqtt::Result<mqtt::SslOptions> {
+        let mut ssl_opts = mqtt::SslOptionsBuilder::new();
+        if let Some(ca_file) = &self.ca_file {
+            ssl_opts.trust_store(ca_file)?;
+        }
+        if let Some(client_cert) = &self.client_cert {
+            ssl_opts.key_store(client_cert)?;
+        }
+        if let Some(client_key) = &self.client_key {
+            ssl_opts.private_key(client_key)?;
+        }
+        if let Some(client_key_password) = &self.client_key_password {
+            ssl_opts.private_key_password(client_key_password);
+        }
+        if let Some(alpn) = &self.alpn {
+            let protos: Vec<&str> = alpn.iter().map(|p| p.as_str()).collect();
+            ssl_opts.alpn_protos(&protos);
+        }
+        // The certificate must match the host name of the broker URI, which Paho
+        // also sends as SNI.
+        ssl_opts.enable_server_cert_auth(true).verify(true);
+        Ok(ssl_opts.finalize())
+    }
+}
+
+// With TLS, every broker is reached through the TLS variant of its scheme; Paho
+// takes a URI without scheme as plain TCP.
+fn broker_uri(uri: String, use_tls: bool) -> String {
+    if !use_tls {
+        return uri;
+    }
+    match uri.split_once("://") {
+        None => format!("ssl://{}", uri),
+        Some(("tcp" | "mqtt", address)) => format!("ssl://{}", address),
+        Some(("ws", address)) => format!("wss://{}", address),
+        Some(_) => uri,
+    }
+}
+
+// Builds a presence record; called on every use, so its timestamp is current.
+pub type PresencePayload = Arc<dyn Fn() -> String + Send + Sync>;
+
+// Retained presence records of the device. The offline payload is registered
+// as the MQTT Last Will, so the broker publishes it if the adapter vanishes.
+#[derive(Clone)]
+pub struct PresenceStatus {
+    pub topic: String,
+    pub online: PresencePayload,
+    pub offline: PresencePayload,
+}
+
+// Everything needed to connect, but the Last Will: it is rebuilt on every
+// connection with a fresh offline record.
+#[derive(Clone)]
+struct ConnectSettings {
+    server_uris: Vec<String>,
+    ssl_options: Option<mqtt::SslOptions>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+// Messages waiting to be sent, kept while the broker is unreachable.
+#[derive(Default)]
+struct OutgoingQueue {
+    messages: VecDeque<mqtt::Message>,
+}
+
+impl OutgoingQueue {
+    // Once the queue is full, the oldest message is dropped.
+    fn push(&mut self, message: mqtt::Message) {
+        if self.messages.len() >= MAX_QUEUED_MESSAGES {
+            println!("Outgoing queue is full; dropping the oldest message");
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    // Sends the messages in order until one fails; that one stays first in the queue.
+    fn flush<F: FnMut(&mqtt::Message) -> mqtt::Result<()>>(&mut self, mut send: F) {
+        while let Some(message) = self.messages.pop_front() {
+            if let Err(e) = send(&message) {
+                println!("Error sending message: {:?}", e);
+                self.messages.push_front(message);
+                break;
+            }
+        }
+    }
+}
+
+// Topics subscribed to, in order, to be restored on a new connection.
+#[derive(Default)]
+struct Subscriptions {
+    topics: Vec<String>,
+}
+
+impl Subscriptions {
+    fn add(&mut self, topic: String) {
+        if !self.topics.contains(&topic) {
+            self.topics.push(topic);
+        }
+    }
+
+    // A topic that fails doesn't keep the next ones from being subscribed to.
+    fn restore<F: FnMut(&str) -> mqtt::Result<()>>(&self, mut subscribe: F) {
+        for topic in self.topics.iter() {
+            if let Err(e) = subscribe(topic) {
+                println!("Error subscribing to topic: {:?}", e);
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct MqttClient {
     paho_client: mqtt::Client,
-    ipc_channel: (Sender<MqttMessage>, Receiver<MqttMessage>),
+    ipc_channel: (Sender<mqtt::Message>, Receiver<mqtt::Message>),
     paho_receiver: mqtt::Receiver<Option<mqtt::Message>>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    settings: ConnectSettings,
+    presence: PresenceStatus,
+    shutting_down: Arc<AtomicBool>,
+    // Messages published but neither sent nor dropped yet.
+    unsent: Arc<AtomicUsize>,
 }
 
 impl MqttClient {
-    pub fn new(broker: String, mqtt_port: u16) -> MqttClient {
+    pub fn new(settings: MqttSettings, presence: PresenceStatus) -> MqttClient {
+        let MqttSettings {
+            broker,
+            port,
+            fallback_brokers,
+            security,
+        } = settings;
+        let use_tls = security.tls_enabled();
+        let primary_uri = broker_uri(broker + ":" + &port.to_string(), use_tls);
+
         // Create a client & define connect options
         let create_opts = mqtt::CreateOptionsBuilder::new()
-            .server_uri(broker + ":" + &mqtt_port.to_string())
+            .server_uri(primary_uri.clone())
             .mqtt_version(5)
             .finalize();
 
         let paho_client = mqtt::Client::new(create_opts).unwrap();
 
-        let conn_opts = mqtt::ConnectOptionsBuilder::new_v5()
-            .keep_alive_interval(std::time::Duration::from_secs(20))
-            .clean_start(true)
-            .finalize();
+        // The primary broker is always tried first, then the fallbacks in the given order.
+        let mut server_uris = vec![primary_uri];
+        server_uris.extend(fallback_brokers.into_iter().map(|uri| broker_uri(uri, use_tls)));
 
-        // Connect and wait for it to complete or fail
-        if let Err(e) = paho_client.connect(conn_opts) {
-            println!("Error connecting: {:?}", e);
-        }
+        let ssl_options = if use_tls {
+            match security.ssl_options() {
+                Ok(ssl_opts) => Some(ssl_opts),
+                Err(e) => {
+                    println!("Invalid MQTT TLS configuration: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        } else {
+            None
+        };
 
         let paho_receiver = paho_client.start_consuming();
         let ipc_channel = channel::unbounded();
 
-        MqttClient {
+        let mqtt_client = MqttClient {
             paho_client,
             ipc_channel,
             paho_receiver,
+            subscriptions: Arc::new(Mutex::new(Subscriptions::default())),
+            settings: ConnectSettings {
+                server_uris,
+                ssl_options,
+                username: security.username,
+                password: security.password,
+            },
+            presence,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            unsent: Arc::new(AtomicUsize::new(0)),
+        };
+        mqtt_client.connect();
+        mqtt_client
+    }
+
+    fn connect_options(&self) -> mqtt::ConnectOptions {
+        let settings = &self.settings;
+        let mut conn_opts = mqtt::ConnectOptionsBuilder::new_v5();
+        conn_opts
+            .server_uris(&settings.server_uris)
+            .keep_alive_interval(std::time::Duration::from_secs(20))
+            .clean_start(true)
+            .will_message(Self::build_retained_message(
+                &self.presence.topic,
+                &(self.presence.offline)(),
+            ));
+        if let Some(ssl_opts) = &settings.ssl_options {
+            conn_opts.ssl_options(ssl_opts.clone());
+        }
+        if let Some(username) = &settings.username {
+            conn_opts.user_name(username);
+        }
+        if let Some(password) = &settings.password {
+            conn_opts.password(password);
+        }
+        conn_opts.finalize()
+    }
+
+    // Connects with exponential backoff and publishes the online record.
+    fn connect(&self) {
+        let mut delay = RECONNECT_MIN_DELAY;
+        while let Err(e) = self.paho_client.connect(self.connect_options()) {
+            println!("Error connecting: {:?}; retrying in {:?}", e, delay);
+            thread::sleep(delay);
+            delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+        }
+        self.publish_online_status();
+    }
+
+    fn publish_online_status(&self) {
+        let message = Self::build_retained_message(&self.presence.topic, &(self.presence.online)());
+        if let Err(e) = self.paho_client.publish(message) {
+            println!("Error publishing online status: {:?}", e);
+        }
+    }
+
+    // Sends the messages still queued, for a bounded time, then replaces the
+    // retained status with the offline record and closes the connection.
+    pub fn disconnect(&self) {
+        let deadline = Instant::now() + SHUTDOWN_FLUSH_TIMEOUT;
+        while self.unsent.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        let unsent = self.unsent.load(Ordering::SeqCst);
+        if unsent > 0 {
+            println!("Dropping {} messages not sent before shutting down", unsent);
+        }
+
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let message = Self::build_retained_message(&self.presence.topic, &(self.presence.offline)());
+        if let Err(e) = self.paho_client.publish(message) {
+            println!("Error publishing offline status: {:?}", e);
+        }
+        if let Err(e) = self.paho_client.disconnect(None) {
+            println!("Error disconnecting: {:?}", e);
         }
     }
 
@@ -52,43 +332,89 @@ impl MqttClient {
         // Start another thread to process messages to be sent
         let paho_client = self.paho_client.clone();
         let channel_receiver = self.ipc_channel.1.clone();
-        thread::spawn(move || loop {
-            let msg_tx = match channel_receiver.recv() {
-                Ok(msg) => msg,
-                Err(e) => {
-                    println!("Error channel tx: {:?}", e);
-                    return;
+        let unsent = self.unsent.clone();
+        thread::spawn(move || {
+            let mut pending = OutgoingQueue::default();
+            loop {
+                // While messages are pending, wake up periodically to retry them.
+                let received = if pending.is_empty() {
+                    channel_receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    channel_receiver.recv_timeout(Duration::from_millis(500))
+                };
+
+                let queued = pending.len();
+                let mut received_count = 0;
+                match received {
+                    Ok(msg_tx) => {
+                        pending.push(msg_tx);
+                        received_count = 1;
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        println!("Error channel tx: channel disconnected");
+                        return;
+                    }
                 }
-            };
-            let function_topic = msg_tx.function_topic;
-            let correlation_data = msg_tx.correlation_data;
-            let payload = msg_tx.payload;
-            let mut msg_prop = mqtt::properties::Properties::new();
-            if let Err(e) = msg_prop.push_val(PropertyCode::CorrelationData, correlation_data) {
-                println!("Error setting correlation data: {:?}", e);
-            }
-            let message = mqtt::MessageBuilder::new()
-                .topic(function_topic)
-                .payload(payload)
-                .qos(1)
-                .properties(msg_prop)
-                .finalize();
-
-            if let Err(e) = paho_client.publish(message) {
-                println!("Error sending message: {:?}", e);
+
+                // Messages stay queued while the broker is unreachable.
+                if paho_client.is_connected() {
+                    pending.flush(|message| paho_client.publish(message.clone()));
+                }
+                // Sent, or dropped from a full queue.
+                unsent.fetch_sub(queued + received_count - pending.len(), Ordering::SeqCst);
             }
         });
 
         println!("Ready to process DAB requests");
     }
+
+    fn build_message(msg_tx: MqttMessage) -> mqtt::Message {
+        let mut msg_prop = mqtt::properties::Properties::new();
+        if let Err(e) = msg_prop.push_val(PropertyCode::CorrelationData, msg_tx.correlation_data) {
+            println!("Error setting correlation data: {:?}", e);
+        }
+        mqtt::MessageBuilder::new()
+            .topic(msg_tx.function_topic)
+            .payload(msg_tx.payload)
+            .qos(1)
+            .properties(msg_prop)
+            .finalize()
+    }
+
+    fn build_retained_message(topic: &str, payload: &str) -> mqtt::Message {
+        mqtt::MessageBuilder::new()
+            .topic(topic)
+            .payload(payload)
+            .qos(1)
+            .retained(true)
+            .finalize()
+    }
+
     pub fn subscribe(&mut self, topic: String) {
         let qos = 1;
         if let Err(e) = self.paho_client.subscribe(&topic, qos) {
             println!("Error subscribing to topic: {:?}", e);
         }
+        // Remember the topic so it can be restored after a reconnection.
+        self.subscriptions.lock().unwrap().add(topic);
+    }
+
+    // Reconnects and restores every subscription, since the session is not kept
+    // by the broker (clean start). The online record also replaces the Last Will
+    // the broker may have published meanwhile.
+    fn reconnect(&self) {
+        self.connect();
+        println!("Reconnected to the MQTT broker");
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .restore(|topic| self.paho_client.subscribe(topic, 1).map(|_| ()));
     }
+
     pub fn publish(&self, msg_tx: MqttMessage) {
-        self.ipc_channel.0.send(msg_tx).unwrap();
+        self.unsent.fetch_add(1, Ordering::SeqCst);
+        self.ipc_channel.0.send(Self::build_message(msg_tx)).unwrap();
     }
     pub fn receive(&mut self) -> Result<MqttMessage, Option<String>> {
         match self.paho_receiver.recv() {
@@ -131,7 +457,16 @@ impl MqttClient {
                     None => Err(Some("No ResponseTopic provided".to_string())),
                 }
             }
-            Ok(None) => Err(None),
+            // The consumer yields None when the connection to the broker is lost.
+            Ok(None) => {
+                if !self.paho_client.is_connected()
+                    && !self.shutting_down.load(Ordering::SeqCst)
+                {
+                    println!("Lost connection to the MQTT broker");
+                    self.reconnect();
+                }
+                Err(None)
+            }
             Err(e) => Err(Some(e.to_string())),
         }
     }
@@ -0,0 +1,512 @@
+// Tests the outgoing queue and the subscriptions of the MQTT client, then runs the
+// client against a local mosquitto broker with self-signed certificates. The
+// broker tests need the mosquitto and openssl commands, so they are ignored by
+// default: cargo test mqtt_client -- --ignored
+
+use super::{
+    broker_uri, MqttClient, MqttMessage, MqttSecurity, MqttSettings, OutgoingQueue, PresenceStatus,
+    Subscriptions, MAX_QUEUED_MESSAGES,
+};
+use paho_mqtt as mqtt;
+use paho_mqtt::properties::PropertyCode;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const KEY_PASSWORD: &str = "secret";
+
+fn message(payload: usize) -> mqtt::Message {
+    mqtt::Message::new("dab/queue-test/out", payload.to_string(), 1)
+}
+
+fn payloads(messages: &[mqtt::Message]) -> Vec<usize> {
+    messages
+        .iter()
+        .map(|message| message.payload_str().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn broker_uris_use_tls_when_enabled() {
+    for (uri, expected) in [
+        ("broker.local:8883", "ssl://broker.local:8883"),
+        ("tcp://broker.local:8883", "ssl://broker.local:8883"),
+        ("mqtt://broker.local:8883", "ssl://broker.local:8883"),
+        ("ws://broker.local:443/mqtt", "wss://broker.local:443/mqtt"),
+        ("ssl://broker.local:8883", "ssl://broker.local:8883"),
+        ("wss://broker.local:443/mqtt", "wss://broker.local:443/mqtt"),
+    ] {
+        assert_eq!(broker_uri(uri.to_string(), true), expected);
+    }
+    assert_eq!(
+        broker_uri("tcp://broker.local:1883".to_string(), false),
+        "tcp://broker.local:1883"
+    );
+    assert_eq!(
+        broker_uri("broker.local:1883".to_string(), false),
+        "broker.local:1883"
+    );
+}
+
+#[test]
+fn outgoing_queue_keeps_the_latest_messages() {
+    let mut queue = OutgoingQueue::default();
+    for payload in 0..MAX_QUEUED_MESSAGES + 5 {
+        queue.push(message(payload));
+    }
+
+    let mut sent = vec![];
+    queue.flush(|message| {
+        sent.push(message.clone());
+        Ok(())
+    });
+    assert_eq!(
+        payloads(&sent),
+        (5..MAX_QUEUED_MESSAGES + 5).collect::<Vec<_>>()
+    );
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn outgoing_queue_keeps_the_messages_that_failed() {
+    let mut queue = OutgoingQueue::default();
+    for payload in 0..10 {
+        queue.push(message(payload));
+    }
+
+    // The connection is lost after three messages.
+    let mut sent = vec![];
+    queue.flush(|message| {
+        if sent.len() == 3 {
+            return Err(mqtt::errors::Disconnected);
+        }
+        sent.push(message.clone());
+        Ok(())
+    });
+    assert_eq!(payloads(&sent), vec![0, 1, 2]);
+    assert!(!queue.is_empty());
+
+    queue.push(message(10));
+    queue.flush(|message| {
+        sent.push(message.clone());
+        Ok(())
+    });
+    assert_eq!(payloads(&sent), (0..=10).collect::<Vec<_>>());
+}
+
+#[test]
+fn subscriptions_are_restored_in_order() {
+    let mut subscriptions = Subscriptions::default();
+    for topic in ["dab/device/#", "dab/discovery", "dab/device/#"] {
+        subscriptions.add(topic.to_string());
+    }
+
+    // A failed topic doesn't stop the restoration.
+    let mut restored = vec![];
+    subscriptions.restore(|topic| {
+        restored.push(topic.to_string());
+        match topic {
+            "dab/device/#" => Err(mqtt::errors::Disconnected),
+            _ => Ok(()),
+        }
+    });
+    assert_eq!(restored, vec!["dab/device/#", "dab/discovery"]);
+}
+
+fn openssl(dir: &Path, args: &[&str]) {
+    let status = Command::new("openssl")
+        .current_dir(dir)
+        .args(args)
+        .status()
+        .expect("openssl is needed");
+    assert!(status.success(), "openssl {:?} failed", args);
+}
+
+// A CA, a server certificate for localhost, and a client certificate whose key is
+// encrypted with KEY_PASSWORD.
+fn create_certificates(dir: &Path) {
+    let days = ["-days", "1"];
+    openssl(
+        dir,
+        &[
+            "req",
+            "-x509",
+            "-newkey",
+            "rsa:2048",
+            "-nodes",
+            "-keyout",
+            "ca.key",
+            "-out",
+            "ca.crt",
+            "-subj",
+            "/CN=dab-test-ca",
+            days[0],
+            days[1],
+        ],
+    );
+    openssl(
+        dir,
+        &[
+            "req",
+            "-newkey",
+            "rsa:2048",
+            "-nodes",
+            "-keyout",
+            "server.key",
+            "-out",
+            "server.csr",
+            "-subj",
+            "/CN=localhost",
+        ],
+    );
+    std::fs::write(dir.join("server.ext"), "subjectAltName=DNS:localhost\n").unwrap();
+    openssl(
+        dir,
+        &[
+            "x509",
+            "-req",
+            "-in",
+            "server.csr",
+            "-CA",
+            "ca.crt",
+            "-CAkey",
+            "ca.key",
+            "-CAcreateserial",
+            "-out",
+            "server.crt",
+            "-extfile",
+            "server.ext",
+            days[0],
+            days[1],
+        ],
+    );
+    let passout = format!("pass:{}", KEY_PASSWORD);
+    openssl(
+        dir,
+        &[
+            "req",
+            "-newkey",
+            "rsa:2048",
+            "-passout",
+            &passout,
+            "-keyout",
+            "client.key",
+            "-out",
+            "client.csr",
+            "-subj",
+            "/CN=dab-adapter",
+        ],
+    );
+    openssl(
+        dir,
+        &[
+            "x509",
+            "-req",
+            "-in",
+            "client.csr",
+            "-CA",
+            "ca.crt",
+            "-CAkey",
+            "ca.key",
+            "-CAcreateserial",
+            "-out",
+            "client.crt",
+            days[0],
+            days[1],
+        ],
+    );
+}
+
+// A mosquitto broker on a free local port, requiring a client certificate.
+struct Broker {
+    process: Child,
+    port: u16,
+    dir: PathBuf,
+}
+
+impl Broker {
+    fn start(name: &str) -> Broker {
+        let dir = std::env::temp_dir().join(format!("dab-mqtt-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        create_certificates(&dir);
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = format!(
+            "listener {} 127.0.0.1\ncafile {}\ncertfile {}\nkeyfile {}\nrequire_certificate true\nallow_anonymous true\n",
+            port,
+            dir.join("ca.crt").display(),
+            dir.join("server.crt").display(),
+            dir.join("server.key").display()
+        );
+        std::fs::write(dir.join("mosquitto.conf"), config).unwrap();
+        let process = Self::spawn(&dir, port);
+        Broker { process, port, dir }
+    }
+
+    fn spawn(dir: &Path, port: u16) -> Child {
+        let process = Command::new("mosquitto")
+            .arg("-c")
+            .arg(dir.join("mosquitto.conf"))
+            .spawn()
+            .expect("mosquitto is needed");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "mosquitto didn't start");
+            thread::sleep(Duration::from_millis(50));
+        }
+        process
+    }
+
+    fn stop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+
+    fn restart(&mut self) {
+        self.process = Self::spawn(&self.dir, self.port);
+    }
+
+    fn security(&self) -> MqttSecurity {
+        let path = |name: &str| Some(self.dir.join(name).display().to_string());
+        MqttSecurity {
+            tls: Some(true),
+            ca_file: path("ca.crt"),
+            client_cert: path("client.crt"),
+            client_key: path("client.key"),
+            client_key_password: Some(KEY_PASSWORD.to_string()),
+            ..Default::default()
+        }
+    }
+
+    // Connects a plain Paho client, with the TLS options of the adapter.
+    fn connect(&self, host: &str, security: &MqttSecurity) -> mqtt::Result<mqtt::Client> {
+        let client = mqtt::Client::new(format!("ssl://{}:{}", host, self.port))?;
+        let options = mqtt::ConnectOptionsBuilder::new()
+            .ssl_options(security.ssl_options()?)
+            .connect_timeout(Duration::from_secs(5))
+            .finalize();
+        client.connect(options)?;
+        Ok(client)
+    }
+
+    fn retained(&self, topic: &str) -> Option<String> {
+        let client = self.connect("localhost", &self.security()).unwrap();
+        let messages = client.start_consuming();
+        client.subscribe(topic, 1).unwrap();
+        let message = messages.recv_timeout(Duration::from_secs(5)).ok().flatten();
+        message.map(|message| message.payload_str().to_string())
+    }
+}
+
+impl Drop for Broker {
+    fn drop(&mut self) {
+        self.stop();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn presence(topic: &str) -> PresenceStatus {
+    PresenceStatus {
+        topic: topic.to_string(),
+        online: Arc::new(|| "online".to_string()),
+        offline: Arc::new(|| "offline".to_string()),
+    }
+}
+
+#[test]
+#[ignore = "needs mosquitto and openssl"]
+fn connects_with_tls_and_an_encrypted_client_key() {
+    let broker = Broker::start("mtls");
+    let client = MqttClient::new(
+        MqttSettings {
+            broker: "localhost".to_string(),
+            port: broker.port,
+            fallback_brokers: vec![],
+            security: broker.security(),
+        },
+        presence("dab/mtls-test/status"),
+    );
+    assert_eq!(
+        broker.retained("dab/mtls-test/status").as_deref(),
+        Some("online")
+    );
+    client.disconnect();
+    assert_eq!(
+        broker.retained("dab/mtls-test/status").as_deref(),
+        Some("offline")
+    );
+
+    // The broker requires the client certificate, and its key needs the password.
+    let without_certificate = MqttSecurity {
+        client_cert: None,
+        client_key: None,
+        client_key_password: None,
+        ..broker.security()
+    };
+    assert!(broker.connect("localhost", &without_certificate).is_err());
+    let wrong_password = MqttSecurity {
+        client_key_password: Some("wrong".to_string()),
+        ..broker.security()
+    };
+    assert!(broker.connect("localhost", &wrong_password).is_err());
+}
+
+#[test]
+#[ignore = "needs mosquitto and openssl"]
+fn verifies_the_broker_certificate_against_the_host_name() {
+    let broker = Broker::start("host-name");
+    // The certificate is for localhost only.
+    assert!(broker.connect("127.0.0.1", &broker.security()).is_err());
+    assert!(broker.connect("localhost", &broker.security()).is_ok());
+}
+
+#[test]
+#[ignore = "needs mosquitto and openssl"]
+fn reaches_fallback_brokers_with_tls() {
+    let broker = Broker::start("fallback");
+    let unused_port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    // The fallback is given without scheme, and is still reached with TLS.
+    let client = MqttClient::new(
+        MqttSettings {
+            broker: "localhost".to_string(),
+            port: unused_port,
+            fallback_brokers: vec![format!("localhost:{}", broker.port)],
+            security: broker.security(),
+        },
+        presence("dab/fallback-test/status"),
+    );
+    assert_eq!(
+        broker.retained("dab/fallback-test/status").as_deref(),
+        Some("online")
+    );
+    client.disconnect();
+}
+
+#[test]
+#[ignore = "needs mosquitto and openssl"]
+fn restores_subscriptions_and_sends_the_queued_messages_after_reconnecting() {
+    let mut broker = Broker::start("reconnect");
+    let mut client = MqttClient::new(
+        MqttSettings {
+            broker: "localhost".to_string(),
+            port: broker.port,
+            fallback_brokers: vec![],
+            security: broker.security(),
+        },
+        presence("dab/reconnect-test/status"),
+    );
+    client.subscribe("dab/reconnect-test/request".to_string());
+    client.start();
+
+    // The broker is down while the adapter publishes.
+    broker.stop();
+    for payload in 0..MAX_QUEUED_MESSAGES + 5 {
+        client.publish(MqttMessage {
+            function_topic: "dab/reconnect-test/out".to_string(),
+            response_topic: "".to_string(),
+            correlation_data: vec![0],
+            payload: payload.to_string(),
+        });
+    }
+    broker.restart();
+    let listener = broker.connect("localhost", &broker.security()).unwrap();
+    let sent = listener.start_consuming();
+    listener.subscribe("dab/reconnect-test/out", 1).unwrap();
+
+    // The lost connection is noticed by the receiving loop.
+    let (requests, received) = crossbeam::channel::unbounded();
+    let mut receiver = client.clone();
+    thread::spawn(move || loop {
+        if let Ok(request) = receiver.receive() {
+            let _ = requests.send(request);
+        }
+    });
+
+    let mut payloads = vec![];
+    while payloads.len() < MAX_QUEUED_MESSAGES {
+        match sent.recv_timeout(Duration::from_secs(30)) {
+            Ok(Some(message)) => payloads.push(message.payload_str().parse::<usize>().unwrap()),
+            other => panic!("the queued messages weren't sent: {:?}", other),
+        }
+    }
+    assert_eq!(payloads, (5..MAX_QUEUED_MESSAGES + 5).collect::<Vec<_>>());
+
+    let mut properties = mqtt::Properties::new();
+    properties
+        .push_string(PropertyCode::ResponseTopic, "dab/reconnect-test/response")
+        .unwrap();
+    let request = mqtt::MessageBuilder::new()
+        .topic("dab/reconnect-test/request")
+        .payload("{}")
+        .qos(1)
+        .properties(properties)
+        .finalize();
+    listener.publish(request).unwrap();
+    let request = received
+        .recv_timeout(Duration::from_secs(5))
+        .expect("the subscription wasn't restored");
+    assert_eq!(request.response_topic, "dab/reconnect-test/response");
+    client.disconnect();
+}
+
+#[test]
+#[ignore = "needs mosquitto and openssl"]
+fn sends_the_queued_messages_before_going_offline() {
+    let mut broker = Broker::start("shutdown");
+    let mut client = MqttClient::new(
+        MqttSettings {
+            broker: "localhost".to_string(),
+            port: broker.port,
+            fallback_brokers: vec![],
+            security: broker.security(),
+        },
+        presence("dab/shutdown-test/status"),
+    );
+    client.start();
+
+    // Responses are queued while the broker is down, then the adapter shuts down.
+    broker.stop();
+    for payload in 0..10 {
+        client.publish(MqttMessage {
+            function_topic: "dab/shutdown-test/out".to_string(),
+            response_topic: "".to_string(),
+            correlation_data: vec![0],
+            payload: payload.to_string(),
+        });
+    }
+    broker.restart();
+    let listener = broker.connect("localhost", &broker.security()).unwrap();
+    let sent = listener.start_consuming();
+    listener.subscribe("dab/shutdown-test/out", 1).unwrap();
+
+    let mut receiver = client.clone();
+    thread::spawn(move || loop {
+        let _ = receiver.receive();
+    });
+    client.disconnect();
+
+    let mut payloads = vec![];
+    while payloads.len() < 10 {
+        match sent.recv_timeout(Duration::from_secs(5)) {
+            Ok(Some(message)) => payloads.push(message.payload_str().parse::<usize>().unwrap()),
+            other => panic!("the queued messages weren't sent: {:?}", other),
+        }
+    }
+    assert_eq!(payloads, (0..10).collect::<Vec<_>>());
+    assert_eq!(
+        broker.retained("dab/shutdown-test/status").as_deref(),
+        Some("offline")
+    );
+}
@@ -33,6 +33,16 @@ pub enum RequestTypes {
     VoiceSendAudioRequest,
     VoiceSendTextRequest,
     VersionRequest,
+    #[cfg(feature = "2_1")]
+    ApplicationInstallRequest,
+    #[cfg(feature = "2_1")]
+    ApplicationUninstallRequest,
+    #[cfg(feature = "2_1")]
+    ApplicationClearDataRequest,
+    #[cfg(feature = "2_1")]
+    SystemPowerModeGetRequest,
+    #[cfg(feature = "2_1")]
+    SystemPowerModeSetRequest,
 }
 
 #[allow(non_snake_case)]
@@ -83,17 +93,41 @@ pub struct Messages {
     pub message: String,
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Default, Serialize, Deserialize, Clone, Copy)]
+pub enum PresenceState {
+    #[default]
+    online,
+    offline,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Default)]
+pub struct DeviceStatus {
+    pub status: PresenceState,
+    pub timestamp: u64,
+    pub deviceId: String,
+    pub ip: String,
+    pub adapterVersion: String,
+    pub dabVersions: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ErrorResponse {
     pub status: u16,
     pub error: String,
 }
 
+#[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Debug)]
-pub struct TelemetryMessage {
+pub struct TelemetryMessage<T = f64> {
+    // Milliseconds since the epoch.
     pub timestamp: u64,
     pub metric: String,
-    pub value: u32,
+    pub value: T,
+    // The device telemetry session, when named.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sessionId: Option<String>,
 }
 
 #[allow(non_snake_case)]
@@ -154,12 +188,92 @@ pub struct GetDeviceInformationResponse {
     pub screenHeightPixels: u32,
     pub uptimeSince: u64,
     pub deviceId: String,
+    #[cfg(feature = "2_1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deviceType: Option<String>,
+    #[cfg(feature = "2_1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modelName: Option<String>,
 }
 
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
 pub struct VersionRequest {}
 
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct InstallApplicationRequest {
+    pub appId: String,
+    pub url: String,
+    pub version: Option<String>,
+    pub appName: Option<String>,
+    // SHA-256 of the package, as hex digits optionally prefixed with "sha256:".
+    pub checksum: Option<String>,
+}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct InstallApplicationResponse {}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct UninstallApplicationRequest {
+    pub appId: String,
+}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct UninstallApplicationResponse {}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct ClearApplicationDataRequest {
+    pub appId: String,
+}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct ClearApplicationDataResponse {}
+
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PowerMode {
+    #[default]
+    On,
+    Standby,
+    LightSleep,
+    DeepSleep,
+}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct GetPowerModeRequest {}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct GetPowerModeResponse {
+    pub powerMode: PowerMode,
+}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct SetPowerModeRequest {
+    pub powerMode: PowerMode,
+}
+
+#[cfg(feature = "2_1")]
+#[allow(non_snake_case)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct SetPowerModeResponse {}
+
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
 pub struct Version {
@@ -263,7 +377,7 @@ pub struct OutputResolution {
 }
 
 #[allow(dead_code)]
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
 pub enum MatchContentFrameRate {
     #[default]
     EnabledAlways,
@@ -281,7 +395,7 @@ pub enum HdrOutputMode {
 }
 
 #[allow(dead_code)]
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
 pub enum PictureMode {
     #[default]
     Standard,
@@ -318,7 +432,7 @@ pub enum AudioOutputSource {
 }
 
 #[allow(dead_code)]
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
 pub enum VideoInputSource {
     Tuner,
     HDMI1,
@@ -342,6 +456,14 @@ pub struct AudioVolume {
 #[derive(Default, Serialize, Deserialize)]
 pub struct ListSystemSettingsRequest {}
 
+// DAB 2.1 extension.
+#[cfg(feature = "2_1")]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TextToSpeechRate {
+    pub min: u32,
+    pub max: u32,
+}
+
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
 pub struct ListSystemSettingsResponse {
@@ -359,6 +481,22 @@ pub struct ListSystemSettingsResponse {
     pub audioVolume: AudioVolume,
     pub mute: bool,
     pub textToSpeech: bool,
+    // DAB 2.1 extensions.
+    #[cfg(feature = "2_1")]
+    pub closedCaptions: bool,
+    #[cfg(feature = "2_1")]
+    pub captionsLanguage: Vec<String>,
+    #[cfg(feature = "2_1")]
+    pub audioDescription: bool,
+    #[cfg(feature = "2_1")]
+    pub highContrastText: bool,
+    #[cfg(feature = "2_1")]
+    pub screenMagnification: bool,
+    #[cfg(feature = "2_1")]
+    pub textToSpeechVoice: Vec<String>,
+    #[cfg(feature = "2_1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub textToSpeechRate: Option<TextToSpeechRate>,
 }
 
 #[allow(non_snake_case)]
@@ -382,6 +520,24 @@ pub struct GetSystemSettingsResponse {
     pub audioVolume: u32,
     pub mute: bool,
     pub textToSpeech: bool,
+    // DAB 2.1 extensions.
+    #[cfg(feature = "2_1")]
+    pub closedCaptions: bool,
+    #[cfg(feature = "2_1")]
+    pub captionsLanguage: String,
+    #[cfg(feature = "2_1")]
+    pub audioDescription: bool,
+    #[cfg(feature = "2_1")]
+    pub highContrastText: bool,
+    #[cfg(feature = "2_1")]
+    pub screenMagnification: bool,
+    #[cfg(feature = "2_1")]
+    pub textToSpeechVoice: String,
+    #[cfg(feature = "2_1")]
+    pub textToSpeechRate: u32,
+    // The settings that couldn't be read, with their error.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub errors: HashMap<String, String>,
 }
 
 #[allow(non_snake_case)]
@@ -402,6 +558,21 @@ pub struct SetSystemSettingsRequest {
     pub audioVolume: Option<u32>,
     pub mute: Option<bool>,
     pub textToSpeech: Option<bool>,
+    // DAB 2.1 extensions.
+    #[cfg(feature = "2_1")]
+    pub closedCaptions: Option<bool>,
+    #[cfg(feature = "2_1")]
+    pub captionsLanguage: Option<String>,
+    #[cfg(feature = "2_1")]
+    pub audioDescription: Option<bool>,
+    #[cfg(feature = "2_1")]
+    pub highContrastText: Option<bool>,
+    #[cfg(feature = "2_1")]
+    pub screenMagnification: Option<bool>,
+    #[cfg(feature = "2_1")]
+    pub textToSpeechVoice: Option<String>,
+    #[cfg(feature = "2_1")]
+    pub textToSpeechRate: Option<u32>,
 }
 
 #[allow(non_snake_case)]
@@ -455,6 +626,10 @@ pub struct ApplicationListRequest {}
 #[derive(Default, Serialize, Deserialize)]
 pub struct Application {
     pub appId: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub friendlyName: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
 #[allow(non_snake_case)]
@@ -507,20 +682,69 @@ pub struct LongKeyPressRequest {
 #[derive(Default, Serialize, Deserialize)]
 pub struct LongKeyPressResponse {}
 
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputImageFormat {
+    Png,
+    #[serde(alias = "jpg")]
+    Jpeg,
+    Webp,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct CropRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Without options, the screen capture is returned as the device provides it.
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
-pub struct CaptureScreenshotRequest {}
+pub struct CaptureScreenshotRequest {
+    pub format: Option<OutputImageFormat>,
+    // JPEG quality, from 1 to 100.
+    pub quality: Option<u8>,
+    pub maxWidth: Option<u32>,
+    pub maxHeight: Option<u32>,
+    // Applied before the downscale, in screen pixels.
+    pub crop: Option<CropRegion>,
+    // Where to send the image instead of returning it: an HTTP URL or a file path.
+    pub destination: Option<String>,
+    pub uploadMethod: Option<UploadMethod>,
+}
+
+#[derive(Default, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum UploadMethod {
+    #[default]
+    Put,
+    Post,
+}
 
+// With a destination, the image is described by its location, size and SHA-256
+// instead of outputImage.
+#[skip_serializing_none]
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
 pub struct CaptureScreenshotResponse {
-    pub outputImage: String,
+    pub outputImage: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub mimeType: String,
+    pub location: Option<String>,
+    pub size: Option<usize>,
+    pub sha256: Option<String>,
 }
 
 // Implement device-telemetry
+// Requests without sessionId address the default session.
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
-pub struct StopDeviceTelemetryRequest {}
+pub struct StopDeviceTelemetryRequest {
+    pub sessionId: Option<String>,
+}
 
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
@@ -530,12 +754,19 @@ pub struct StopDeviceTelemetryResponse {}
 #[derive(Default, Serialize, Deserialize)]
 pub struct StartDeviceTelemetryRequest {
     pub duration: u64,
+    pub sessionId: Option<String>,
+    // Names of the metrics to publish; all the enabled metrics by default.
+    pub metrics: Option<Vec<String>>,
+    // Milliseconds after which the session stops on its own.
+    pub timeToLive: Option<u64>,
 }
 
 #[allow(non_snake_case)]
 #[derive(Default, Serialize, Deserialize)]
 pub struct StartDeviceTelemetryResponse {
     pub duration: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sessionId: Option<String>,
 }
 
 // Implement device-telemetry
@@ -1,2 +1,155 @@
 // pub mod emulator;
 pub mod rdk;
+pub mod screenshot;
+pub mod simulator;
+
+use crate::dab::structs::*;
+use std::sync::Arc;
+
+fn not_implemented(operation: &str) -> Result<String, DabError> {
+    Err(DabError::Err501(format!("{} operator not implemented", operation)))
+}
+
+// Publishes a notification on the dab/<device-id>/messages topic.
+pub type Notifier = Arc<dyn Fn(NotificationLevel, String) + Send + Sync>;
+
+// A device platform the adapter can drive. Every DAB operation handled by the
+// dispatcher maps to one method; it returns the JSON response body on success.
+// Operations default to "not implemented", so a backend or a test double only
+// needs to provide the ones it supports.
+pub trait DabPlatform: Send + Sync {
+    // Identity of the device on the DAB bus.
+    fn get_device_id(&self) -> Result<String, DabError>;
+    fn get_ip_address(&self) -> String;
+
+    fn get_supported_versions(&self) -> Vec<String> {
+        vec!["2.0".to_string()]
+    }
+
+    // The application an appId stands for; the requests naming an application by
+    // one of its aliases must not overlap with the ones using its appId.
+    fn resolve_app_id(&self, app_id: &str) -> String {
+        app_id.to_string()
+    }
+
+    // Given once the adapter is connected to the broker, for operations that report
+    // their progress while they run.
+    fn set_notifier(&self, _notifier: Notifier) {}
+
+    // Device telemetry metrics: used memory in KB and CPU load in percent.
+    fn get_device_memory(&self) -> Result<u32, DabError> {
+        Err(DabError::Err501("memory metric not implemented".to_string()))
+    }
+    fn get_device_cpu(&self) -> Result<u32, DabError> {
+        Err(DabError::Err501("cpu metric not implemented".to_string()))
+    }
+    // All the device telemetry metrics, by name; a metric that can't be read is left out.
+    fn get_device_metrics(&self) -> Vec<(String, f64)> {
+        [("memory", self.get_device_memory()), ("cpu", self.get_device_cpu())]
+            .into_iter()
+            .filter_map(|(metric, value)| value.ok().map(|value| (metric.to_string(), value as f64)))
+            .collect()
+    }
+
+    // Application telemetry metrics: the DAB state of the application and, while it
+    // runs, its used memory in KB and CPU load in percent.
+    fn get_app_state(&self, _app_id: &str) -> Result<String, DabError> {
+        Err(DabError::Err501("state metric not implemented".to_string()))
+    }
+    fn get_app_memory(&self, _app_id: &str) -> Result<u32, DabError> {
+        Err(DabError::Err501("memory metric not implemented".to_string()))
+    }
+    fn get_app_cpu(&self, _app_id: &str) -> Result<u32, DabError> {
+        Err(DabError::Err501("cpu metric not implemented".to_string()))
+    }
+
+    fn operations_list(&self, _request: OperationsListRequest) -> Result<String, DabError> {
+        not_implemented("operations/list")
+    }
+    fn applications_list(&self, _request: ApplicationListRequest) -> Result<String, DabError> {
+        not_implemented("applications/list")
+    }
+    fn applications_launch(&self, _request: LaunchApplicationRequest) -> Result<String, DabError> {
+        not_implemented("applications/launch")
+    }
+    fn applications_launch_with_content(
+        &self,
+        _request: LaunchApplicationWithContentRequest,
+    ) -> Result<String, DabError> {
+        not_implemented("applications/launch-with-content")
+    }
+    fn applications_get_state(
+        &self,
+        _request: GetApplicationStateRequest,
+    ) -> Result<String, DabError> {
+        not_implemented("applications/get-state")
+    }
+    fn applications_exit(&self, _request: ExitApplicationRequest) -> Result<String, DabError> {
+        not_implemented("applications/exit")
+    }
+    fn device_info(&self, _request: DeviceInfoRequest) -> Result<String, DabError> {
+        not_implemented("device/info")
+    }
+    fn system_restart(&self, _request: RestartRequest) -> Result<String, DabError> {
+        not_implemented("system/restart")
+    }
+    fn system_settings_list(&self, _request: ListSystemSettingsRequest) -> Result<String, DabError> {
+        not_implemented("system/settings/list")
+    }
+    fn system_settings_get(&self, _request: GetSystemSettingsRequest) -> Result<String, DabError> {
+        not_implemented("system/settings/get")
+    }
+    fn system_settings_set(&self, _request: SetSystemSettingsRequest) -> Result<String, DabError> {
+        not_implemented("system/settings/set")
+    }
+    fn input_key_list(&self, _request: KeyListRequest) -> Result<String, DabError> {
+        not_implemented("input/key/list")
+    }
+    fn input_key_press(&self, _request: KeyPressRequest) -> Result<String, DabError> {
+        not_implemented("input/key-press")
+    }
+    fn input_long_key_press(&self, _request: LongKeyPressRequest) -> Result<String, DabError> {
+        not_implemented("input/long-key-press")
+    }
+    fn output_image(&self, _request: CaptureScreenshotRequest) -> Result<String, DabError> {
+        not_implemented("output/image")
+    }
+    fn health_check_get(&self, _request: HealthCheckRequest) -> Result<String, DabError> {
+        not_implemented("health-check/get")
+    }
+    fn voice_list(&self, _request: VoiceListRequest) -> Result<String, DabError> {
+        not_implemented("voice/list")
+    }
+    fn voice_set(&self, _request: SetVoiceSystemRequest) -> Result<String, DabError> {
+        not_implemented("voice/set")
+    }
+    fn voice_send_audio(&self, _request: SendAudioRequest) -> Result<String, DabError> {
+        not_implemented("voice/send-audio")
+    }
+    fn voice_send_text(&self, _request: SendTextRequest) -> Result<String, DabError> {
+        not_implemented("voice/send-text")
+    }
+    fn version(&self, _request: VersionRequest) -> Result<String, DabError> {
+        not_implemented("version")
+    }
+    #[cfg(feature = "2_1")]
+    fn applications_install(&self, _request: InstallApplicationRequest) -> Result<String, DabError> {
+        not_implemented("applications/install")
+    }
+    #[cfg(feature = "2_1")]
+    fn applications_uninstall(&self, _request: UninstallApplicationRequest) -> Result<String, DabError> {
+        not_implemented("applications/uninstall")
+    }
+    #[cfg(feature = "2_1")]
+    fn applications_clear_data(&self, _request: ClearApplicationDataRequest) -> Result<String, DabError> {
+        not_implemented("applications/clear-data")
+    }
+    #[cfg(feature = "2_1")]
+    fn system_power_mode_get(&self, _request: GetPowerModeRequest) -> Result<String, DabError> {
+        not_implemented("system/power-mode/get")
+    }
+    #[cfg(feature = "2_1")]
+    fn system_power_mode_set(&self, _request: SetPowerModeRequest) -> Result<String, DabError> {
+        not_implemented("system/power-mode/set")
+    }
+}
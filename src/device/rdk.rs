@@ -1,10 +1,225 @@
 pub mod applications;
 pub mod device;
+pub mod events;
 pub mod health_check;
 pub mod input;
 pub mod interface;
+#[cfg(test)]
+pub mod mock_thunder;
 pub mod operations;
 pub mod output;
 pub mod system;
+pub mod telemetry;
+#[cfg(test)]
+mod tests;
 pub mod version;
 pub mod voice;
+
+use crate::dab::structs::*;
+use crate::device::DabPlatform;
+use crate::device::Notifier;
+use interface::SleepPolicy;
+use interface::ThunderClient;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+// Drives an RDK device through the Thunder JSON-RPC interface.
+pub struct RdkPlatform {
+    thunder: Arc<ThunderClient>,
+    collectors: Vec<Box<dyn telemetry::MetricCollector>>,
+    active_input: system::settings::input::ActiveInput,
+    notifier: OnceLock<Notifier>,
+    sleep_policy: SleepPolicy,
+    // The system/power-mode lane: the dispatcher runs the other operations waking
+    // the device up in their own lanes, so they take it for the wake-up.
+    power_mode: Mutex<()>,
+    upload_dir: Option<PathBuf>,
+    capture_port: u16,
+}
+
+impl RdkPlatform {
+    pub fn new(thunder: ThunderClient) -> RdkPlatform {
+        if thunder.debug() {
+            interface::print_lifecycle_timeouts();
+        }
+        let thunder = Arc::new(thunder);
+        thunder.attach_events();
+        let collectors = telemetry::collectors(&interface::get_telemetry_metrics());
+        RdkPlatform {
+            thunder,
+            collectors,
+            active_input: Default::default(),
+            notifier: OnceLock::new(),
+            sleep_policy: interface::get_sleep_policy(),
+            power_mode: Mutex::new(()),
+            upload_dir: None,
+            capture_port: output::image::CAPTURE_PORT,
+        }
+    }
+
+    // Directory where output/image may write files, none by default.
+    pub fn with_upload_dir(mut self, upload_dir: Option<PathBuf>) -> RdkPlatform {
+        self.upload_dir = upload_dir;
+        self
+    }
+
+    fn lock_power_mode(&self) -> MutexGuard<'_, ()> {
+        match self.power_mode.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn ensure_awake(&self, operation: &str) -> Result<(), DabError> {
+        let _power_mode = self.lock_power_mode();
+        system::power_mode::ensure_awake(&self.thunder, self.sleep_policy, operation)
+    }
+
+    // Messages are dropped until the adapter is connected to the broker.
+    #[cfg(feature = "2_1")]
+    fn notify(&self, level: NotificationLevel, message: String) {
+        if let Some(notifier) = self.notifier.get() {
+            notifier(level, message);
+        }
+    }
+}
+
+impl DabPlatform for RdkPlatform {
+    fn get_device_id(&self) -> Result<String, DabError> {
+        interface::get_device_id(&self.thunder)
+    }
+    fn get_ip_address(&self) -> String {
+        self.thunder.host()
+    }
+    fn get_supported_versions(&self) -> Vec<String> {
+        version::get_supported_versions()
+    }
+    fn resolve_app_id(&self, app_id: &str) -> String {
+        applications::registry::resolve(app_id).callsign
+    }
+    fn set_notifier(&self, notifier: Notifier) {
+        let _ = self.notifier.set(notifier);
+    }
+    fn get_device_memory(&self) -> Result<u32, DabError> {
+        interface::get_device_memory(&self.thunder)
+    }
+    fn get_device_cpu(&self) -> Result<u32, DabError> {
+        interface::get_device_cpu(&self.thunder)
+    }
+    fn get_device_metrics(&self) -> Vec<(String, f64)> {
+        telemetry::collect(&self.thunder, &self.collectors)
+    }
+    fn get_app_state(&self, app_id: &str) -> Result<String, DabError> {
+        interface::get_app_state(&self.thunder, app_id)
+    }
+    fn get_app_memory(&self, app_id: &str) -> Result<u32, DabError> {
+        interface::get_app_memory(&self.thunder, app_id)
+    }
+    fn get_app_cpu(&self, app_id: &str) -> Result<u32, DabError> {
+        interface::get_app_cpu(&self.thunder, app_id)
+    }
+
+    fn operations_list(&self, request: OperationsListRequest) -> Result<String, DabError> {
+        operations::list::process(request)
+    }
+    fn applications_list(&self, request: ApplicationListRequest) -> Result<String, DabError> {
+        applications::list::process(&self.thunder, request)
+    }
+    fn applications_launch(&self, request: LaunchApplicationRequest) -> Result<String, DabError> {
+        self.ensure_awake("applications/launch")?;
+        applications::launch::process(&self.thunder, request)
+    }
+    fn applications_launch_with_content(
+        &self,
+        request: LaunchApplicationWithContentRequest,
+    ) -> Result<String, DabError> {
+        self.ensure_awake("applications/launch-with-content")?;
+        applications::launch_with_content::process(&self.thunder, request)
+    }
+    fn applications_get_state(
+        &self,
+        request: GetApplicationStateRequest,
+    ) -> Result<String, DabError> {
+        applications::get_state::process(&self.thunder, request)
+    }
+    fn applications_exit(&self, request: ExitApplicationRequest) -> Result<String, DabError> {
+        applications::exit::process(&self.thunder, request)
+    }
+    fn device_info(&self, request: DeviceInfoRequest) -> Result<String, DabError> {
+        device::info::process(&self.thunder, request)
+    }
+    fn system_restart(&self, request: RestartRequest) -> Result<String, DabError> {
+        system::restart::process(&self.thunder, request)
+    }
+    fn system_settings_list(&self, request: ListSystemSettingsRequest) -> Result<String, DabError> {
+        system::settings::list::process(&self.thunder, request)
+    }
+    fn system_settings_get(&self, request: GetSystemSettingsRequest) -> Result<String, DabError> {
+        system::settings::get::process(&self.thunder, &self.active_input, request)
+    }
+    fn system_settings_set(&self, request: SetSystemSettingsRequest) -> Result<String, DabError> {
+        system::settings::set::process(&self.thunder, &self.active_input, request)
+    }
+    fn input_key_list(&self, request: KeyListRequest) -> Result<String, DabError> {
+        input::key::list::process(request)
+    }
+    fn input_key_press(&self, request: KeyPressRequest) -> Result<String, DabError> {
+        if request.keyCode != "KEY_POWER" {
+            self.ensure_awake("input/key-press")?;
+        }
+        input::key_press::process(&self.thunder, request)
+    }
+    fn input_long_key_press(&self, request: LongKeyPressRequest) -> Result<String, DabError> {
+        if request.keyCode != "KEY_POWER" {
+            self.ensure_awake("input/long-key-press")?;
+        }
+        input::long_key_press::process(&self.thunder, request)
+    }
+    fn output_image(&self, request: CaptureScreenshotRequest) -> Result<String, DabError> {
+        self.ensure_awake("output/image")?;
+        output::image::process(&self.thunder, request, self.upload_dir.as_deref(), self.capture_port)
+    }
+    fn health_check_get(&self, request: HealthCheckRequest) -> Result<String, DabError> {
+        health_check::get::process(request)
+    }
+    fn voice_list(&self, request: VoiceListRequest) -> Result<String, DabError> {
+        voice::list::process(&self.thunder, request)
+    }
+    fn voice_set(&self, request: SetVoiceSystemRequest) -> Result<String, DabError> {
+        voice::set::process(&self.thunder, request)
+    }
+    fn voice_send_audio(&self, request: SendAudioRequest) -> Result<String, DabError> {
+        self.ensure_awake("voice/send-audio")?;
+        voice::send_audio::process(&self.thunder, request)
+    }
+    fn voice_send_text(&self, request: SendTextRequest) -> Result<String, DabError> {
+        self.ensure_awake("voice/send-text")?;
+        voice::send_text::process(&self.thunder, request)
+    }
+    fn version(&self, request: VersionRequest) -> Result<String, DabError> {
+        version::process(request)
+    }
+    #[cfg(feature = "2_1")]
+    fn applications_install(&self, request: InstallApplicationRequest) -> Result<String, DabError> {
+        applications::install::process(&self.thunder, request, &|message| {
+            self.notify(NotificationLevel::info, message)
+        })
+    }
+    #[cfg(feature = "2_1")]
+    fn applications_uninstall(&self, request: UninstallApplicationRequest) -> Result<String, DabError> {
+        applications::uninstall::process(&self.thunder, request)
+    }
+    #[cfg(feature = "2_1")]
+    fn applications_clear_data(&self, request: ClearApplicationDataRequest) -> Result<String, DabError> {
+        applications::clear_data::process(&self.thunder, request)
+    }
+    #[cfg(feature = "2_1")]
+    fn system_power_mode_get(&self, request: GetPowerModeRequest) -> Result<String, DabError> {
+        system::power_mode::get::process(&self.thunder, request)
+    }
+    #[cfg(feature = "2_1")]
+    fn system_power_mode_set(&self, request: SetPowerModeRequest) -> Result<String, DabError> {
+        let _power_mode = self.lock_power_mode();
+        system::power_mode::set::process(&self.thunder, request)
+    }
+}
@@ -1,5 +1,14 @@
+#[cfg(feature = "2_1")]
+pub mod clear_data;
 pub mod exit;
 pub mod get_state;
+#[cfg(feature = "2_1")]
+pub mod install;
 pub mod launch;
 pub mod launch_with_content;
 pub mod list;
+#[cfg(feature = "2_1")]
+pub mod packages;
+pub mod registry;
+#[cfg(feature = "2_1")]
+pub mod uninstall;
@@ -3,15 +3,20 @@ use crate::dab::structs::ExitApplicationRequest;
 use crate::dab::structs::ExitApplicationResponse;
 use crate::device::rdk::applications::get_state::AppState;
 use crate::device::rdk::applications::get_state::get_app_state;
-use crate::device::rdk::applications::get_state::get_dab_app_state;
-use crate::device::rdk::applications::launch::{rdkshell_suspend, rdkshell_destroy};
+use crate::device::rdk::applications::launch::{
+    rdkshell_destroy, rdkshell_suspend, subscribe_rdkshell_event, wait_for_app_state,
+};
+use crate::device::rdk::applications::registry;
 use crate::device::rdk::interface::get_lifecycle_timeout;
-use std::{thread, time};
+use crate::device::rdk::interface::ThunderClient;
+use std::time;
+
+const EXIT_EVENT_TIMEOUT: time::Duration = time::Duration::from_secs(2);
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: ExitApplicationRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: ExitApplicationRequest) -> Result<String, DabError> {
     let mut ResponseOperator = ExitApplicationResponse::default();
     if _dab_request.appId.is_empty() {
         return Err(DabError::Err400(
@@ -19,22 +24,24 @@ pub fn process(_dab_request: ExitApplicationRequest) -> Result<String, DabError>
         ));
     }
 
+    let app = registry::resolve(&_dab_request.appId);
     // background default is false
     let to_background = _dab_request.background.unwrap_or(false);
 
+    let exited = subscribe_rdkshell_event(thunder, if to_background { "onSuspended" } else { "onDestroyed" });
     let mut was_stopped = false;
-    let app_state = get_app_state(&_dab_request.appId)?;
+    let app_state = get_app_state(thunder, &app.callsign)?;
     match app_state {
         AppState::Visible | AppState::Invisible | AppState::Suspended => {
             if to_background {
-                rdkshell_suspend(_dab_request.appId.clone())?;
+                rdkshell_suspend(thunder, app.callsign.clone())?;
             } else {
-                rdkshell_destroy(_dab_request.appId.clone())?;
+                rdkshell_destroy(thunder, app.callsign.clone())?;
             }
         },
         AppState::Hibernated => {
             if to_background == false {
-                rdkshell_destroy(_dab_request.appId.clone())?;
+                rdkshell_destroy(thunder, app.callsign.clone())?;
             }
         },
         AppState::Stopped => {
@@ -43,29 +50,23 @@ pub fn process(_dab_request: ExitApplicationRequest) -> Result<String, DabError>
     }
 
     // *******************************************************************
-    for _idx in 1..=8 {
-        // 2 seconds (8*250ms)
-        // TODO: refactor to listen to Thunder events with websocket.
-        thread::sleep(time::Duration::from_millis(250));
-
-        if was_stopped && to_background {
-            println!("{} was already STOPPED before putting to BACKGROUND; Exiting loop.", _dab_request.appId);
-            wait_till_app_exit_timeout(&_dab_request.appId, "exit_to_background_timeout_ms");
-            break;
-        }
+    if was_stopped && to_background {
+        println!("{} was already STOPPED before putting to BACKGROUND.", _dab_request.appId);
+        wait_till_app_exit_timeout(&app.app_id, "exit_to_background_timeout_ms");
+        return Ok(serde_json::to_string(&ResponseOperator).unwrap());
+    }
 
-        ResponseOperator.state = get_dab_app_state(_dab_request.appId.clone())?;
+    ResponseOperator.state = wait_for_app_state(thunder, exited.as_ref(), &app.callsign, EXIT_EVENT_TIMEOUT, |state| {
+        is_state_match(state, to_background)
+    })?;
 
-        if is_state_match(&ResponseOperator.state, to_background) {
-            let timeout_type = if to_background {
-                "exit_to_background_timeout_ms"
-            } else {
-                "exit_to_destroy_timeout_ms"
-            };
-            
-            wait_till_app_exit_timeout(&_dab_request.appId, timeout_type);
-            break;
-        }
+    if is_state_match(&ResponseOperator.state, to_background) {
+        let timeout_type = if to_background {
+            "exit_to_background_timeout_ms"
+        } else {
+            "exit_to_destroy_timeout_ms"
+        };
+        wait_till_app_exit_timeout(&app.app_id, timeout_type);
     }
     Ok(serde_json::to_string(&ResponseOperator).unwrap())
 }
@@ -1,9 +1,10 @@
 use crate::dab::structs::DabError;
 use crate::dab::structs::GetApplicationStateRequest;
 use crate::dab::structs::GetApplicationStateResponse;
-use crate::device::rdk::interface::rdk_request;
 use crate::device::rdk::interface::RdkResponse;
-use crate::hw_specific::applications::launch::get_visibility;
+use crate::device::rdk::applications::launch::get_visibility;
+use crate::device::rdk::applications::registry;
+use crate::device::rdk::interface::ThunderClient;
 use serde::Deserialize;
 
 /**
@@ -12,7 +13,7 @@ use serde::Deserialize;
  *   FOREGROUND: Application is visible active & focused(accepting inputs).
  *   BACKGROUND: Application instance is running but not visible & focused.
 */
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DABAppState {
     Stopped,
     Background,
@@ -43,8 +44,8 @@ impl DABAppState {
  * @param callsign: String
  * @return String: DAB application state
 */
-pub fn get_dab_app_state(callsign: String) -> Result<String, DabError> {
-    match get_app_state(&callsign) {
+pub fn get_dab_app_state(thunder: &ThunderClient, callsign: String) -> Result<String, DabError> {
+    match get_app_state(thunder, &callsign) {
         Ok(app_state) => {
             match app_state {
                 AppState::Stopped => {
@@ -62,7 +63,7 @@ pub fn get_dab_app_state(callsign: String) -> Result<String, DabError> {
     }
 }
 
-pub fn get_app_state(callsign: &str) -> Result<AppState, DabError> {
+pub fn get_app_state(thunder: &ThunderClient, callsign: &str) -> Result<AppState, DabError> {
     #[derive(Deserialize)]
     #[allow(dead_code)]
     struct State {
@@ -78,7 +79,7 @@ pub fn get_app_state(callsign: &str) -> Result<AppState, DabError> {
         success: bool,
     }
 
-    let rdkresponse: RdkResponse<GetState> = rdk_request("org.rdk.RDKShell.getState")?;
+    let rdkresponse: RdkResponse<GetState> = thunder.rdk_request("org.rdk.RDKShell.getState")?;
 
     for item in rdkresponse.result.state {
         if item.callsign == callsign {
@@ -87,7 +88,7 @@ pub fn get_app_state(callsign: &str) -> Result<AppState, DabError> {
                 "hibernated" => return Ok(AppState::Hibernated),
                 "activated" | "resumed" => {
                     // Launch request mandates that application should be focused and visible.
-                    let visibility = get_visibility(callsign.to_owned())?;
+                    let visibility = get_visibility(thunder, callsign.to_owned())?;
                     let app_state = if visibility {
                         AppState::Visible
                     } else {
@@ -113,7 +114,7 @@ pub fn get_app_state(callsign: &str) -> Result<AppState, DabError> {
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: GetApplicationStateRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: GetApplicationStateRequest) -> Result<String, DabError> {
     let mut ResponseOperator = GetApplicationStateResponse::default();
     // *** Fill in the fields of the struct GetApplicationStateResponse here ***
 
@@ -123,7 +124,8 @@ pub fn process(_dab_request: GetApplicationStateRequest) -> Result<String, DabEr
         ));
     }
 
-    ResponseOperator.state = get_dab_app_state(_dab_request.appId.clone())?;
+    let app = registry::resolve(&_dab_request.appId);
+    ResponseOperator.state = get_dab_app_state(thunder, app.callsign)?;
 
     // *******************************************************************
     Ok(serde_json::to_string(&ResponseOperator).unwrap())
@@ -0,0 +1,32 @@
+use crate::dab::structs::DabError;
+use crate::dab::structs::InstallApplicationRequest;
+use crate::dab::structs::InstallApplicationResponse;
+use crate::device::rdk::applications::packages;
+use crate::device::rdk::applications::packages::Progress;
+use crate::device::rdk::interface::ThunderClient;
+
+#[allow(non_snake_case)]
+pub fn process(
+    thunder: &ThunderClient,
+    _dab_request: InstallApplicationRequest,
+    progress: Progress,
+) -> Result<String, DabError> {
+    let ResponseOperator = InstallApplicationResponse::default();
+    if _dab_request.appId.is_empty() {
+        return Err(DabError::Err400("request missing 'appId' parameter".to_string()));
+    }
+    if _dab_request.url.is_empty() {
+        return Err(DabError::Err400("request missing 'url' parameter".to_string()));
+    }
+
+    packages::install(
+        thunder,
+        &_dab_request.appId,
+        &_dab_request.url,
+        _dab_request.version.as_deref(),
+        _dab_request.appName.as_deref(),
+        _dab_request.checksum.as_deref(),
+        &|message| progress(format!("{}: {}", _dab_request.appId, message)),
+    )?;
+    Ok(serde_json::to_string(&ResponseOperator).unwrap())
+}
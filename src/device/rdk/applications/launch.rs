@@ -3,13 +3,15 @@ use crate::dab::structs::LaunchApplicationRequest;
 use crate::device::rdk::applications::get_state::AppState;
 use crate::device::rdk::applications::get_state::get_app_state;
 use crate::device::rdk::applications::get_state::get_dab_app_state;
-use crate::device::rdk::interface::http_post;
 use crate::device::rdk::interface::get_lifecycle_timeout;
 use crate::device::rdk::system::settings::get::get_rdk_language;
+use crate::device::rdk::applications::registry::{self, AppConfig, TemplateValues};
+use crate::device::rdk::events::Subscription;
+use crate::device::rdk::interface::ThunderClient;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::Value;
 
-use std::{thread, time};
+use std::time;
 use urlencoding::decode;
 
 #[derive(Serialize, Clone)]
@@ -28,27 +30,20 @@ pub struct RdkRequest<T> {
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: LaunchApplicationRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: LaunchApplicationRequest) -> Result<String, DabError> {
     if _dab_request.appId.is_empty() {
         return Err(DabError::Err400(
             "request missing 'appId' parameter".to_string(),
         ));
     }
 
-    let launch_req_params = RDKShellRequestParams {
-        callsign: _dab_request.appId.clone(),
-    };
-
-    let is_cobalt = _dab_request.appId.to_lowercase() == "cobalt"
-        || _dab_request.appId.to_lowercase() == "youtube";
-
-    let is_netflix = _dab_request.appId.to_lowercase() == "netflix";
+    let app = registry::resolve(&_dab_request.appId);
 
     let mut param_list = vec![];
 
     if let Some(mut parameters) = _dab_request.parameters.clone() {
         if parameters.len() > 0 {
-            if is_cobalt {
+            if app.decode_parameters {
                 // Decode each parameter before appending to the list
                 for param in &mut parameters {
                     *param = decode(param).unwrap().to_string();
@@ -58,111 +53,188 @@ pub fn process(_dab_request: LaunchApplicationRequest) -> Result<String, DabErro
         }
     }
 
+    let mut values = TemplateValues::new();
+    values.insert("parameters", param_list.join("&"));
+    if app.uses_placeholder("language") {
+        let language = get_rdk_language(thunder).map_err(|err|{
+            eprintln!("Unable to retrieve RDK language.");
+            err
+        })?;
+        values.insert("language", language);
+    }
+
+    launch_app(thunder, &app, &values, None)?;
+
+    Ok("{}".to_string())
+}
+
+//******************************* Generic Implementation for Reuse *******************************/
+
+const LAUNCH_EVENT_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+const APP_STATE_POLL_INTERVAL: time::Duration = time::Duration::from_millis(250);
+
+// Launches or resumes the application and brings it to the foreground, passing it the
+// launch parameters of the values, or the page to load for web applications.
+pub fn launch_app(
+    thunder: &ThunderClient,
+    app: &AppConfig,
+    values: &TemplateValues,
+    uri: Option<String>,
+) -> Result<(), DabError> {
+    let launch_req_params = RDKShellRequestParams {
+        callsign: app.callsign.clone(),
+    };
+    let has_parameters = values.get("parameters").map_or(false, |parameters| !parameters.is_empty());
+
+    let launched = subscribe_rdkshell_event(thunder, "onLaunched");
     let mut app_created = true;
-    let mut app_state = get_app_state(&_dab_request.appId)?;
+    let mut deeplink_after_start = false;
+    let mut app_state = get_app_state(thunder, &app.callsign)?;
 
     if let AppState::Hibernated = app_state {
-        println!("Restoring {} from hibernation", _dab_request.appId);
+        println!("Restoring {} from hibernation", app.callsign);
 
         // App is hibernated; restore app.
         let request = RdkRequest {
             jsonrpc: "2.0".into(),
-            id: 2,
+            id: thunder.next_id(),
             method: "org.rdk.RDKShell.restore".into(),
             params: &launch_req_params,
         };
 
         let json_string = serde_json::to_string(&request).unwrap();
-        http_post(json_string)?;
-        app_state = get_app_state(&_dab_request.appId)?;
+        thunder.http_post(json_string)?;
+        app_state = get_app_state(thunder, &app.callsign)?;
     }
 
     match app_state {
         AppState::Stopped => {
             // Cold launch of app.
-            let req_params = if is_cobalt {
-                let url = format!("https://www.youtube.com/tv?{}", param_list.join("&"));
-                let language = get_rdk_language().map_err(|err|{
-                    eprintln!("Unable to retrieve RDK language.");
-                    err
-                })?;
-                let config = json!({"url": url, "language": language});
-                RDKShellParams {
-                    callsign: _dab_request.appId.clone(),
-                    r#type: "Cobalt".into(),
-                    configuration: Some(config.to_string()),
-                }
-            } else if is_netflix {
-                let querystring = format!("{}", param_list.join("&"));
-                let config = json!({"querystring": querystring});
-                RDKShellParams {
-                    callsign: _dab_request.appId.clone(),
-                    r#type: "Netflix".into(),
-                    configuration: Some(config.to_string()),
-                }
-            } else {
-                RDKShellParams {
-                    callsign: _dab_request.appId.clone(),
-                    r#type: "LightningApp".into(),
-                    configuration: None,
-                }
+            let req_params = RDKShellParams {
+                callsign: app.callsign.clone(),
+                r#type: app.rdk_type.clone(),
+                configuration: app.launch_configuration(values),
+                uri: uri.clone(),
             };
-            send_rdkshell_launch_request(req_params)?;
+            // Without a launch configuration, the parameters are deeplinked once started.
+            deeplink_after_start = has_parameters
+                && req_params.configuration.is_none()
+                && uri.is_none()
+                && app.deeplink.is_some();
+            send_rdkshell_launch_request(thunder, req_params)?;
         },
         AppState::Suspended | AppState::Invisible | AppState::Visible => {
             app_created = false;
             //// FIXME: If parameters(?) are App startup specific, it may not take effect when resuming "plugin" runtime.
             // Deeplink is required only if you need to pass parameters to the app runtime.
-            if param_list.len() > 0 {
-                // Do app specific deeplinking.
-                if is_cobalt {
-                    // Cobalt plugin specific.
-                    let request = RdkRequest {
-                        jsonrpc: "2.0".into(),
-                        id: 3,
-                        method: _dab_request.appId.clone() + ".1.deeplink".into(),
-                        params: format!("https://www.youtube.com/tv?{}", param_list.join("&")),
-                    };
-
-                    let json_string = serde_json::to_string(&request).unwrap();
-                    http_post(json_string)?;
-                } else {
-                    // Other App specific deeplinking.
-                    return Err(DabError::Err500(
-                        "Require App specific deeplinking implementation.".to_string(),
-                    ));
-                }
+            if has_parameters || uri.is_some() {
+                deeplink(thunder, app, values, uri)?;
             }
 
             // App is suspended; resume/relaunch app.
             let request = RdkRequest {
                 jsonrpc: "2.0".into(),
-                id: 3,
+                id: thunder.next_id(),
                 method: "org.rdk.RDKShell.launch".into(),
                 params: &launch_req_params,
             };
 
             let json_string = serde_json::to_string(&request).unwrap();
-            http_post(json_string)?;
+            thunder.http_post(json_string)?;
         },
         _ => {
             println!("Should not reach here in any condition. Invalid {} App state: {:?}",
-                _dab_request.appId.clone(), app_state);
+                app.callsign, app_state);
         }
     }
 
-    wait_till_app_starts(_dab_request.appId, app_created)?;
+    wait_till_app_starts(thunder, launched.as_ref(), app, app_created)?;
+    if deeplink_after_start {
+        deeplink(thunder, app, values, None)?;
+    }
+    Ok(())
+}
 
-    Ok("{}".to_string())
+// Passes the parameters, or the page to load, to the running application.
+fn deeplink(thunder: &ThunderClient, app: &AppConfig, values: &TemplateValues, uri: Option<String>) -> Result<(), DabError> {
+    let (method, params) = match (app.deeplink_request(values), uri) {
+        (Some(deeplink), _) => deeplink,
+        // Web applications load the page set as their url.
+        (None, Some(uri)) => (format!("{}.1.url", app.callsign), Value::String(uri)),
+        (None, None) => {
+            return Err(DabError::Err500(format!(
+                "Missing {} specific deeplinking implementation.",
+                app.app_id
+            )));
+        }
+    };
+
+    let request = RdkRequest {
+        jsonrpc: "2.0".into(),
+        id: thunder.next_id(),
+        method,
+        params,
+    };
+    let json_string = serde_json::to_string(&request).unwrap();
+    thunder.http_post(json_string)?;
+    Ok(())
 }
 
-//******************************* Generic Implementation for Reuse *******************************/
+// Params of the RDKShell onLaunched, onSuspended and onDestroyed events.
+#[derive(Deserialize)]
+pub struct RdkShellAppEvent {
+    pub client: String,
+}
+
+// Subscribe before the RDKShell request, so the event can't be missed.
+// Without the event bus, e.g. while Thunder restarts, the state is polled instead.
+pub fn subscribe_rdkshell_event<'a>(
+    thunder: &'a ThunderClient,
+    event: &str,
+) -> Option<Subscription<'a, RdkShellAppEvent>> {
+    match thunder.events().and_then(|events| events.subscribe("org.rdk.RDKShell.1", event)) {
+        Ok(subscription) => Some(subscription),
+        Err(e) => {
+            println!("Polling the application state, without the RDKShell {} event: {:?}", event, e);
+            None
+        }
+    }
+}
+
+// Returns the DAB state of the app once it's reached, or the last one read when the
+// timeout expires. The state is read again when the RDKShell event is received, then
+// polled, as the state may still lag behind the event.
+pub fn wait_for_app_state<F: Fn(&str) -> bool>(
+    thunder: &ThunderClient,
+    event: Option<&Subscription<RdkShellAppEvent>>,
+    callsign: &str,
+    timeout: time::Duration,
+    reached: F,
+) -> Result<String, DabError> {
+    let deadline = time::Instant::now() + timeout;
+    let mut app_state = get_dab_app_state(thunder, callsign.to_string())?;
+    if reached(&app_state) {
+        return Ok(app_state);
+    }
+    if let Some(event) = event {
+        if event.wait_for(timeout, |event| event.client.eq_ignore_ascii_case(callsign)).is_ok() {
+            app_state = get_dab_app_state(thunder, callsign.to_string())?;
+        }
+    }
+    while !reached(&app_state) && time::Instant::now() < deadline {
+        std::thread::sleep(APP_STATE_POLL_INTERVAL);
+        app_state = get_dab_app_state(thunder, callsign.to_string())?;
+    }
+    Ok(app_state)
+}
 
 #[derive(Serialize, Clone)]
 pub struct RDKShellParams {
     pub callsign: String,
     pub r#type: String,
     pub configuration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -176,7 +248,7 @@ pub struct RDKShellRequestWithParamConfig {
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn send_rdkshell_launch_request(params: RDKShellParams) -> Result<(), DabError> {
+pub fn send_rdkshell_launch_request(thunder: &ThunderClient, params: RDKShellParams) -> Result<(), DabError> {
     #[derive(Deserialize)]
     struct LaunchResult {
         launchType: Option<String>,
@@ -193,13 +265,14 @@ pub fn send_rdkshell_launch_request(params: RDKShellParams) -> Result<(), DabErr
 
     let request = RDKShellRequestWithParamConfig {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.launch".into(),
         params,
     };
     let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
-    let rdkresponse: RdkResponseLaunch = serde_json::from_str(&response).unwrap();
+    let response = thunder.http_post(json_string)?;
+    let rdkresponse: RdkResponseLaunch = serde_json::from_str(&response)
+        .map_err(|e| DabError::Err500(format!("Invalid org.rdk.RDKShell.launch response: {}", e)))?;
     if rdkresponse.result.success == false {
         return Err(DabError::Err500(
             format!("Error from org.rdk.RDKShell.launch {}", rdkresponse.result.message.unwrap_or("".to_string())),
@@ -208,7 +281,7 @@ pub fn send_rdkshell_launch_request(params: RDKShellParams) -> Result<(), DabErr
     Ok(())
 }
 
-pub fn move_to_front_set_focus(callsign: String) -> Result<String, DabError> {
+pub fn move_to_front_set_focus(thunder: &ThunderClient, callsign: String) -> Result<String, DabError> {
     //****************org.rdk.RDKShell.moveToFront/setFocus******************************//
 
     // RDK Request Common Structs
@@ -232,25 +305,25 @@ pub fn move_to_front_set_focus(callsign: String) -> Result<String, DabError> {
     };
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.moveToFront".into(),
         params: req_params.clone(),
     };
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
 
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.1.setFocus".into(),
         params: req_params.clone(),
     };
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
     Ok("{}".to_string())
 }
 
-pub fn set_visibility(client: String, visible: bool) -> Result<String, DabError> {
+pub fn set_visibility(thunder: &ThunderClient, client: String, visible: bool) -> Result<String, DabError> {
     #[derive(Serialize)]
     struct RdkRequest {
         jsonrpc: String,
@@ -268,7 +341,7 @@ pub fn set_visibility(client: String, visible: bool) -> Result<String, DabError>
 
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.setVisibility".into(),
         params: RequestParams {
             client: client.clone(),
@@ -278,13 +351,13 @@ pub fn set_visibility(client: String, visible: bool) -> Result<String, DabError>
     };
 
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
     Ok("{}".to_string())
 }
 
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn get_visibility(client: String) -> Result<bool, DabError> {
+pub fn get_visibility(thunder: &ThunderClient, client: String) -> Result<bool, DabError> {
     #[derive(Serialize)]
     struct RdkRequest {
         jsonrpc: String,
@@ -315,7 +388,7 @@ pub fn get_visibility(client: String) -> Result<bool, DabError> {
 
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.getVisibility".into(),
         params: RequestParams {
             client: client.clone(),
@@ -324,18 +397,19 @@ pub fn get_visibility(client: String) -> Result<bool, DabError> {
     };
 
     let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
-    let rdkresponse: RdkResponse = serde_json::from_str(&response).unwrap();
+    let response = thunder.http_post(json_string)?;
+    let rdkresponse: RdkResponse = serde_json::from_str(&response)
+        .map_err(|e| DabError::Err500(format!("Invalid RDKShell.getVisibility response: {}", e)))?;
     if rdkresponse.result.success == false {
         return Err(DabError::Err500(
             format!("Error RDKShell.getVisibility {}", rdkresponse.result.message.unwrap_or("".to_string())),
         ));
     }
 
-    Ok(rdkresponse.result.visible.is_some())
+    Ok(rdkresponse.result.visible.unwrap_or(false))
 }
 
-pub fn rdkshell_suspend(callsign:String) -> Result<String, DabError> {
+pub fn rdkshell_suspend(thunder: &ThunderClient, callsign:String) -> Result<String, DabError> {
     #[derive(Serialize)]
     struct RdkRequest {
         jsonrpc: String,
@@ -351,7 +425,7 @@ pub fn rdkshell_suspend(callsign:String) -> Result<String, DabError> {
 
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.suspend".into(),
         params: RequestParams {
             callsign: callsign,
@@ -359,11 +433,11 @@ pub fn rdkshell_suspend(callsign:String) -> Result<String, DabError> {
     };
 
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
     Ok("{}".to_string())
 }
 
-pub fn rdkshell_destroy(callsign:String) -> Result<String, DabError> {
+pub fn rdkshell_destroy(thunder: &ThunderClient, callsign:String) -> Result<String, DabError> {
     #[derive(Serialize)]
     struct RdkRequest {
         jsonrpc: String,
@@ -379,7 +453,7 @@ pub fn rdkshell_destroy(callsign:String) -> Result<String, DabError> {
 
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.destroy".into(),
         params: RequestParams {
             callsign: callsign,
@@ -387,38 +461,37 @@ pub fn rdkshell_destroy(callsign:String) -> Result<String, DabError> {
     };
 
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
     Ok("{}".to_string())
 }
 
-pub fn wait_till_app_starts(req_params: String, app_created: bool) -> Result<(), DabError> {
-    let mut app_state: String = "STOPPED".to_string();
-    for _idx in 1..=20 {
-        thread::sleep(time::Duration::from_millis(250));
-        app_state = get_dab_app_state(req_params.clone())?;
-        if app_state == "FOREGROUND".to_string() {
-            let timeout_type = if !app_created {
-                "cold_launch_timeout_ms"
-            } else {
-                "resume_launch_timeout_ms"
-            };
-
-            let sleep_time = get_lifecycle_timeout(&req_params.to_lowercase(), timeout_type).unwrap_or(2500);
-            std::thread::sleep(time::Duration::from_millis(sleep_time));
-            break;
-        }
-    }
-
+pub fn wait_till_app_starts(
+    thunder: &ThunderClient,
+    launched: Option<&Subscription<RdkShellAppEvent>>,
+    app: &AppConfig,
+    app_created: bool,
+) -> Result<(), DabError> {
+    let app_state = wait_for_app_state(thunder, launched, &app.callsign, LAUNCH_EVENT_TIMEOUT, |state| {
+        state == "FOREGROUND"
+    })?;
     if app_state != "FOREGROUND" {
         return Err(DabError::Err500(
             "Check state request(5 second) timeout, app may not be visible to user.".to_string(),
         ));
     }
 
-    if !get_visibility(req_params.clone())? {
-        set_visibility(req_params.clone(), true)?;
+    let timeout_type = if !app_created {
+        "cold_launch_timeout_ms"
+    } else {
+        "resume_launch_timeout_ms"
+    };
+    let sleep_time = get_lifecycle_timeout(&app.app_id.to_lowercase(), timeout_type).unwrap_or(2500);
+    std::thread::sleep(time::Duration::from_millis(sleep_time));
+
+    if !get_visibility(thunder, app.callsign.clone())? {
+        set_visibility(thunder, app.callsign.clone(), true)?;
     }
-    move_to_front_set_focus(req_params.clone())?;
+    move_to_front_set_focus(thunder, app.callsign.clone())?;
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -2,69 +2,45 @@ use crate::dab::structs::Application;
 use crate::dab::structs::ApplicationListRequest;
 use crate::dab::structs::DabError;
 use crate::dab::structs::ListApplicationsResponse;
-use crate::device::rdk::interface::http_post;
-use serde::{Deserialize, Serialize};
+use crate::device::rdk::applications::registry;
+use crate::device::rdk::interface::RdkResponse;
+use crate::device::rdk::interface::ThunderClient;
+use serde::Deserialize;
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: ApplicationListRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: ApplicationListRequest) -> Result<String, DabError> {
     let mut ResponseOperator = ListApplicationsResponse::default();
     // *** Fill in the fields of the struct Application here ***
 
-    #[derive(Serialize)]
-    struct RdkRequest {
-        jsonrpc: String,
-        id: i32,
-        method: String,
-        params: String,
-    }
-
-    let request = RdkRequest {
-        jsonrpc: "2.0".into(),
-        id: 3,
-        method: "org.rdk.RDKShell.getAvailableTypes".into(),
-        params: "{}".into(),
-    };
-
-    #[derive(Deserialize)]
-    struct RdkResponse {
-        jsonrpc: String,
-        id: i32,
-        result: GetAvailableTypesResult,
-    }
-
     #[derive(Deserialize)]
     struct GetAvailableTypesResult {
         types: Vec<String>,
         success: bool,
     }
 
-    let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
-
-    let rdkresponse: RdkResponse = serde_json::from_str(&response).unwrap();
+    let rdkresponse: RdkResponse<GetAvailableTypesResult> =
+        thunder.rdk_request("org.rdk.RDKShell.getAvailableTypes")?;
     for s in rdkresponse.result.types.iter() {
-        match s.as_str() {
-            "YouTube" => {
-                let app = Application {
-                    appId: ("YouTube").to_string(),
-                };
-                ResponseOperator.applications.push(app);
-            }
-            "Amazon" => {
-                let app = Application {
-                    appId: ("PrimeVideo").to_string(),
-                };
-                ResponseOperator.applications.push(app);
-            }
-            "Netflix" => {
-                let app = Application {
-                    appId: ("Netflix").to_string(),
-                };
-                ResponseOperator.applications.push(app);
-            }
-            &_ => {},
+        for app in registry::apps().iter().filter(|app| app.available_type == *s) {
+            ResponseOperator.applications.push(Application {
+                appId: app.app_id.clone(),
+                ..Default::default()
+            });
+        }
+    }
+
+    // Packages installed through LISA, on devices that have it.
+    #[cfg(feature = "2_1")]
+    if let Ok(packages) = super::packages::get_installed_packages(thunder, None) {
+        for package in packages {
+            ResponseOperator.applications.retain(|app| app.appId != package.app_id);
+            ResponseOperator.applications.push(Application {
+                appId: package.app_id,
+                friendlyName: Some(package.app_name).filter(|name| !name.is_empty()),
+                version: Some(package.version),
+            });
         }
     }
 
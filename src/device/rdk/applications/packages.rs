@@ -0,0 +1,286 @@
+// Applications packaged for DAC and installed through LISA, the RDK application
+// installer. Installing and uninstalling run in the background: LISA answers with a
+// handle and reports the progress and the outcome with operationStatus events
+// carrying it.
+
+use crate::dab::structs::DabError;
+use crate::device::rdk::interface::RdkResponse;
+use crate::device::rdk::interface::ThunderClient;
+use async_std::future::timeout;
+use futures::executor::block_on;
+use futures::AsyncReadExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const PACKAGE_TYPE: &str = "application/vnd.rdk-app.dac.native";
+// Version given to the packages installed without one.
+const DEFAULT_VERSION: &str = "1.0";
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(600);
+// A download is abandoned when the server doesn't answer or send data for this long.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Reports the progress of an operation, as a message for dab/<device-id>/messages.
+pub type Progress<'a> = &'a dyn Fn(String);
+
+#[derive(Deserialize)]
+struct OperationStatus {
+    handle: String,
+    status: String,
+    #[serde(default)]
+    details: String,
+}
+
+#[derive(Deserialize)]
+struct InstalledVersion {
+    version: String,
+    #[serde(default, rename = "appName")]
+    app_name: String,
+}
+
+#[derive(Deserialize)]
+struct InstalledApp {
+    id: String,
+    r#type: String,
+    #[serde(default)]
+    installed: Vec<InstalledVersion>,
+}
+
+#[derive(Deserialize)]
+struct AppList {
+    #[serde(default)]
+    apps: Vec<InstalledApp>,
+}
+
+// One installed version of an application.
+pub struct InstalledPackage {
+    pub app_id: String,
+    pub r#type: String,
+    pub version: String,
+    pub app_name: String,
+}
+
+// The installed packages, of one application when app_id is given.
+pub fn get_installed_packages(thunder: &ThunderClient, app_id: Option<&str>) -> Result<Vec<InstalledPackage>, DabError> {
+    let params = match app_id {
+        Some(app_id) => json!({"id": app_id}),
+        None => json!({}),
+    };
+    let rdkresponse: RdkResponse<AppList> = thunder.rdk_request_with_params("LISA.1.getList", params)?;
+
+    let mut packages = vec![];
+    for app in rdkresponse.result.apps {
+        for installed in app.installed {
+            packages.push(InstalledPackage {
+                app_id: app.id.clone(),
+                r#type: app.r#type.clone(),
+                version: installed.version,
+                app_name: installed.app_name,
+            });
+        }
+    }
+    Ok(packages)
+}
+
+fn get_app_packages(thunder: &ThunderClient, app_id: &str) -> Result<Vec<InstalledPackage>, DabError> {
+    let packages = get_installed_packages(thunder, Some(app_id))?;
+    if packages.is_empty() {
+        return Err(DabError::Err400(format!("Application '{}' is not installed", app_id)));
+    }
+    Ok(packages)
+}
+
+// Starts a background operation and waits for its outcome.
+fn run_operation(thunder: &ThunderClient, method: &str, params: Value, progress: Progress) -> Result<(), DabError> {
+    let statuses = thunder.events()?.subscribe::<OperationStatus>("LISA.1", "operationStatus")?;
+    let rdkresponse: RdkResponse<String> = thunder.rdk_request_with_params(method, params)?;
+    let handle = rdkresponse.result;
+
+    let deadline = Instant::now() + OPERATION_TIMEOUT;
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let status = statuses.wait_for(timeout, |status| status.handle == handle)?;
+        match status.status.as_str() {
+            "Progress" => progress(status.details),
+            "Success" => return Ok(()),
+            _ => {
+                return Err(DabError::Err500(format!(
+                    "{} {}: {}", method, status.status.to_lowercase(), status.details)))
+            }
+        }
+    }
+}
+
+fn parse_checksum(checksum: &str) -> Result<String, DabError> {
+    let digest = checksum.strip_prefix("sha256:").unwrap_or(checksum).to_lowercase();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(DabError::Err400(format!("Invalid SHA-256 checksum '{}'", checksum)));
+    }
+    Ok(digest)
+}
+
+// Downloaded package, removed once installed or on failure.
+struct PackageFile(PathBuf);
+
+impl Drop for PackageFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// A new file only readable by the adapter, with a name no other download uses.
+fn create_package_file() -> Result<(PackageFile, File), DabError> {
+    static DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
+
+    loop {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+        let name = format!(
+            "dab-package-{}-{}-{:08x}",
+            std::process::id(),
+            DOWNLOADS.fetch_add(1, Ordering::Relaxed),
+            nanos
+        );
+        let path = std::env::temp_dir().join(name);
+        match OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(file) => return Ok((PackageFile(path), file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(DabError::Err500(format!("Error creating {}: {}", path.display(), e)))
+            }
+        }
+    }
+}
+
+// Downloads the package to the file, reporting every tenth of it when its size is
+// known, and returns its SHA-256 digest.
+fn download_package(url: &str, file: &mut File, progress: Progress) -> Result<String, DabError> {
+    let download_error = |e: surf::Error| DabError::Err500(format!("Error downloading {}: {}", url, e));
+    let timed_out = |_| DabError::Err500(format!("Timed out downloading {}", url));
+
+    block_on(async {
+        let mut response = timeout(DOWNLOAD_TIMEOUT, surf::get(url))
+            .await
+            .map_err(timed_out)?
+            .map_err(download_error)?;
+        if !response.status().is_success() {
+            return Err(DabError::Err400(format!(
+                "Error downloading {}: HTTP {}", url, response.status())));
+        }
+        let size = response.len().unwrap_or(0);
+
+        let mut body = response.take_body().into_reader();
+        let mut hasher = Sha256::new();
+        let mut downloaded = 0;
+        let mut buffer = vec![0; 64 * 1024];
+        let mut reported = 0;
+        loop {
+            let read = timeout(DOWNLOAD_TIMEOUT, body.read(&mut buffer))
+                .await
+                .map_err(timed_out)?
+                .map_err(|e| DabError::Err500(format!("Error downloading {}: {}", url, e)))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read])
+                .map_err(|e| DabError::Err500(format!("Error saving {}: {}", url, e)))?;
+            downloaded += read;
+            if size > 0 && downloaded * 10 / size > reported {
+                reported = downloaded * 10 / size;
+                progress(format!("downloaded {}%", reported * 10));
+            }
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+}
+
+// Downloads the package and checks it against the checksum; the verified copy is
+// the one installed.
+fn verify_package(url: &str, checksum: &str, progress: Progress) -> Result<PackageFile, DabError> {
+    let digest = parse_checksum(checksum)?;
+    let (package, mut file) = create_package_file()?;
+    if download_package(url, &mut file, progress)? != digest {
+        return Err(DabError::Err400(format!("Checksum mismatch for {}", url)));
+    }
+    progress("checksum verified".to_string());
+    Ok(package)
+}
+
+pub fn install(
+    thunder: &ThunderClient,
+    app_id: &str,
+    url: &str,
+    version: Option<&str>,
+    app_name: Option<&str>,
+    checksum: Option<&str>,
+    progress: Progress,
+) -> Result<(), DabError> {
+    let verified = match checksum {
+        // A remote LISA would download the package again, unverified.
+        Some(_) if !thunder.is_local() => {
+            return Err(DabError::Err400(
+                "'checksum' needs Thunder to run on the same device as dab-adapter".to_string(),
+            ))
+        }
+        Some(checksum) => Some(verify_package(url, checksum, progress)?),
+        None => None,
+    };
+    let package_url = match &verified {
+        Some(package) => format!("file://{}", package.0.display()),
+        None => url.to_string(),
+    };
+
+    // The verified copy is removed on return.
+    run_operation(
+        thunder,
+        "LISA.1.install",
+        json!({
+            "type": PACKAGE_TYPE,
+            "id": app_id,
+            "version": version.unwrap_or(DEFAULT_VERSION),
+            "url": package_url,
+            "appName": app_name.unwrap_or(app_id),
+            "category": "application",
+        }),
+        &|details| progress(format!("installing {}", details)),
+    )
+}
+
+pub fn uninstall(thunder: &ThunderClient, app_id: &str) -> Result<(), DabError> {
+    for package in get_app_packages(thunder, app_id)? {
+        run_operation(
+            thunder,
+            "LISA.1.uninstall",
+            json!({
+                "type": package.r#type,
+                "id": package.app_id,
+                "version": package.version,
+                "uninstallType": "full",
+            }),
+            &|_| {},
+        )?;
+    }
+    Ok(())
+}
+
+// Resets the persistent storage of every installed version.
+pub fn clear_data(thunder: &ThunderClient, app_id: &str) -> Result<(), DabError> {
+    for package in get_app_packages(thunder, app_id)? {
+        let _rdkresponse: RdkResponse<Value> = thunder.rdk_request_with_params(
+            "LISA.1.reset",
+            json!({
+                "type": package.r#type,
+                "id": package.app_id,
+                "version": package.version,
+                "resetType": "full",
+            }),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+// Registry of the applications known to the adapter, mapping the DAB appIds to the
+// RDKShell callsigns and types, and describing how to launch and deeplink them.
+// The built-in entries may be replaced, and other applications added, in the
+// /etc/dab/apps.json file.
+
+use crate::device::rdk::interface::read_platform_config_json;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const APPS_CONFIG_PATH: &str = "/etc/dab/apps.json";
+
+/* File Format Reference:
+    [
+        {
+            "app_id": "YouTube",
+            "aliases": ["Cobalt"],
+            "type": "Cobalt",
+            "available_type": "YouTube",
+            "callsign": "YouTube",
+            "decode_parameters": true,
+            "content_parameter": "v={contentId}",
+            "launch_configuration": {"url": "https://www.youtube.com/tv?{parameters}", "language": "{language}"},
+            "deeplink": {"method": "deeplink", "template": "https://www.youtube.com/tv?{parameters}"},
+            "lifecycle_timeouts": {
+                "cold_launch_timeout_ms": 6000,
+                "resume_launch_timeout_ms": 3000,
+                "exit_to_destroy_timeout_ms": 2500,
+                "exit_to_background_timeout_ms": 2000
+            }
+        }
+    ]
+
+    Only "app_id" and "type" are required; "callsign" defaults to the appId and
+    "available_type" to the type. In the templates, "{parameters}" is replaced by the
+    launch parameters joined with '&', "{contentId}" by the contentId of
+    applications/launch-with-content, and "{language}" by the device language.
+
+    Applications are launched with content through "content_parameter", added to the
+    launch parameters, or "uri", the page loaded by web applications (LightningApp and
+    HtmlApp). Web applications without any of them load the contentId as URL.
+*/
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Deeplink {
+    // Method of the application plugin, e.g. "deeplink" for YouTube.1.deeplink.
+    pub method: String,
+    // The params of the method: a string or an object.
+    pub template: Value,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AppConfig {
+    pub app_id: String,
+    // Other appIds accepted in the requests.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    // Type given to RDKShell.launch.
+    #[serde(rename = "type")]
+    pub rdk_type: String,
+    // Type listed by RDKShell.getAvailableTypes when the application is installed.
+    #[serde(default)]
+    pub available_type: String,
+    #[serde(default)]
+    pub callsign: String,
+    // Whether the launch parameters are URL decoded.
+    #[serde(default)]
+    pub decode_parameters: bool,
+    // Launch parameter carrying the contentId of applications/launch-with-content.
+    #[serde(default)]
+    pub content_parameter: Option<String>,
+    // Page loaded by web applications, given to RDKShell.launch and set as their url.
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub launch_configuration: Option<Value>,
+    #[serde(default)]
+    pub deeplink: Option<Deeplink>,
+    #[serde(default)]
+    pub lifecycle_timeouts: HashMap<String, u64>,
+}
+
+// Values of the template placeholders, by name.
+pub type TemplateValues = HashMap<&'static str, String>;
+
+impl AppConfig {
+    // Applications missing from the registry are launched as Lightning apps.
+    fn lightning_app(app_id: &str) -> AppConfig {
+        AppConfig {
+            app_id: app_id.to_string(),
+            aliases: vec![],
+            rdk_type: "LightningApp".to_string(),
+            available_type: String::new(),
+            callsign: app_id.to_string(),
+            decode_parameters: false,
+            content_parameter: None,
+            uri: None,
+            launch_configuration: None,
+            deeplink: None,
+            lifecycle_timeouts: HashMap::new(),
+        }
+    }
+
+    fn with_defaults(mut self) -> AppConfig {
+        if self.callsign.is_empty() {
+            self.callsign = self.app_id.clone();
+        }
+        if self.available_type.is_empty() {
+            self.available_type = self.rdk_type.clone();
+        }
+        self
+    }
+
+    pub fn matches(&self, app_id: &str) -> bool {
+        self.app_id.eq_ignore_ascii_case(app_id)
+            || self.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(app_id))
+    }
+
+    // Whether a template of the application uses the placeholder, e.g. "language".
+    pub fn uses_placeholder(&self, name: &str) -> bool {
+        let placeholder = format!("{{{}}}", name);
+        let in_template = |template: &Value| template.to_string().contains(&placeholder);
+        self.launch_configuration.as_ref().is_some_and(in_template)
+            || self.deeplink.as_ref().is_some_and(|deeplink| in_template(&deeplink.template))
+    }
+
+    pub fn is_web_app(&self) -> bool {
+        self.rdk_type == "LightningApp" || self.rdk_type == "HtmlApp"
+    }
+
+    pub fn supports_content(&self) -> bool {
+        self.content_parameter.is_some() || self.uri.is_some() || self.is_web_app()
+    }
+
+    // The launch parameter carrying the contentId, when the application has one.
+    pub fn content_launch_parameter(&self, content_id: &str) -> Option<String> {
+        self.content_parameter
+            .as_ref()
+            .map(|parameter| parameter.replace("{contentId}", content_id))
+    }
+
+    // The page to load with the content, for web applications.
+    pub fn content_uri(&self, values: &TemplateValues) -> Option<String> {
+        if let Some(uri) = &self.uri {
+            return Some(fill_text(uri, values));
+        }
+        if !self.is_web_app() || self.content_parameter.is_some() {
+            return None;
+        }
+        let content_id = values.get("contentId").cloned().unwrap_or_default();
+        match values.get("parameters") {
+            Some(parameters) if !parameters.is_empty() => {
+                let separator = if content_id.contains('?') { '&' } else { '?' };
+                Some(format!("{}{}{}", content_id, separator, parameters))
+            }
+            _ => Some(content_id),
+        }
+    }
+
+    // The RDKShell.launch configuration, which RDKShell takes as a JSON string.
+    pub fn launch_configuration(&self, values: &TemplateValues) -> Option<String> {
+        self.launch_configuration
+            .as_ref()
+            .map(|template| fill_template(template, values).to_string())
+    }
+
+    // The method and params of the deeplink request, when the application supports it.
+    pub fn deeplink_request(&self, values: &TemplateValues) -> Option<(String, Value)> {
+        self.deeplink.as_ref().map(|deeplink| {
+            (
+                format!("{}.1.{}", self.callsign, deeplink.method),
+                fill_template(&deeplink.template, values),
+            )
+        })
+    }
+}
+
+fn fill_text(text: &str, values: &TemplateValues) -> String {
+    let mut text = text.to_string();
+    for (name, value) in values {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+fn fill_template(template: &Value, values: &TemplateValues) -> Value {
+    match template {
+        Value::String(text) => Value::String(fill_text(text, values)),
+        Value::Array(items) => Value::Array(items.iter().map(|item| fill_template(item, values)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), fill_template(value, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn builtin_apps() -> Vec<AppConfig> {
+    let apps = json!([
+        {
+            "app_id": "YouTube",
+            "aliases": ["Cobalt"],
+            "type": "Cobalt",
+            "available_type": "YouTube",
+            "decode_parameters": true,
+            "content_parameter": "v={contentId}",
+            "launch_configuration": {"url": "https://www.youtube.com/tv?{parameters}", "language": "{language}"},
+            "deeplink": {"method": "deeplink", "template": "https://www.youtube.com/tv?{parameters}"},
+            "lifecycle_timeouts": {
+                "cold_launch_timeout_ms": 6000,
+                "resume_launch_timeout_ms": 3000,
+                "exit_to_destroy_timeout_ms": 2500,
+                "exit_to_background_timeout_ms": 2000
+            }
+        },
+        {
+            "app_id": "PrimeVideo",
+            "aliases": ["Amazon"],
+            "type": "Amazon",
+            "callsign": "Amazon",
+            "content_parameter": "contentId={contentId}",
+            "deeplink": {"method": "deeplink", "template": "{parameters}"}
+        },
+        {
+            "app_id": "Netflix",
+            "type": "Netflix",
+            "content_parameter": "m=https://api-global.netflix.com/catalog/titles/movie/{contentId}&source_type=4",
+            "launch_configuration": {"querystring": "{parameters}"},
+            "deeplink": {"method": "systemcommand", "template": {"command": "{parameters}"}}
+        }
+    ]);
+    serde_json::from_value::<Vec<AppConfig>>(apps)
+        .unwrap()
+        .into_iter()
+        .map(AppConfig::with_defaults)
+        .collect()
+}
+
+// The built-in applications, with the entries of the file replacing the ones with
+// the same appId.
+pub fn parse_apps(json: &str) -> Result<Vec<AppConfig>, String> {
+    let configured: Vec<AppConfig> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut apps = builtin_apps();
+    for app in configured {
+        let app = app.with_defaults();
+        match apps.iter_mut().find(|known| known.app_id.eq_ignore_ascii_case(&app.app_id)) {
+            Some(known) => *known = app,
+            None => apps.push(app),
+        }
+    }
+    Ok(apps)
+}
+
+lazy_static! {
+    static ref APPS: Vec<AppConfig> = {
+        if let Ok(json_file) = read_platform_config_json(APPS_CONFIG_PATH) {
+            match parse_apps(&json_file) {
+                Ok(apps) => {
+                    println!("Loaded the applications from {}.", APPS_CONFIG_PATH);
+                    return apps;
+                }
+                Err(error) => {
+                    eprintln!("Error while parsing {}: {}", APPS_CONFIG_PATH, error);
+                }
+            }
+        }
+        builtin_apps()
+    };
+}
+
+pub fn apps() -> &'static [AppConfig] {
+    &APPS
+}
+
+// The registry entry of the appId or one of its aliases.
+pub fn resolve(app_id: &str) -> AppConfig {
+    APPS.iter()
+        .find(|app| app.matches(app_id))
+        .cloned()
+        .unwrap_or_else(|| AppConfig::lightning_app(app_id))
+}
@@ -0,0 +1,16 @@
+use crate::dab::structs::DabError;
+use crate::dab::structs::UninstallApplicationRequest;
+use crate::dab::structs::UninstallApplicationResponse;
+use crate::device::rdk::applications::packages;
+use crate::device::rdk::interface::ThunderClient;
+
+#[allow(non_snake_case)]
+pub fn process(thunder: &ThunderClient, _dab_request: UninstallApplicationRequest) -> Result<String, DabError> {
+    let ResponseOperator = UninstallApplicationResponse::default();
+    if _dab_request.appId.is_empty() {
+        return Err(DabError::Err400("request missing 'appId' parameter".to_string()));
+    }
+
+    packages::uninstall(thunder, &_dab_request.appId)?;
+    Ok(serde_json::to_string(&ResponseOperator).unwrap())
+}
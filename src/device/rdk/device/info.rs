@@ -7,14 +7,14 @@ use crate::dab::structs::GetDeviceInformationResponse;
 use crate::dab::structs::NetworkInterface;
 use crate::dab::structs::NetworkInterfaceType;
 use crate::device::rdk::interface::get_device_id;
-use crate::device::rdk::interface::http_post;
-use crate::device::rdk::interface::{get_rdk_device_info, get_thunder_property};
+use crate::device::rdk::interface::get_rdk_device_info;
+use crate::device::rdk::interface::ThunderClient;
 use serde::{Deserialize, Serialize};
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: DeviceInfoRequest) -> Result<String, DabError> {
     let mut ResponseOperator = GetDeviceInformationResponse::default();
     // *** Fill in the fields of the struct DeviceInformation here ***
 
@@ -28,7 +28,7 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
 
     let request = GetConnectedVideoDisplaysRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.DisplaySettings.getConnectedVideoDisplays".into(),
     };
 
@@ -46,19 +46,19 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
     }
 
     let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
+    let response = thunder.http_post(json_string)?;
     let ConnectedVideoDisplays: GetConnectedVideoDisplaysResponse;
     ConnectedVideoDisplays = serde_json::from_str(&response).unwrap();
 
     //######### Map from Static Hashmap: Begin #########
 
-    ResponseOperator.manufacturer = get_rdk_device_info("manufacturer")?;
-    ResponseOperator.model = get_rdk_device_info("model")?;
-    ResponseOperator.serialNumber = get_rdk_device_info("serialnumber")?;
-    ResponseOperator.chipset = get_rdk_device_info("chipset")?;
+    ResponseOperator.manufacturer = get_rdk_device_info(thunder, "manufacturer")?;
+    ResponseOperator.model = get_rdk_device_info(thunder, "model")?;
+    ResponseOperator.serialNumber = get_rdk_device_info(thunder, "serialnumber")?;
+    ResponseOperator.chipset = get_rdk_device_info(thunder, "chipset")?;
     // Both firmwareVersion and firmwareBuild are same for RDKV devices.
-    ResponseOperator.firmwareVersion = get_rdk_device_info("firmwareversion")?;
-    ResponseOperator.firmwareBuild = get_rdk_device_info("firmwareversion")?;
+    ResponseOperator.firmwareVersion = get_rdk_device_info(thunder, "firmwareversion")?;
+    ResponseOperator.firmwareBuild = get_rdk_device_info(thunder, "firmwareversion")?;
 
     //######### Map from Static Hashmap: End #########
 
@@ -72,7 +72,7 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
     // Equivalent to DisplayInfo.width and DisplayInfo.height
     let request = GetScreenResolutionRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.getScreenResolution".into(),
     };
 
@@ -91,7 +91,7 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
     }
 
     let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
+    let response = thunder.http_post(json_string)?;
 
     let ScreenResolution: GetScreenResolutionResponse;
     ScreenResolution = serde_json::from_str(&response).unwrap();
@@ -106,7 +106,7 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
 
     let request = GetInterfacesRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.Network.getInterfaces".into(),
     };
 
@@ -131,13 +131,13 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
     }
 
     let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
+    let response = thunder.http_post(json_string)?;
     let mut Interfaces: GetInterfacesResponse;
     Interfaces = serde_json::from_str(&response).unwrap();
 
     //#########DeviceInfo.systeminfo#########
  
-    let mut device_uptime: u64 = match get_thunder_property("DeviceInfo.systeminfo","uptime") {
+    let mut device_uptime: u64 = match thunder.get_thunder_property("DeviceInfo.systeminfo","uptime") {
         Ok(uptime) => uptime.parse::<u64>().unwrap_or(0),
         Err(err) => return Err(err),
     };
@@ -179,7 +179,7 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
 
         let request = GetIPSettingsRequest {
             jsonrpc: "2.0".into(),
-            id: 3,
+            id: thunder.next_id(),
             method: "org.rdk.Network.getIPSettings".into(),
             params: req_params,
         };
@@ -205,7 +205,7 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
         }
 
         let json_string = serde_json::to_string(&request).unwrap();
-        let response = http_post(json_string)?;
+        let response = thunder.http_post(json_string)?;
 
         let IPSettings: GetIPSettingsResponse = serde_json::from_str(&response).unwrap();
         if let Some(ipaddr) = IPSettings.result.ipaddr {
@@ -238,7 +238,13 @@ pub fn process(_dab_request: DeviceInfoRequest) -> Result<String, DabError> {
     // DAB device/info needs : Current screen resolution width & height measured in pixels
     ResponseOperator.screenWidthPixels = ScreenResolution.result.w;
     ResponseOperator.screenHeightPixels = ScreenResolution.result.h;
-    ResponseOperator.deviceId = get_device_id()?;
+    ResponseOperator.deviceId = get_device_id(thunder)?;
+    // Optional in DAB 2.1, left out when the device doesn't report them.
+    #[cfg(feature = "2_1")]
+    {
+        ResponseOperator.deviceType = thunder.get_thunder_property("DeviceInfo.devicetype", "devicetype").ok();
+        ResponseOperator.modelName = thunder.get_thunder_property("DeviceInfo.modelname", "model").ok();
+    }
 
     if ConnectedVideoDisplays.result.connectedVideoDisplays.len() > 0
         && ConnectedVideoDisplays.result.connectedVideoDisplays[0].contains("HDMI")
@@ -0,0 +1,513 @@
+// One WebSocket connection to Thunder, shared by all the requests. It carries the
+// event registrations and the JSON-RPC calls whose responses must be ordered with
+// the events. When Thunder restarts, the connection is opened again and the events
+// are registered again.
+
+use crate::dab::structs::DabError;
+use crate::device::rdk::interface::ThunderClient;
+use crossbeam::channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender};
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream};
+
+const CALL_TIMEOUT: Duration = Duration::from_secs(10);
+const RECONNECT_MIN_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type Reply = Sender<Result<Value, String>>;
+// Callsign and event name, e.g. ("org.rdk.RDKShell.1", "onLaunched").
+type EventKey = (String, String);
+
+enum Command {
+    Call {
+        method: String,
+        params: Value,
+        reply: Reply,
+    },
+    Subscribe {
+        key: EventKey,
+        subscriber: u64,
+        events: Sender<Value>,
+        reply: Reply,
+    },
+    Unsubscribe {
+        key: EventKey,
+        subscriber: u64,
+    },
+}
+
+pub struct EventBus {
+    thunder: Weak<ThunderClient>,
+    // The connection is only opened once an event or a call is needed.
+    commands: Mutex<Option<mpsc::UnboundedSender<Command>>>,
+    next_subscriber: AtomicU64,
+}
+
+impl EventBus {
+    // The bus doesn't keep the client alive; it stops when the client is dropped.
+    pub fn new(thunder: Weak<ThunderClient>) -> EventBus {
+        EventBus {
+            thunder,
+            commands: Mutex::new(None),
+            next_subscriber: AtomicU64::new(1),
+        }
+    }
+
+    fn send(&self, command: Command) -> Result<(), DabError> {
+        let mut commands = self.commands.lock().unwrap();
+        let sender = commands.get_or_insert_with(|| Connection::start(self.thunder.clone()));
+        sender
+            .send(command)
+            .map_err(|_| DabError::Err500("The Thunder WebSocket connection is closed".to_string()))
+    }
+
+    // Calls a Thunder method over the WebSocket connection.
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, DabError> {
+        let (reply, response) = bounded(1);
+        self.send(Command::Call {
+            method: method.to_string(),
+            params,
+            reply,
+        })?;
+        wait_reply(&response, method)
+    }
+
+    // Registers for an event of a Thunder plugin. The events are queued from now on,
+    // so subscribe before sending the request that triggers them.
+    pub fn subscribe<T: DeserializeOwned>(
+        &self,
+        callsign: &str,
+        event: &str,
+    ) -> Result<Subscription<'_, T>, DabError> {
+        let key = (callsign.to_string(), event.to_string());
+        let (subscriber, events) = self.register(&key)?;
+        Ok(Subscription {
+            bus: self,
+            key,
+            subscriber,
+            events,
+            _event: PhantomData,
+        })
+    }
+
+    // Registers for an event until the receiver is dropped, to follow a state Thunder
+    // has no method for. The events are queued in the receiver.
+    pub fn watch(&self, callsign: &str, event: &str) -> Result<Receiver<Value>, DabError> {
+        let key = (callsign.to_string(), event.to_string());
+        self.register(&key).map(|(_, events)| events)
+    }
+
+    fn register(&self, key: &EventKey) -> Result<(u64, Receiver<Value>), DabError> {
+        let subscriber = self.next_subscriber.fetch_add(1, Ordering::Relaxed);
+        let (events_sender, events) = unbounded();
+        let (reply, response) = bounded(1);
+        self.send(Command::Subscribe {
+            key: key.clone(),
+            subscriber,
+            events: events_sender,
+            reply,
+        })?;
+        wait_reply(&response, &format!("{}.register", key.0))?;
+        Ok((subscriber, events))
+    }
+}
+
+fn wait_reply(response: &Receiver<Result<Value, String>>, method: &str) -> Result<Value, DabError> {
+    match response.recv_timeout(CALL_TIMEOUT) {
+        Ok(result) => result.map_err(DabError::Err500),
+        Err(_) => Err(DabError::Err500(format!(
+            "Timed out waiting for the {} response",
+            method
+        ))),
+    }
+}
+
+// Events received for one registration, with their params deserialized as T.
+// The registration is removed when dropped.
+pub struct Subscription<'a, T> {
+    bus: &'a EventBus,
+    key: EventKey,
+    subscriber: u64,
+    events: Receiver<Value>,
+    _event: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> Subscription<'_, T> {
+    // Returns the first event accepted by the filter; the other ones are discarded.
+    pub fn wait_for<F: Fn(&T) -> bool>(&self, timeout: Duration, filter: F) -> Result<T, DabError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let params = match self.events.recv_deadline(deadline) {
+                Ok(params) => params,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(DabError::Err500(format!(
+                        "Timed out waiting for '{}' event.",
+                        self.key.1
+                    )))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(DabError::Err500(
+                        "The Thunder WebSocket connection is closed".to_string(),
+                    ))
+                }
+            };
+            match serde_json::from_value::<T>(params) {
+                Ok(event) if filter(&event) => return Ok(event),
+                Ok(_) => {}
+                Err(e) => println!("Ignoring unexpected '{}' event: {}", self.key.1, e),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Subscription<'_, T> {
+    fn drop(&mut self) {
+        let _ = self.bus.send(Command::Unsubscribe {
+            key: self.key.clone(),
+            subscriber: self.subscriber,
+        });
+    }
+}
+
+struct Registration {
+    // Thunder prefixes the event names with this id in the notifications.
+    client_id: String,
+    registered: bool,
+    subscribers: Vec<(u64, Sender<Value>)>,
+    // Subscribers waiting for the registration to be confirmed.
+    waiting: Vec<Reply>,
+}
+
+enum Pending {
+    Call(String, Reply),
+    Register(EventKey),
+}
+
+// State of the background thread owning the WebSocket.
+struct Connection {
+    thunder: Weak<ThunderClient>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    debug: bool,
+    next_id: u64,
+    next_client_id: u64,
+    pending: HashMap<u64, Pending>,
+    registrations: HashMap<EventKey, Registration>,
+    // Commands received while disconnected.
+    queued: Vec<Command>,
+}
+
+impl Connection {
+    fn start(thunder: Weak<ThunderClient>) -> mpsc::UnboundedSender<Command> {
+        let (sender, commands) = mpsc::unbounded_channel();
+        let debug = thunder.upgrade().map(|thunder| thunder.debug()).unwrap_or(false);
+        let connection = Connection {
+            thunder,
+            commands,
+            debug,
+            next_id: 1,
+            next_client_id: 1,
+            pending: HashMap::new(),
+            registrations: HashMap::new(),
+            queued: Vec::new(),
+        };
+        thread::Builder::new()
+            .name("ThunderEvents".to_string())
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                rt.block_on(connection.run());
+            })
+            .unwrap();
+        sender
+    }
+
+    async fn run(mut self) {
+        let mut delay = RECONNECT_MIN_DELAY;
+        loop {
+            let thunder = match self.thunder.upgrade() {
+                Some(thunder) => thunder,
+                None => return,
+            };
+            let connected = thunder.ws_open().await;
+            drop(thunder);
+
+            match connected {
+                Ok(ws_stream) => {
+                    delay = RECONNECT_MIN_DELAY;
+                    if !self.serve(ws_stream).await {
+                        return;
+                    }
+                    println!("Lost the Thunder WebSocket connection; reconnecting.");
+                    continue;
+                }
+                Err(DabError::Err400(error) | DabError::Err500(error) | DabError::Err501(error)) => {
+                    // Nobody should wait for the next attempt.
+                    for command in self.queued.drain(..) {
+                        match command {
+                            Command::Call { reply, .. } | Command::Subscribe { reply, .. } => {
+                                let _ = reply.send(Err(error.clone()));
+                            }
+                            Command::Unsubscribe { .. } => {}
+                        }
+                    }
+                }
+            }
+
+            // Retry after the delay, or as soon as a request needs the connection.
+            let retry = tokio::time::sleep(delay);
+            tokio::pin!(retry);
+            tokio::select! {
+                _ = &mut retry => {},
+                command = self.commands.recv() => match command {
+                    Some(command) => self.queue(command),
+                    None => return,
+                },
+            }
+            delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+        }
+    }
+
+    fn queue(&mut self, command: Command) {
+        match command {
+            Command::Unsubscribe { key, subscriber } => self.unsubscribe(&key, subscriber),
+            command => self.queued.push(command),
+        }
+    }
+
+    // Returns false when the bus is dropped, true when the connection is lost.
+    async fn serve(&mut self, mut ws_stream: WsStream) -> bool {
+        let mut connected = self.restore(&mut ws_stream).await;
+        while connected {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => connected = self.handle(&mut ws_stream, command).await,
+                    None => {
+                        let _ = ws_stream.close(None).await;
+                        return false;
+                    }
+                },
+                message = ws_stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Some((key, client_id)) = self.dispatch(&text) {
+                            connected = self.unregister(&mut ws_stream, &key, client_id).await;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => connected = false,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+
+        for (_, pending) in self.pending.drain() {
+            if let Pending::Call(method, reply) = pending {
+                let _ = reply.send(Err(format!(
+                    "Lost the Thunder WebSocket connection during {}",
+                    method
+                )));
+            }
+        }
+        for registration in self.registrations.values_mut() {
+            registration.registered = false;
+        }
+        true
+    }
+
+    // Registers the events again on a new connection, and sends the queued commands.
+    async fn restore(&mut self, ws_stream: &mut WsStream) -> bool {
+        let keys: Vec<EventKey> = self.registrations.keys().cloned().collect();
+        for key in keys {
+            if !self.register(ws_stream, key).await {
+                return false;
+            }
+        }
+        let queued: Vec<Command> = self.queued.drain(..).collect();
+        for command in queued {
+            if !self.handle(ws_stream, command).await {
+                return false;
+            }
+        }
+        true
+    }
+
+    async fn handle(&mut self, ws_stream: &mut WsStream, command: Command) -> bool {
+        match command {
+            Command::Call { method, params, reply } => {
+                let id = self.next_id();
+                let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+                self.pending.insert(id, Pending::Call(method, reply));
+                self.send(ws_stream, request).await
+            }
+            Command::Subscribe { key, subscriber, events, reply } => {
+                let registration = match self.registrations.get_mut(&key) {
+                    Some(registration) => registration,
+                    None => {
+                        let client_id = format!("dab.{}", self.next_client_id);
+                        self.next_client_id += 1;
+                        self.registrations.insert(
+                            key.clone(),
+                            Registration {
+                                client_id,
+                                registered: false,
+                                subscribers: Vec::new(),
+                                waiting: Vec::new(),
+                            },
+                        );
+                        let registration = self.registrations.get_mut(&key).unwrap();
+                        registration.subscribers.push((subscriber, events));
+                        registration.waiting.push(reply);
+                        return self.register(ws_stream, key).await;
+                    }
+                };
+                registration.subscribers.push((subscriber, events));
+                if registration.registered {
+                    let _ = reply.send(Ok(Value::Null));
+                } else {
+                    registration.waiting.push(reply);
+                }
+                true
+            }
+            Command::Unsubscribe { key, subscriber } => {
+                let client_id = match self.registrations.get(&key) {
+                    Some(registration) => registration.client_id.clone(),
+                    None => return true,
+                };
+                self.unsubscribe(&key, subscriber);
+                if self.registrations.contains_key(&key) {
+                    return true;
+                }
+                self.unregister(ws_stream, &key, client_id).await
+            }
+        }
+    }
+
+    fn unsubscribe(&mut self, key: &EventKey, subscriber: u64) {
+        if let Some(registration) = self.registrations.get_mut(key) {
+            registration.subscribers.retain(|(id, _)| *id != subscriber);
+            if registration.subscribers.is_empty() {
+                self.registrations.remove(key);
+            }
+        }
+    }
+
+    async fn register(&mut self, ws_stream: &mut WsStream, key: EventKey) -> bool {
+        let client_id = self.registrations[&key].client_id.clone();
+        let id = self.next_id();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": format!("{}.register", key.0),
+            "params": {"event": key.1, "id": client_id}
+        });
+        self.pending.insert(id, Pending::Register(key));
+        self.send(ws_stream, request).await
+    }
+
+    async fn unregister(&mut self, ws_stream: &mut WsStream, key: &EventKey, client_id: String) -> bool {
+        let id = self.next_id();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": format!("{}.unregister", key.0),
+            "params": {"event": key.1, "id": client_id}
+        });
+        self.send(ws_stream, request).await
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    async fn send(&self, ws_stream: &mut WsStream, request: Value) -> bool {
+        if self.debug {
+            println!("RDK WebSocket request: {}", request);
+        }
+        ws_stream.send(Message::Text(request.to_string())).await.is_ok()
+    }
+
+    // Returns the registration left without subscribers by a notification, to be
+    // unregistered: the receivers of watched events are dropped without unsubscribing.
+    fn dispatch(&mut self, text: &str) -> Option<(EventKey, String)> {
+        if self.debug {
+            println!("RDK WebSocket message: {}", text);
+        }
+        let message: Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                println!("Ignoring invalid Thunder WebSocket message: {}", e);
+                return None;
+            }
+        };
+
+        if let Some(id) = message["id"].as_u64() {
+            match self.pending.remove(&id) {
+                Some(Pending::Call(method, reply)) => {
+                    let _ = reply.send(call_result(&method, &message));
+                }
+                Some(Pending::Register(key)) => self.registered(key, &message),
+                None => {}
+            }
+            return None;
+        }
+
+        // Notifications are named "<client id>.<event>".
+        let (client_id, event) = message["method"].as_str()?.rsplit_once('.')?;
+        let (key, registration) = self
+            .registrations
+            .iter_mut()
+            .find(|((_, name), registration)| name == event && registration.client_id == client_id)?;
+        registration
+            .subscribers
+            .retain(|(_, events)| events.send(message["params"].clone()).is_ok());
+        if !registration.subscribers.is_empty() {
+            return None;
+        }
+        let key = key.clone();
+        let registration = self.registrations.remove(&key)?;
+        Some((key, registration.client_id))
+    }
+
+    fn registered(&mut self, key: EventKey, message: &Value) {
+        let result = call_result(&format!("{}.register", key.0), message);
+        let registration = match self.registrations.get_mut(&key) {
+            Some(registration) => registration,
+            None => return,
+        };
+        if result.is_ok() {
+            registration.registered = true;
+            for reply in registration.waiting.drain(..) {
+                let _ = reply.send(Ok(Value::Null));
+            }
+        } else if let Some(registration) = self.registrations.remove(&key) {
+            for reply in registration.waiting {
+                let _ = reply.send(result.clone());
+            }
+        }
+    }
+}
+
+fn call_result(method: &str, response: &Value) -> Result<Value, String> {
+    if !response["error"].is_null() {
+        return Err(response["error"]["message"]
+            .as_str()
+            .unwrap_or("Unknown Thunder error")
+            .to_string());
+    }
+    if response["result"]["success"] == Value::Bool(false) {
+        return Err(format!("{} failed", method));
+    }
+    Ok(response["result"].clone())
+}
@@ -2,13 +2,13 @@ use crate::dab::structs::DabError;
 use crate::dab::structs::KeyPressRequest;
 use crate::dab::structs::KeyPressResponse;
 use crate::device::rdk::interface::get_keycode;
-use crate::device::rdk::interface::http_post;
+use crate::device::rdk::interface::ThunderClient;
 use serde::{Deserialize, Serialize};
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: KeyPressRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: KeyPressRequest) -> Result<String, DabError> {
     let mut ResponseOperator = KeyPressResponse::default();
     // *** Fill in the fields of the struct KeyPressResponse here ***
 
@@ -43,7 +43,7 @@ pub fn process(_dab_request: KeyPressRequest) -> Result<String, DabError> {
 
     let request = InjectKeyRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.1.injectKey".into(),
         params: req_params,
     };
@@ -61,7 +61,7 @@ pub fn process(_dab_request: KeyPressRequest) -> Result<String, DabError> {
     }
 
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
 
     // *******************************************************************
     Ok(serde_json::to_string(&ResponseOperator).unwrap())
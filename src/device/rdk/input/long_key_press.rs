@@ -2,7 +2,7 @@ use crate::dab::structs::DabError;
 use crate::dab::structs::LongKeyPressRequest;
 use crate::dab::structs::LongKeyPressResponse;
 use crate::device::rdk::interface::get_keycode;
-use crate::device::rdk::interface::http_post;
+use crate::device::rdk::interface::ThunderClient;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
 use std::thread;
@@ -12,7 +12,7 @@ use std::time::Instant;
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: LongKeyPressRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: LongKeyPressRequest) -> Result<String, DabError> {
     let mut ResponseOperator = LongKeyPressResponse::default();
     // *** Fill in the fields of the struct LongKeyPressResponse here ***
 
@@ -70,7 +70,7 @@ pub fn process(_dab_request: LongKeyPressRequest) -> Result<String, DabError> {
 
     let request = InjectKeyRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.RDKShell.1.injectKey".into(),
         params: req_params,
     };
@@ -93,7 +93,7 @@ pub fn process(_dab_request: LongKeyPressRequest) -> Result<String, DabError> {
     while elapsed_time < total_time {
         let start_time = Instant::now();
 
-        http_post(json_string.clone())?;
+        thunder.http_post(json_string.clone())?;
 
         let mut end_time = Instant::now().duration_since(start_time).as_millis();
         if end_time < interval_ms.into() {
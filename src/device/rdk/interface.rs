@@ -1,9 +1,11 @@
 use crate::dab::structs::AudioOutputMode;
 use crate::dab::structs::AudioVolume;
 use crate::dab::structs::DabError;
+use crate::device::rdk::applications::get_state::get_dab_app_state;
+use crate::device::rdk::applications::registry;
+use crate::device::rdk::events::{EventBus, Subscription};
+use crate::device::rdk::telemetry;
 use futures::executor::block_on;
-use futures_util::stream::StreamExt;
-use futures_util::SinkExt;
 use lazy_static::lazy_static;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
@@ -12,350 +14,596 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 use std::io::Write;
-use std::{thread, time};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time;
 use surf::Client;
 use tokio::net::TcpStream;
-use tokio::time::{timeout, Duration};
-use tokio_tungstenite::{
-    connect_async, tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream,
-};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 use url::Url;
 
-static mut DEVICE_ADDRESS: String = String::new();
-static mut DEBUG: bool = false;
+pub const DEFAULT_THUNDER_PORT: u16 = 9998;
 
-pub fn init(device_ip: &str, debug: bool) {
-    unsafe {
-        DEVICE_ADDRESS.push_str(&device_ip);
-        DEBUG = debug;
+// Thunder answers with this JSON-RPC error code when the SecurityAgent rejects the token.
+const THUNDER_ERROR_PRIVILEGED_REQUEST: i64 = -32604;
+
+const SERVICE_STATE_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+const SERVICE_STATE_POLL_INTERVAL: time::Duration = time::Duration::from_millis(100);
+
+#[derive(Deserialize)]
+struct ServiceStateChange {
+    callsign: String,
+    state: String,
+}
+
+// Where the SecurityAgent token for Thunder comes from, on images where Thunder
+// requires one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ThunderTokenSource {
+    None,
+    // Environment variable holding the token.
+    Env(String),
+    // File holding the token, re-read when Thunder rejects it.
+    File(PathBuf),
+    // SecurityAgent.createtoken; only allowed for clients on the device.
+    SecurityAgent,
+}
+
+// Connection to the Thunder JSON-RPC interface of a device. The HTTP client is
+// shared by all the requests, so the connections to Thunder are reused.
+pub struct ThunderClient {
+    http_url: Url,
+    ws_url: Url,
+    client: Client,
+    next_id: AtomicI32,
+    debug: bool,
+    token_source: ThunderTokenSource,
+    token: Mutex<Option<String>>,
+    // Static device info, queried once.
+    device_id: OnceLock<String>,
+    device_info: OnceLock<HashMap<String, String>>,
+    events: OnceLock<EventBus>,
+}
+
+impl ThunderClient {
+    // Parameters: url: The Thunder JSON-RPC endpoint, e.g. http://192.168.1.10:9998/jsonrpc.
+    // The WebSocket endpoint is the same, with the ws or wss scheme.
+    pub fn new(url: &str, debug: bool) -> Result<ThunderClient, String> {
+        let http_url = Url::parse(url).map_err(|e| format!("Invalid Thunder URL {}: {}", url, e))?;
+        let ws_scheme = match http_url.scheme() {
+            "http" => "ws",
+            "https" => "wss",
+            scheme => return Err(format!("Unsupported Thunder URL scheme: {}", scheme)),
+        };
+        if http_url.host_str().is_none() {
+            return Err(format!("Missing host in Thunder URL {}", url));
+        }
+        let mut ws_url = http_url.clone();
+        ws_url
+            .set_scheme(ws_scheme)
+            .map_err(|_| format!("Invalid Thunder URL {}", url))?;
+
+        Ok(ThunderClient {
+            http_url,
+            ws_url,
+            client: Client::new(),
+            next_id: AtomicI32::new(1),
+            debug,
+            token_source: ThunderTokenSource::None,
+            token: Mutex::new(None),
+            device_id: OnceLock::new(),
+            device_info: OnceLock::new(),
+            events: OnceLock::new(),
+        })
     }
 
-    if unsafe { DEBUG } {
-        for app in APP_LIFECYCLE_TIMEOUTS.keys() {
-            for (key, value) in APP_LIFECYCLE_TIMEOUTS.get(app).unwrap() {
-                println!("{:<15} - {:<30} = {:>5}ms.", app, key, value);
-            }
+    // Thunder on its default port of the given device.
+    pub fn for_device(device_ip: &str, debug: bool) -> Result<ThunderClient, String> {
+        let host = if device_ip.contains(':') && !device_ip.starts_with('[') {
+            format!("[{}]", device_ip)
+        } else {
+            device_ip.to_string()
+        };
+        ThunderClient::new(
+            &format!("http://{}:{}/jsonrpc", host, DEFAULT_THUNDER_PORT),
+            debug,
+        )
+    }
+
+    // The token is attached to every HTTP and WebSocket request, and obtained again
+    // from the source when Thunder rejects it.
+    pub fn with_token_source(mut self, token_source: ThunderTokenSource) -> ThunderClient {
+        self.token_source = token_source;
+        // The SecurityAgent is only asked for a token once Thunder requires one.
+        if self.token_source != ThunderTokenSource::SecurityAgent {
+            *self.token.lock().unwrap() = self.request_token();
         }
+        self
     }
-}
 
-lazy_static! {
-    static ref RDK_DEVICE_ID: String = request_device_id().unwrap();
-}
+    // Whether Thunder runs on the same device, so the SecurityAgent can hand out a token.
+    pub fn is_local(&self) -> bool {
+        match self.http_url.host() {
+            Some(url::Host::Domain(domain)) => domain == "localhost",
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        }
+    }
 
-pub fn get_device_id() -> Result<String, DabError> {
-    Ok(RDK_DEVICE_ID.to_string())
-}
+    pub fn host(&self) -> String {
+        self.http_url
+            .host_str()
+            .unwrap_or_default()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string()
+    }
 
-fn request_device_id() -> Result<String, DabError> {
-    let json_string =
-        "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"org.rdk.System.getDeviceInfo\",\"params\":{\"params\":[\"estb_mac\"]}}".to_string();
-    let response = http_post(json_string)?;
-    let rdkresponse: serde_json::Value = serde_json::from_str(&response).unwrap();
-    let device_id = rdkresponse["result"]["estb_mac"]
-        .as_str()
-        .ok_or(DabError::Err500(
-            "RDK Error: org.rdk.System.getDeviceInfo.result.estb_mac not found".to_string(),
-        ))?;
-    Ok(device_id.replace(":", "").to_string())
-}
+    // Enables the events; the bus needs a shared client to reconnect on its own.
+    pub fn attach_events(self: &Arc<Self>) {
+        let _ = self.events.set(EventBus::new(Arc::downgrade(self)));
+    }
 
-pub fn http_download(url: String) -> Result<(), DabError> {
-    let client = Client::new();
+    pub fn events(&self) -> Result<&EventBus, DabError> {
+        self.events
+            .get()
+            .ok_or(DabError::Err500("Thunder events are not enabled".to_string()))
+    }
 
-    let response = block_on(async { client.get(url).await });
+    pub fn debug(&self) -> bool {
+        self.debug
+    }
 
-    match response {
-        Ok(mut r) => {
-            let mut file = File::create("/tmp/tts.wav").unwrap();
-            let body = block_on(r.body_bytes()).unwrap();
-            file.write_all(&body).unwrap();
-            return Ok(());
+    pub fn next_id(&self) -> i32 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn http_download(&self, url: String) -> Result<(), DabError> {
+        let response = block_on(async { self.client.get(url).await });
+
+        match response {
+            Ok(mut r) => {
+                let mut file = File::create("/tmp/tts.wav").unwrap();
+                let body = block_on(r.body_bytes()).unwrap();
+                file.write_all(&body).unwrap();
+                return Ok(());
+            }
+            Err(err) => return Err(DabError::Err500(err.to_string())),
         }
-        Err(err) => return Err(DabError::Err500(err.to_string())),
     }
-}
 
-pub fn http_post(json_string: String) -> Result<String, DabError> {
-    let client = Client::new();
-    let rdk_address = format!("http://{}:9998/jsonrpc", unsafe { &DEVICE_ADDRESS });
+    fn current_token(&self) -> Option<String> {
+        self.token.lock().unwrap().clone()
+    }
 
-    unsafe {
-        if DEBUG {
-            println!("RDK request: {}", json_string);
+    fn request_token(&self) -> Option<String> {
+        let token = match &self.token_source {
+            ThunderTokenSource::None => return None,
+            ThunderTokenSource::Env(name) => std::env::var(name)
+                .map_err(|_| format!("Environment variable {} is not set", name)),
+            ThunderTokenSource::File(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("Error reading {}: {}", path.display(), e)),
+            ThunderTokenSource::SecurityAgent => self.create_security_agent_token(),
+        };
+        match token {
+            Ok(token) if !token.trim().is_empty() => Some(token.trim().to_string()),
+            Ok(_) => {
+                eprintln!("Empty Thunder token from {:?}", self.token_source);
+                None
+            }
+            Err(error) => {
+                eprintln!("Unable to get a Thunder token: {}", error);
+                None
+            }
         }
     }
 
-    let response = block_on(async {
-        match client
-            .post(rdk_address)
-            .body_string(json_string)
-            .header("Content-Type", "application/json")
-            .await
-        {
-            Ok(mut response) => {
-                match response.body_string().await {
-                    Ok(body) => Ok(body),
-                    Err(e) => Err(format!("Error while getting the body: {}",e)),
+    fn create_security_agent_token(&self) -> Result<String, String> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id(),
+            "method": "SecurityAgent.1.createtoken",
+            "params": {"url": "http://localhost"}
+        })
+        .to_string();
+        let (_, body) = self.send_post(request, None)?;
+        let response: Value = serde_json::from_str(&body)
+            .map_err(|e| format!("Invalid SecurityAgent.createtoken response: {}", e))?;
+        match response["result"]["token"].as_str() {
+            Some(token) => Ok(token.to_string()),
+            None => Err(format!(
+                "SecurityAgent.createtoken failed: {}",
+                response["error"]["message"].as_str().unwrap_or("no token in response")
+            )),
+        }
+    }
+
+    // Obtains a new token after Thunder rejected the one used. Returns None when
+    // there is no other token to try.
+    fn refresh_token(&self, rejected: &Option<String>) -> Option<String> {
+        let mut token = self.token.lock().unwrap();
+        // Another request may have refreshed it in the meantime.
+        if *token != *rejected {
+            return token.clone();
+        }
+        let refreshed = self.request_token();
+        if refreshed.is_none() || refreshed == *rejected {
+            return None;
+        }
+        println!("Obtained a new Thunder token from {:?}", self.token_source);
+        *token = refreshed.clone();
+        refreshed
+    }
+
+    fn send_post(&self, json_string: String, token: Option<&str>) -> Result<(StatusCode, String), String> {
+        block_on(async {
+            let mut request = self
+                .client
+                .post(self.http_url.clone())
+                .body_string(json_string)
+                .header("Content-Type", "application/json");
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {}", token));
+            }
+            match request.await {
+                Ok(mut response) => {
+                    let status = StatusCode::from_u16(response.status().into())
+                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                    match response.body_string().await {
+                        Ok(body) => Ok((status, body)),
+                        Err(e) => Err(format!("Error while getting the body: {}",e)),
+                    }
                 }
+                Err(e) => Err(format!("Error while sending the request: {}",e)),
             }
-            Err(e) => Err(format!("Error while sending the request: {}",e)),
+        })
+    }
+
+    fn is_authorization_error(status: StatusCode, body: &str) -> bool {
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return true;
         }
-    });
+        body.contains(&THUNDER_ERROR_PRIVILEGED_REQUEST.to_string())
+            && serde_json::from_str::<Value>(body)
+                .map(|response| response["error"]["code"].as_i64() == Some(THUNDER_ERROR_PRIVILEGED_REQUEST))
+                .unwrap_or(false)
+    }
 
-    match response {
-        Ok(r) => {
-            let str = r.to_string();
+    pub fn http_post(&self, json_string: String) -> Result<String, DabError> {
+        if self.debug {
+            println!("RDK request: {}", json_string);
+        }
 
-            unsafe {
-                if DEBUG {
-                    println!("RDK response: {}", str);
+        let mut token = self.current_token();
+        let mut response = self.send_post(json_string.clone(), token.as_deref());
+        if let Ok((status, body)) = &response {
+            if Self::is_authorization_error(*status, body) {
+                token = self.refresh_token(&token);
+                if token.is_some() {
+                    response = self.send_post(json_string, token.as_deref());
                 }
             }
-
-            return Ok(str);
         }
-        Err(err) => {
-            let str = err.to_string();
 
-            unsafe {
-                if DEBUG {
+        let response = response.and_then(|(status, body)| {
+            if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                Err(format!("Thunder rejected the request ({}); check the SecurityAgent token", status))
+            } else {
+                Ok(body)
+            }
+        });
+
+        match response {
+            Ok(r) => {
+                let str = r.to_string();
+
+                if self.debug {
+                    println!("RDK response: {}", str);
+                }
+
+                return Ok(str);
+            }
+            Err(err) => {
+                let str = err.to_string();
+
+                if self.debug {
                     println!("RDK error: {}", str);
                 }
+
+                return Err(DabError::Err500(str));
             }
+        }
+    }
 
-            return Err(DabError::Err500(str));
+    async fn ws_connect(
+        &self,
+        token: Option<&str>,
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, WsError> {
+        let mut request = self.ws_url.clone().into_client_request()?;
+        if let Some(Ok(authorization)) =
+            token.map(|token| HeaderValue::from_str(&format!("Bearer {}", token)))
+        {
+            request.headers_mut().insert("Authorization", authorization);
         }
+        connect_async(request).await.map(|(ws_stream, _)| ws_stream)
     }
-}
 
-pub async fn ws_open() -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, DabError> {
-    let rdk_address = format!("ws://{}:9998/jsonrpc", unsafe { &DEVICE_ADDRESS });
-    let url = Url::parse(&rdk_address).expect("Invalid WebSocket URL");
+    pub async fn ws_open(&self) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, DabError> {
+        let token = self.current_token();
+        let result = match self.ws_connect(token.as_deref()).await {
+            Err(WsError::Http(response))
+                if response.status() == StatusCode::UNAUTHORIZED
+                    || response.status() == StatusCode::FORBIDDEN =>
+            {
+                match self.refresh_token(&token) {
+                    Some(token) => self.ws_connect(Some(&token)).await,
+                    None => Err(WsError::Http(response)),
+                }
+            }
+            result => result,
+        };
 
-    connect_async(url)
-        .await
-        .map_err(|e| DabError::Err500(format!("Failed to connect: {}", e)))
-        .map(|(ws_stream, _)| ws_stream)
-}
+        result.map_err(|e| DabError::Err500(format!("Failed to connect: {}", e)))
+    }
 
-pub async fn ws_close(
-    ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
-) -> Result<(), DabError> {
-    ws_stream
-        .close(None)
-        .await
-        .map_err(|e| DabError::Err500(format!("Failed to close WebSocket: {}", e)))
-}
+    pub fn rdk_request<R: DeserializeOwned>(&self, method: &str) -> Result<R, DabError> {
+        #[derive(Serialize)]
+        struct RdkNullParams {}
 
-pub async fn ws_send(
-    ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
-    payload: Value,
-) -> Result<(), DabError> {
-    ws_stream
-        .send(Message::Text(payload.to_string()))
-        .await
-        .map_err(|e| DabError::Err500(format!("Failed to send message: {}", e)))
-}
+        self.rdk_request_impl::<RdkNullParams, R>(method, None)
+    }
 
-pub async fn ws_receive(
-    ws_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
-) -> Result<Value, DabError> {
-    match timeout(Duration::from_secs(10), ws_stream.next()).await {
-        Ok(Some(Ok(message))) => {
-            if let Message::Text(text) = message {
-                serde_json::from_str(&text)
-                    .map_err(|e| DabError::Err500(format!("Invalid JSON: {}", e)))
-            } else {
-                Err(DabError::Err500("Received a non-text message".to_string()))
-            }
-        }
-        Ok(Some(Err(e))) => Err(DabError::Err500(format!("Error reading message: {:?}", e))),
-        Ok(None) => Err(DabError::Err500(
-            "The WebSocket stream has been closed by the server".to_string(),
-        )),
-        Err(_) => Err(DabError::Err500(
-            "Timeout occurred while waiting for a response".to_string(),
-        )),
+    pub fn rdk_request_with_params<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<R, DabError> {
+        self.rdk_request_impl(method, Some(params))
     }
-}
 
-#[allow(dead_code)]
-#[derive(Deserialize)]
-pub struct RdkResponse<T> {
-    pub jsonrpc: String,
-    pub id: i32,
-    pub result: T,
-}
+    fn rdk_request_impl<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Option<P>,
+    ) -> Result<R, DabError> {
+        #[derive(Serialize)]
+        struct RdkRequest<P> {
+            jsonrpc: String,
+            id: i32,
+            method: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            params: Option<P>,
+        }
 
-#[allow(dead_code)]
-#[derive(Deserialize)]
-pub struct RdkResult {
-    success: bool,
-}
+        let request = RdkRequest {
+            jsonrpc: "2.0".into(),
+            id: self.next_id(),
+            method: method.into(),
+            params,
+        };
+        let json_string = serde_json::to_string(&request).unwrap();
+        let response = self.http_post(json_string)?;
 
-pub type RdkResponseSimple = RdkResponse<RdkResult>;
+        let val: serde_json::Value = match serde_json::from_str(&response) {
+            Ok(val) => val,
+            Err(e) => return Err(DabError::Err500(e.to_string())),
+        };
 
-pub fn rdk_request<R: DeserializeOwned>(method: &str) -> Result<R, DabError> {
-    #[derive(Serialize)]
-    struct RdkNullParams {}
+        if val["error"] != serde_json::Value::Null {
+            return Err(DabError::Err500(
+                val["error"]["message"].as_str().unwrap().into(),
+            ));
+        } else if !val["result"].is_null() && val["result"]["success"].is_boolean() {
+            if !val["result"]["success"].as_bool().unwrap() {
+                return Err(DabError::Err500(format!("{} failed", method)));
+            }
+        }
 
-    rdk_request_impl::<RdkNullParams, R>(method, None)
-}
+        let res: R = match serde_json::from_value(val) {
+            Ok(res) => res,
+            Err(e) => return Err(DabError::Err500(e.to_string())),
+        };
 
-pub fn rdk_request_with_params<P: Serialize, R: DeserializeOwned>(
-    method: &str,
-    params: P,
-) -> Result<R, DabError> {
-    rdk_request_impl(method, Some(params))
-}
+        Ok(res)
+    }
 
-fn rdk_request_impl<P: Serialize, R: DeserializeOwned>(
-    method: &str,
-    params: Option<P>,
-) -> Result<R, DabError> {
-    #[derive(Serialize)]
-    struct RdkRequest<P> {
-        jsonrpc: String,
-        id: i32,
-        method: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        params: Option<P>,
+    // Function to activate a service.
+    // Parameters: service: The service to activate.
+    // Returns Ok on success else DabError.
+    pub fn service_activate(&self, service: String) -> Result<(), DabError> {
+        self.change_service_state(&service, "activate", "activated")
+            .map_err(|_| DabError::Err500(format!("Failed to activate service '{}'.", service)))
     }
 
-    static mut JSONRPC_ID: i32 = 1;
+    // Function to deactivate a service.
+    // Parameters: service: The service to deactivate.
+    // Returns Ok on success else DabError.
+    #[allow(dead_code)]
+    pub fn service_deactivate(&self, service: String) -> Result<(), DabError> {
+        self.change_service_state(&service, "deactivate", "deactivated")
+            .map_err(|_| DabError::Err500(format!("Failed to deactivate service '{}'.", service)))
+    }
 
-    let id;
+    fn change_service_state(&self, service: &str, action: &str, state: &str) -> Result<(), DabError> {
+        // Without the event bus, e.g. when Thunder refuses the WebSocket, the state is polled.
+        let state_changes = match self.events().and_then(|events| events.subscribe("Controller.1", "statechange")) {
+            Ok(state_changes) => Some(state_changes),
+            Err(e) => {
+                println!("Polling the state of {}, without Thunder events: {:?}", service, e);
+                None
+            }
+        };
+        //#########Controller.1.activate / Controller.1.deactivate#########
+        let method = format!("Controller.1.{}", action);
+        let payload = json!({
+            "jsonrpc":"2.0",
+            "id":self.next_id(),
+            "method":method,
+            "params":{
+                "callsign":service
+            }
+        }).to_string();
+        let response = self.http_post(payload)?;
+        let response_value: serde_json::Value = serde_json::from_str(&response)
+            .map_err(|e| DabError::Err500(format!("Failed to parse response: {}", e)))?;
+        if response_value.get("result").is_none() {
+            return Err(DabError::Err500(format!("Key 'result' not found in response for method '{}'.", method)));
+        }
+        self.wait_for_service_state(state_changes.as_ref(), service, state)
+    }
 
-    unsafe {
-        id = JSONRPC_ID;
-        JSONRPC_ID += 1;
+    // Controller notifies the "statechange" once the plugin is (de)activated.
+    fn wait_for_service_state(
+        &self,
+        state_changes: Option<&Subscription<ServiceStateChange>>,
+        service: &str,
+        state: &str,
+    ) -> Result<(), DabError> {
+        if self.get_service_state(service)? == state {
+            return Ok(());
+        }
+        if let Some(state_changes) = state_changes {
+            return state_changes
+                .wait_for(SERVICE_STATE_TIMEOUT, |change| {
+                    change.callsign == service && change.state.to_lowercase() == state
+                })
+                .map(|_| ());
+        }
+        let deadline = time::Instant::now() + SERVICE_STATE_TIMEOUT;
+        while time::Instant::now() < deadline {
+            std::thread::sleep(SERVICE_STATE_POLL_INTERVAL);
+            if self.get_service_state(service)? == state {
+                return Ok(());
+            }
+        }
+        Err(DabError::Err500(format!("Timed out waiting for {} to be {}.", service, state)))
     }
 
-    let request = RdkRequest {
-        jsonrpc: "2.0".into(),
-        id,
-        method: method.into(),
-        params,
-    };
-    let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
+    // Parameters: service: The service to check the state of.
+    // Returns the state of the service:"unavailable/deactivated/deactivation/activated/activation/precondition/hibernated/destroyed"
+    // on success else DabError.
+    pub fn get_service_state(&self, service: &str) -> Result<String, DabError> {
+        let method = format!("Controller.1.status@{service}");
+        let response = self.rdk_request::<serde_json::Value>(&method)?;
+        let state = response["result"][0]["state"]
+            .as_str()
+            .ok_or(DabError::Err500(format!("Key 'state' not found in response for method '{}'.", method)))?;
+        Ok(state.to_string().to_lowercase().clone())
+    }
 
-    let val: serde_json::Value = match serde_json::from_str(&response) {
-        Ok(val) => val,
-        Err(e) => return Err(DabError::Err500(e.to_string())),
-    };
+    // Parameters: service: The service to check the availability of.
+    // Returns true if the service is available else false on success else DabError.
+    pub fn service_is_available(&self, service: &str) -> Result<bool, DabError> {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Status {
+            autostart: bool,
+            callsign: String,
+        }
 
-    if val["error"] != serde_json::Value::Null {
-        return Err(DabError::Err500(
-            val["error"]["message"].as_str().unwrap().into(),
-        ));
-    } else if !val["result"].is_null() && val["result"]["success"].is_boolean() {
-        if !val["result"]["success"].as_bool().unwrap() {
-            return Err(DabError::Err500(format!("{} failed", method)));
+        match self.rdk_request::<RdkResponse<Vec<Status>>>(format!("Controller.1.status@{service}").as_str())
+        {
+            Err(message) => {
+                let error_message = match &message {
+                    DabError::Err400(msg) => msg,
+                    DabError::Err500(msg) => msg,
+                    DabError::Err501(msg) => msg,
+                };
+
+                if error_message == "ERROR_UNKNOWN_KEY" {
+                    return Ok(false);
+                }
+                return Err(message);
+            }
+            Ok(_) => return Ok(true),
         }
     }
 
-    let res: R = match serde_json::from_value(val) {
-        Ok(res) => res,
-        Err(e) => return Err(DabError::Err500(e.to_string())),
-    };
+    // Function to get thunder property value. Properties are read-only and will always return a valid value on API success.
+    // Parameters: method_name: The method name to call, key_name: The key to be matched in the response.
+    // Returns the value of the key as String on success else DabError.
+    pub fn get_thunder_property(&self, method_name: &str, key_name: &str) -> Result<String, DabError> {
+        let json_string = format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"{}\"}}", self.next_id(), method_name);
+        let response = self.http_post(json_string)?;
+        let response_value: serde_json::Value = serde_json::from_str(&response).map_err(|e| DabError::Err500(format!("Failed to parse response: {}", e)))?;
+        let result = response_value.get("result").ok_or(DabError::Err500(format!("Key 'result' not found in response for method '{}'.", method_name)))?;
+        if result.is_null() {
+            return Err(DabError::Err500(format!("Key 'result' is null in response for method '{}'.", method_name)));
+        }
 
-    Ok(res)
-}
+        let value = if !key_name.is_empty() {
+            result
+                .get(key_name)
+                .ok_or(DabError::Err500(format!("Key '{}' not found in response for method '{}'.", key_name, method_name)))?
+        } else {
+            result
+        };
 
-// Function to activate a service.
-// Parameters: service: The service to activate.
-// Returns Ok on success else DabError.
-pub fn service_activate(service: String) -> Result<(), DabError> {
-    //#########Controller.1.activate#########
-    let activate_payload = json!({
-        "jsonrpc":"2.0",
-        "id":1,
-        "method":"Controller.1.activate",
-        "params":{
-            "callsign":service.clone()
-        }
-    }).to_string();
-    let response = http_post(activate_payload)?;
-    let response_value: serde_json::Value = serde_json::from_str(&response)
-        .map_err(|e| DabError::Err500(format!("Failed to parse response: {}", e)))?;
-    if response_value.get("result").is_none() {
-        return Err(DabError::Err500(format!("Key 'result' not found in response for method 'Controller.1.activate'.")));
-    }
-    thread::sleep(time::Duration::from_millis(200));
-    if get_service_state(service.as_str())?.to_lowercase() != "activated" {
-        return Err(DabError::Err500(format!("Failed to activate service '{}' after 200ms.", service)));
-    }
-    Ok(())
+        match value {
+            serde_json::Value::String(s) => Ok(s.clone()),
+            serde_json::Value::Number(n) => Ok(n.to_string()),
+            serde_json::Value::Object(o) => {
+                serde_json::to_string(o)
+                    .map_err(|_| DabError::Err500(format!("Failed to serialize object for key '{}'.", key_name)))
+            },
+            _ => Err(DabError::Err500(format!("Unsupported type for key '{}' in response.", key_name))),
+        }
+    }
 }
 
-// Function to deactivate a service.
-// Parameters: service: The service to deactivate.
-// Returns Ok on success else DabError.
-pub fn service_deactivate(service: String) -> Result<(), DabError> {
-    //#########Controller.1.deactivate#########
-    let activate_payload = json!({
-        "jsonrpc":"2.0",
-        "id":1,
-        "method":"Controller.1.deactivate",
-        "params":{
-            "callsign":service.clone()
-        }
-    }).to_string();
-    let response = http_post(activate_payload)?;
-    let response_value: serde_json::Value = serde_json::from_str(&response)
-        .map_err(|e| DabError::Err500(format!("Failed to parse response: {}", e)))?;
-    if response_value.get("result").is_none() {
-        return Err(DabError::Err500(format!("Key 'result' not found in response for method 'Controller.1.activate'.")));
-    }
-    thread::sleep(time::Duration::from_millis(200));
-    if get_service_state(service.as_str())?.to_lowercase() != "deactivated" {
-        return Err(DabError::Err500(format!("Failed to deactivate service '{}' after 200ms.", service)));
-    }
-    Ok(())
+pub fn print_lifecycle_timeouts() {
+    for app in APP_LIFECYCLE_TIMEOUTS.keys() {
+        for (key, value) in APP_LIFECYCLE_TIMEOUTS.get(app).unwrap() {
+            println!("{:<15} - {:<30} = {:>5}ms.", app, key, value);
+        }
+    }
 }
 
-// Parameters: service: The service to check the state of.
-// Returns the state of the service:"unavailable/deactivated/deactivation/activated/activation/precondition/hibernated/destroyed"
-// on success else DabError.
-pub fn get_service_state(service: &str) -> Result<String, DabError> {
-    let method = format!("Controller.1.status@{service}");
-    let response = rdk_request::<serde_json::Value>(&method)?;
-    let state = response["result"][0]["state"]
+pub fn get_device_id(thunder: &ThunderClient) -> Result<String, DabError> {
+    if let Some(device_id) = thunder.device_id.get() {
+        return Ok(device_id.clone());
+    }
+    let device_id = request_device_id(thunder)?;
+    Ok(thunder.device_id.get_or_init(|| device_id).clone())
+}
+
+fn request_device_id(thunder: &ThunderClient) -> Result<String, DabError> {
+    let json_string = format!(
+        "{{\"jsonrpc\":\"2.0\",\"id\":{},\"method\":\"org.rdk.System.getDeviceInfo\",\"params\":{{\"params\":[\"estb_mac\"]}}}}",
+        thunder.next_id()
+    );
+    let response = thunder.http_post(json_string)?;
+    let rdkresponse: serde_json::Value = serde_json::from_str(&response)
+        .map_err(|e| DabError::Err500(format!("Invalid org.rdk.System.getDeviceInfo response: {}", e)))?;
+    let device_id = rdkresponse["result"]["estb_mac"]
         .as_str()
-        .ok_or(DabError::Err500(format!("Key 'state' not found in response for method '{}'.", method)))?;
-    Ok(state.to_string().to_lowercase().clone())
+        .ok_or(DabError::Err500(
+            "RDK Error: org.rdk.System.getDeviceInfo.result.estb_mac not found".to_string(),
+        ))?;
+    Ok(device_id.replace(":", "").to_string())
 }
 
-// Parameters: service: The service to check the availability of.
-// Returns true if the service is available else false on success else DabError.
-pub fn service_is_available(service: &str) -> Result<bool, DabError> {
-    #[allow(dead_code)]
-    #[derive(Deserialize)]
-    struct Status {
-        autostart: bool,
-        callsign: String,
-    }
-
-    match rdk_request::<RdkResponse<Vec<Status>>>(format!("Controller.1.status@{service}").as_str())
-    {
-        Err(message) => {
-            let error_message = match &message {
-                DabError::Err400(msg) => msg,
-                DabError::Err500(msg) => msg,
-                DabError::Err501(msg) => msg,
-            };
+#[allow(dead_code)]
+#[derive(Deserialize)]
+pub struct RdkResponse<T> {
+    pub jsonrpc: String,
+    pub id: i32,
+    pub result: T,
+}
 
-            if error_message == "ERROR_UNKNOWN_KEY" {
-                return Ok(false);
-            }
-            return Err(message);
-        }
-        Ok(_) => return Ok(true),
-    }
+#[allow(dead_code)]
+#[derive(Deserialize)]
+pub struct RdkResult {
+    success: bool,
 }
 
+pub type RdkResponseSimple = RdkResponse<RdkResult>;
+
 // DAB key codes are listed here:
 // https://github.com/device-automation-bus/dab-specification-2.0/blob/main/DAB.md#54-input
 
@@ -391,7 +639,18 @@ pub fn service_is_available(service: &str) -> Result<bool, DabError> {
 #[derive(Deserialize, Debug)]
 struct Settings {
     supported_languages: Option<Vec<String>>,
-    audio_volume_range: Option<AudioVolume>
+    audio_volume_range: Option<AudioVolume>,
+    telemetry_metrics: Option<Vec<String>>,
+    sleep_policy: Option<SleepPolicy>
+}
+
+// What the operations that need the device awake do when it is in standby or asleep.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SleepPolicy {
+    #[default]
+    Wake,
+    Reject,
 }
 
 lazy_static! {
@@ -413,7 +672,9 @@ lazy_static! {
         println!("Using default settings.");
         Settings {
             supported_languages: None,
-            audio_volume_range: None
+            audio_volume_range: None,
+            telemetry_metrics: None,
+            sleep_policy: None
         }
 
     };
@@ -493,63 +754,61 @@ lazy_static! {
 
 // Static device info; no need to panic or break runtime. Implementation is based on the assumption
 // that platform response will be constant for a specific build.
-lazy_static! {
-    static ref RDK_DEVICE_INFO: HashMap<String, String> = {
-        let mut rdk_device_info = HashMap::new();
-        match get_thunder_property("DeviceInfo.make", "make") {
-            Ok(make) => { rdk_device_info.insert(String::from("manufacturer"), String::from(make)); },
-            Err(_err) => {
-                if cfg!(debug_assertions) {
-                    rdk_device_info.insert(String::from("manufacturer"), String::from("Unknown-manufacturer"));
-                }
-            },
-        };
-        match get_thunder_property("DeviceInfo.modelid", "sku") {
-            Ok(model) => { rdk_device_info.insert(String::from("model"), String::from(model)); },
-            Err(_err) => { 
-                if cfg!(debug_assertions) {
-                    rdk_device_info.insert(String::from("model"), String::from("Unknown-model"));
-                }
-            },
-        };
-        match get_thunder_property("DeviceInfo.serialnumber", "serialnumber") {
-            Ok(serialnumber) => { rdk_device_info.insert(String::from("serialnumber"), String::from(serialnumber)); },
-            Err(_err) => {
-                if cfg!(debug_assertions) {
-                    rdk_device_info.insert(String::from("serialnumber"), String::from("Unknown-serialnumber"));
-                }
-            },
-        };
-        match get_thunder_property("DeviceInfo.socname", "socname") {
-            Ok(socname) => { rdk_device_info.insert(String::from("chipset"), String::from(socname)); },
-            Err(_err) => {
-                eprintln!("Unable to retrieve chipset from DeviceInfo, trying legacy DeviceIdentification.");
-                match get_thunder_property("DeviceIdentification.deviceidentification", "chipset") {
-                    Ok(chipset) => { rdk_device_info.insert(String::from("chipset"), String::from(chipset)); },
-                    Err(_err) => {
-                        if cfg!(debug_assertions) {
-                            rdk_device_info.insert(String::from("chipset"), String::from("Unknown-chipset"));
-                        }
-                    },
-                };
-            },
-        };
-        match get_thunder_property("DeviceInfo.firmwareversion", "imagename") {
-            Ok(firmwareversion) => { rdk_device_info.insert(String::from("firmwareversion"), String::from(firmwareversion)); },
-            Err(_err) => {
-                if cfg!(debug_assertions) {
-                    rdk_device_info.insert(String::from("firmwareversion"), String::from("Unknown-FWVersion"));
-                }
-            },
-        };
-        rdk_device_info
+fn load_rdk_device_info(thunder: &ThunderClient) -> HashMap<String, String> {
+    let mut rdk_device_info = HashMap::new();
+    match thunder.get_thunder_property("DeviceInfo.make", "make") {
+        Ok(make) => { rdk_device_info.insert(String::from("manufacturer"), String::from(make)); },
+        Err(_err) => {
+            if cfg!(debug_assertions) {
+                rdk_device_info.insert(String::from("manufacturer"), String::from("Unknown-manufacturer"));
+            }
+        },
+    };
+    match thunder.get_thunder_property("DeviceInfo.modelid", "sku") {
+        Ok(model) => { rdk_device_info.insert(String::from("model"), String::from(model)); },
+        Err(_err) => { 
+            if cfg!(debug_assertions) {
+                rdk_device_info.insert(String::from("model"), String::from("Unknown-model"));
+            }
+        },
+    };
+    match thunder.get_thunder_property("DeviceInfo.serialnumber", "serialnumber") {
+        Ok(serialnumber) => { rdk_device_info.insert(String::from("serialnumber"), String::from(serialnumber)); },
+        Err(_err) => {
+            if cfg!(debug_assertions) {
+                rdk_device_info.insert(String::from("serialnumber"), String::from("Unknown-serialnumber"));
+            }
+        },
+    };
+    match thunder.get_thunder_property("DeviceInfo.socname", "socname") {
+        Ok(socname) => { rdk_device_info.insert(String::from("chipset"), String::from(socname)); },
+        Err(_err) => {
+            eprintln!("Unable to retrieve chipset from DeviceInfo, trying legacy DeviceIdentification.");
+            match thunder.get_thunder_property("DeviceIdentification.deviceidentification", "chipset") {
+                Ok(chipset) => { rdk_device_info.insert(String::from("chipset"), String::from(chipset)); },
+                Err(_err) => {
+                    if cfg!(debug_assertions) {
+                        rdk_device_info.insert(String::from("chipset"), String::from("Unknown-chipset"));
+                    }
+                },
+            };
+        },
     };
+    match thunder.get_thunder_property("DeviceInfo.firmwareversion", "imagename") {
+        Ok(firmwareversion) => { rdk_device_info.insert(String::from("firmwareversion"), String::from(firmwareversion)); },
+        Err(_err) => {
+            if cfg!(debug_assertions) {
+                rdk_device_info.insert(String::from("firmwareversion"), String::from("Unknown-FWVersion"));
+            }
+        },
+    };
+    rdk_device_info
 }
 
 // Parameter: propertyname: The property to get the value of.
 // Returns the value of the property on success else DabError.
-pub fn get_rdk_device_info(propertyname: &str) -> Result<String, DabError> {
-    match RDK_DEVICE_INFO.get(propertyname) {
+pub fn get_rdk_device_info(thunder: &ThunderClient, propertyname: &str) -> Result<String, DabError> {
+    match thunder.device_info.get_or_init(|| load_rdk_device_info(thunder)).get(propertyname) {
         Some(val) => Ok(val.clone()),
         None => {
             let error_message = DabError::Err500(format!("No match for property {propertyname}."));
@@ -558,10 +817,6 @@ pub fn get_rdk_device_info(propertyname: &str) -> Result<String, DabError> {
     }
 }
 
-pub fn get_ip_address() -> String {
-    unsafe { DEVICE_ADDRESS.clone() }
-}
-
 pub fn get_rdk_keys() -> Vec<String> {
     RDK_KEYMAP
         .keys()
@@ -590,27 +845,64 @@ pub fn rdk_sound_mode_to_dab(mode: &String) -> Option<AudioOutputMode> {
 
 // Telemetry operations
 
-pub fn get_device_memory() -> Result<u32, DabError> {
+pub fn get_device_memory(thunder: &ThunderClient) -> Result<u32, DabError> {
     // Both properties are in bytes; convert to KB for DAB.
-    let free_ram_bytes = get_thunder_property("DeviceInfo.systeminfo", "freeram")?;
+    let free_ram_bytes = thunder.get_thunder_property("DeviceInfo.systeminfo", "freeram")?;
     let free_ram_bytes = free_ram_bytes.parse::<u32>()
         .map_err(|_| DabError::Err500("Failed to parse free RAM".to_string()))? / 1024;
 
-    let total_ram_bytes = get_thunder_property("DeviceInfo.systeminfo", "totalram")?;
+    let total_ram_bytes = thunder.get_thunder_property("DeviceInfo.systeminfo", "totalram")?;
     let total_ram_bytes = total_ram_bytes.parse::<u32>()
         .map_err(|_| DabError::Err500("Failed to parse total RAM".to_string()))? / 1024;
 
     Ok(total_ram_bytes - free_ram_bytes)
 }
 
-pub fn get_device_cpu() -> Result<u32, DabError> {
-    let cpu_usage = get_thunder_property("DeviceInfo.systeminfo", "cpuload")?;
+pub fn get_device_cpu(thunder: &ThunderClient) -> Result<u32, DabError> {
+    let cpu_usage = thunder.get_thunder_property("DeviceInfo.systeminfo", "cpuload")?;
     let cpu_usage = cpu_usage.parse::<u32>()
         .map_err(|_| DabError::Err500("Failed to parse CPU usage".to_string()))?;
 
     Ok(cpu_usage)
 }
 
+pub fn get_app_state(thunder: &ThunderClient, app_id: &str) -> Result<String, DabError> {
+    get_dab_app_state(thunder, registry::resolve(app_id).callsign)
+}
+
+pub fn get_app_memory(thunder: &ThunderClient, app_id: &str) -> Result<u32, DabError> {
+    #[derive(Serialize)]
+    struct MonitorStatusParams {
+        callsign: String,
+    }
+
+    let callsign = registry::resolve(app_id).callsign;
+    let response: Value = thunder.rdk_request_with_params(
+        "Monitor.1.status",
+        MonitorStatusParams { callsign: callsign.clone() },
+    )?;
+    // Resident memory of the plugin process in bytes; convert to KB for DAB.
+    response["result"][0]["measurements"]["resident"]["last"]
+        .as_u64()
+        .map(|resident_bytes| (resident_bytes / 1024) as u32)
+        .ok_or_else(|| DabError::Err500(format!("No memory measurement of {}", callsign)))
+}
+
+pub fn get_app_cpu(thunder: &ThunderClient, app_id: &str) -> Result<u32, DabError> {
+    let callsign = registry::resolve(app_id).callsign;
+    let response: Value = thunder.rdk_request("org.rdk.RDKShell.getSystemResourceInfo")?;
+    response["result"]["types"]
+        .as_array()
+        .and_then(|clients| {
+            clients.iter().find(|client| {
+                client["callsign"].as_str().is_some_and(|name| name.eq_ignore_ascii_case(&callsign))
+            })
+        })
+        .and_then(|client| client["cpu"].as_u64())
+        .map(|cpu_usage| cpu_usage as u32)
+        .ok_or_else(|| DabError::Err500(format!("No CPU usage of {}", callsign)))
+}
+
 // Read platform override JSON configs from file
 // Optional override configuration; do not panic or break runtime.
 pub fn read_platform_config_json(file_path: &str) -> Result<String, DabError> {
@@ -630,37 +922,6 @@ pub fn read_platform_config_json(file_path: &str) -> Result<String, DabError> {
     Ok(file_content)
 }
 
-// Function to get thunder property value. Properties are read-only and will always return a valid value on API success.
-// Parameters: method_name: The method name to call, key_name: The key to be matched in the response.
-// Returns the value of the key as String on success else DabError.
-pub fn get_thunder_property(method_name: &str, key_name: &str) -> Result<String, DabError> {
-    let json_string = format!("{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"{}\"}}", method_name);
-    let response = http_post(json_string)?;
-    let response_value: serde_json::Value = serde_json::from_str(&response).map_err(|e| DabError::Err500(format!("Failed to parse response: {}", e)))?;
-    let result = response_value.get("result").ok_or(DabError::Err500(format!("Key 'result' not found in response for method '{}'.", method_name)))?;
-    if result.is_null() {
-        return Err(DabError::Err500(format!("Key 'result' is null in response for method '{}'.", method_name)));
-    }
-
-    let value = if !key_name.is_empty() {
-        result
-            .get(key_name)
-            .ok_or(DabError::Err500(format!("Key '{}' not found in response for method '{}'.", key_name, method_name)))?
-    } else {
-        result
-    };
-
-    match value {
-        serde_json::Value::String(s) => Ok(s.clone()),
-        serde_json::Value::Number(n) => Ok(n.to_string()),
-        serde_json::Value::Object(o) => {
-            serde_json::to_string(o)
-                .map_err(|_| DabError::Err500(format!("Failed to serialize object for key '{}'.", key_name)))
-        },
-        _ => Err(DabError::Err500(format!("Unsupported type for key '{}' in response.", key_name))),
-    }
-}
-
 // ############################### APP Lifecycle Time Configs ###############################
 
 type TimeoutMap = HashMap<String, u64>;
@@ -670,14 +931,11 @@ lazy_static! {
     static ref APP_LIFECYCLE_TIMEOUTS: LifecycleTimeouts = {
         let mut app_lifecycle_timeouts = LifecycleTimeouts::new();
 
-        app_lifecycle_timeouts.insert("youtube".to_string(), {
-            let mut timeouts = TimeoutMap::new();
-            timeouts.insert("cold_launch_timeout_ms".to_string(), 6000);
-            timeouts.insert("resume_launch_timeout_ms".to_string(), 3000);
-            timeouts.insert("exit_to_destroy_timeout_ms".to_string(), 2500);
-            timeouts.insert("exit_to_background_timeout_ms".to_string(), 2000);
-            timeouts
-        });
+        for app in registry::apps() {
+            if !app.lifecycle_timeouts.is_empty() {
+                app_lifecycle_timeouts.insert(app.app_id.to_lowercase(), app.lifecycle_timeouts.clone());
+            }
+        }
 
         match read_platform_config_json("/opt/dab_platform_app_lifecycle.json") {
             /* File Format Reference:
@@ -700,9 +958,10 @@ lazy_static! {
                 match serde_json::from_str::<HashMap<String, HashMap<String, u64>>>(&json_file) {
                     Ok(app_lifecycle_config) => {
                         for (app_id, timeout_map) in app_lifecycle_config {
-                            if app_id == "youtube" || app_id == "uk.co.bbc.iplayer" || app_id == "netflix" || app_id == "primevideo" {
-                                app_lifecycle_timeouts.insert(app_id, timeout_map);
-                            }
+                            app_lifecycle_timeouts
+                                .entry(app_id.to_lowercase())
+                                .or_default()
+                                .extend(timeout_map);
                         }
                         println!("Imported platform specified app lifetime configuration file also.");
                     }
@@ -744,3 +1003,14 @@ pub fn get_audio_volume_range() -> AudioVolume {
         .clone()
         .unwrap_or_else(|| AudioVolume { min: 0, max: 100 })
 }
+
+pub fn get_sleep_policy() -> SleepPolicy {
+    SETTINGS.sleep_policy.unwrap_or_default()
+}
+
+pub fn get_telemetry_metrics() -> Vec<String> {
+    SETTINGS
+        .telemetry_metrics
+        .clone()
+        .unwrap_or_else(|| telemetry::DEFAULT_METRICS.iter().map(|metric| metric.to_string()).collect())
+}
@@ -0,0 +1,454 @@
+// Scriptable stand-in for Thunder, serving the JSON-RPC interface over HTTP and
+// WebSocket on a free local port. Tests tell it how to answer each method, inspect
+// the calls it received and push events to the WebSocket clients registered for them.
+
+use crate::device::rdk::interface::{SleepPolicy, ThunderClient, ThunderTokenSource};
+use crate::device::rdk::RdkPlatform;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+const THUNDER_ADDRESS: &str = "127.0.0.1";
+
+// How the mock answers a request.
+pub enum Reply {
+    Result(Value),
+    Error(i64, String),
+    // Sent as is, to simulate malformed responses.
+    Raw(String),
+}
+
+type Handler = Arc<dyn Fn(&Value) -> Reply + Send + Sync>;
+
+#[derive(Clone)]
+struct Event {
+    callsign: String,
+    event: String,
+    params: Value,
+}
+
+#[derive(Default)]
+struct Script {
+    handlers: HashMap<String, Handler>,
+    calls: Vec<(String, Value)>,
+    files: HashMap<String, Vec<u8>>,
+    // SecurityAgent token required by the requests.
+    token: Option<String>,
+    // Closes the WebSocket handshakes, like a Thunder without events.
+    refuse_websockets: bool,
+}
+
+struct Server {
+    script: Mutex<Script>,
+    events: broadcast::Sender<Event>,
+    // Closes the WebSocket connections.
+    disconnect: broadcast::Sender<()>,
+}
+
+// Thunder accepts both "callsign.method" and "callsign.1.method".
+fn normalize_method(method: &str) -> String {
+    method
+        .split('.')
+        .filter(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+// Tests run at the same time, so each capture server takes a free port.
+fn test_platform(thunder: ThunderClient) -> RdkPlatform {
+    let mut platform = RdkPlatform::new(thunder);
+    platform.capture_port = 0;
+    platform
+}
+
+// Pushes events to the WebSocket clients; can be moved into a handler.
+#[derive(Clone)]
+pub struct EventSender {
+    events: broadcast::Sender<Event>,
+}
+
+impl EventSender {
+    pub fn send(&self, callsign: &str, event: &str, params: Value) {
+        let _ = self.events.send(Event {
+            callsign: normalize_method(callsign),
+            event: event.to_string(),
+            params,
+        });
+    }
+}
+
+pub struct MockThunder {
+    server: Arc<Server>,
+    port: u16,
+    platform: RdkPlatform,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl MockThunder {
+    // Starts a mock of a device that is on, and an RDK platform talking to it.
+    pub fn start() -> MockThunder {
+        let (events, _) = broadcast::channel(64);
+        let (disconnect, _) = broadcast::channel(1);
+        let server = Arc::new(Server {
+            script: Mutex::new(Script::default()),
+            events,
+            disconnect,
+        });
+        let listener = std::net::TcpListener::bind((THUNDER_ADDRESS, 0)).unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let accept_server = server.clone();
+        thread::Builder::new()
+            .name("MockThunder".to_string())
+            .spawn(move || {
+                let rt = Runtime::new().unwrap();
+                rt.block_on(async {
+                    let listener = TcpListener::from_std(listener).unwrap();
+                    let accept = async {
+                        while let Ok((stream, _)) = listener.accept().await {
+                            tokio::spawn(serve_connection(accept_server.clone(), stream));
+                        }
+                    };
+                    tokio::select! {
+                        _ = accept => {},
+                        _ = shutdown_rx => {},
+                    }
+                });
+            })
+            .unwrap();
+
+        let url = format!("http://{}:{}/jsonrpc", THUNDER_ADDRESS, port);
+        let mock = MockThunder {
+            server,
+            port,
+            platform: test_platform(ThunderClient::new(&url, false).unwrap()),
+            _shutdown: shutdown_tx,
+        };
+        mock.on_result("org.rdk.System.getPowerState", json!({"powerState": "ON", "success": true}));
+        mock
+    }
+
+    pub fn platform(&self) -> &RdkPlatform {
+        &self.platform
+    }
+
+    // Reconnects the platform to the mock, with a token for the SecurityAgent.
+    pub fn use_token_source(&mut self, token_source: ThunderTokenSource) {
+        let url = format!("http://{}:{}/jsonrpc", THUNDER_ADDRESS, self.port);
+        let thunder = ThunderClient::new(&url, false).unwrap();
+        self.platform = test_platform(thunder.with_token_source(token_source));
+    }
+
+    pub fn use_sleep_policy(&mut self, policy: SleepPolicy) {
+        self.platform.sleep_policy = policy;
+    }
+
+    pub fn use_upload_dir(&mut self, upload_dir: PathBuf) {
+        self.platform.upload_dir = Some(upload_dir);
+    }
+
+    // Closes the WebSocket handshakes, so Thunder events are unavailable.
+    pub fn refuse_websockets(&self) {
+        lock(&self.server.script).refuse_websockets = true;
+    }
+
+    // Rejects the requests without this token, like Thunder with the SecurityAgent
+    // enabled. SecurityAgent.createtoken is always allowed.
+    pub fn require_token(&self, token: &str) {
+        lock(&self.server.script).token = Some(token.to_string());
+    }
+
+    pub fn handle<F>(&self, method: &str, handler: F)
+    where
+        F: Fn(&Value) -> Reply + Send + Sync + 'static,
+    {
+        lock(&self.server.script)
+            .handlers
+            .insert(normalize_method(method), Arc::new(handler));
+    }
+
+    pub fn on_result(&self, method: &str, result: Value) {
+        self.handle(method, move |_| Reply::Result(result.clone()));
+    }
+
+    pub fn on_error(&self, method: &str, code: i64, message: &str) {
+        let message = message.to_string();
+        self.handle(method, move |_| Reply::Error(code, message.clone()));
+    }
+
+    pub fn on_raw(&self, method: &str, body: &str) {
+        let body = body.to_string();
+        self.handle(method, move |_| Reply::Raw(body.clone()));
+    }
+
+    // Serves a file over HTTP GET.
+    pub fn serve_file(&self, path: &str, content: &[u8]) {
+        lock(&self.server.script)
+            .files
+            .insert(path.to_string(), content.to_vec());
+    }
+
+    pub fn file_url(&self, path: &str) -> String {
+        format!("http://{}:{}{}", THUNDER_ADDRESS, self.port, path)
+    }
+
+    pub fn events(&self) -> EventSender {
+        EventSender {
+            events: self.server.events.clone(),
+        }
+    }
+
+    // Parameters of every call received for a method, in order.
+    // Drops the WebSocket clients, as when Thunder restarts.
+    pub fn disconnect_websockets(&self) {
+        let _ = self.server.disconnect.send(());
+    }
+
+    pub fn calls(&self, method: &str) -> Vec<Value> {
+        let method = normalize_method(method);
+        lock(&self.server.script)
+            .calls
+            .iter()
+            .filter(|(called, _)| *called == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+}
+
+fn dispatch(server: &Server, request: &str) -> String {
+    let request: Value = match serde_json::from_str(request) {
+        Ok(request) => request,
+        Err(_) => {
+            return json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": "Parse error"}})
+                .to_string()
+        }
+    };
+    let id = request["id"].clone();
+    let method = normalize_method(request["method"].as_str().unwrap_or_default());
+    let params = request["params"].clone();
+
+    let handler = {
+        let mut script = lock(&server.script);
+        script.calls.push((method.clone(), params.clone()));
+        script.handlers.get(&method).cloned()
+    };
+
+    let reply = match handler {
+        Some(handler) => handler(&params),
+        None if method.ends_with(".register") || method.ends_with(".unregister") => {
+            Reply::Result(json!(0))
+        }
+        None => Reply::Error(-32601, "Unknown method.".to_string()),
+    };
+
+    match reply {
+        Reply::Result(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string(),
+        Reply::Error(code, message) => {
+            json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+                .to_string()
+        }
+        Reply::Raw(body) => body,
+    }
+}
+
+// Waits for a complete request head, without consuming it, and returns its length.
+async fn peek_head(stream: &TcpStream, head: &mut [u8]) -> Option<usize> {
+    loop {
+        let len = match stream.peek(head).await {
+            Ok(0) | Err(_) => return None,
+            Ok(len) => len,
+        };
+        if let Some(pos) = head[..len].windows(4).position(|w| w == b"\r\n\r\n") {
+            return Some(pos + 4);
+        }
+        if len == head.len() {
+            return None;
+        }
+        stream.readable().await.ok()?;
+        tokio::task::yield_now().await;
+    }
+}
+
+async fn serve_connection(server: Arc<Server>, mut stream: TcpStream) {
+    let mut head = vec![0u8; 8192];
+    let head_len = match peek_head(&stream, &mut head).await {
+        Some(head_len) => head_len,
+        None => return,
+    };
+    // A WebSocket handshake is handed over untouched.
+    if String::from_utf8_lossy(&head[..head_len])
+        .to_lowercase()
+        .contains("upgrade: websocket")
+    {
+        if lock(&server.script).refuse_websockets {
+            return;
+        }
+        serve_websocket(server, stream).await;
+        return;
+    }
+
+    // Like Thunder, keep the connection open for the next requests.
+    while let Some(head_len) = peek_head(&stream, &mut head).await {
+        let request_head = String::from_utf8_lossy(&head[..head_len]).to_lowercase();
+        if !serve_http(&server, &mut stream, &request_head, head_len).await {
+            break;
+        }
+    }
+}
+
+// Returns whether the connection can be used for another request.
+async fn serve_http(server: &Server, stream: &mut TcpStream, head: &str, head_len: usize) -> bool {
+    let mut discard = vec![0u8; head_len];
+    if stream.read_exact(&mut discard).await.is_err() {
+        return false;
+    }
+    let content_length = head
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|len| len.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; content_length];
+    if stream.read_exact(&mut body).await.is_err() {
+        return false;
+    }
+
+    let request_line = head.lines().next().unwrap_or_default().to_string();
+    let (status, content_type, response) = if request_line.starts_with("post /jsonrpc") {
+        let request = String::from_utf8_lossy(&body).to_string();
+        let method = serde_json::from_str::<Value>(&request)
+            .map(|request| normalize_method(request["method"].as_str().unwrap_or_default()))
+            .unwrap_or_default();
+        if method == "SecurityAgent.createtoken" || is_authorized(server, head) {
+            let response = dispatch(server, &request);
+            ("200 OK", "application/json", response.into_bytes())
+        } else {
+            ("401 Unauthorized", "text/plain", Vec::new())
+        }
+    } else {
+        let path = request_line.split(' ').nth(1).unwrap_or_default().to_string();
+        match lock(&server.script).files.get(&path) {
+            Some(content) => ("200 OK", "application/octet-stream", content.clone()),
+            None => ("404 Not Found", "text/plain", Vec::new()),
+        }
+    };
+
+    let keep_alive = !head.contains("connection: close");
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: {}\r\n\r\n",
+        status,
+        content_type,
+        response.len(),
+        if keep_alive { "keep-alive" } else { "close" }
+    );
+    if stream.write_all(header.as_bytes()).await.is_err()
+        || stream.write_all(&response).await.is_err()
+    {
+        return false;
+    }
+    if !keep_alive {
+        let _ = stream.shutdown().await;
+    }
+    keep_alive
+}
+
+// The request head is lowercase.
+fn is_authorized(server: &Server, head: &str) -> bool {
+    let token = match &lock(&server.script).token {
+        Some(token) => token.to_lowercase(),
+        None => return true,
+    };
+    head.lines()
+        .find_map(|line| line.strip_prefix("authorization: bearer "))
+        .map(|bearer| bearer.trim() == token)
+        .unwrap_or(false)
+}
+
+async fn serve_websocket(server: Arc<Server>, stream: TcpStream) {
+    let handshake_server = server.clone();
+    // The error type is set by tungstenite.
+    #[allow(clippy::result_large_err)]
+    let check_token = move |request: &Request, response: Response| {
+        let head: String = request
+            .headers()
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value.to_str().unwrap_or_default()))
+            .collect();
+        if is_authorized(&handshake_server, &head.to_lowercase()) {
+            Ok(response)
+        } else {
+            let mut rejection = ErrorResponse::new(None);
+            *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(rejection)
+        }
+    };
+    let mut ws_stream = match tokio_tungstenite::accept_hdr_async(stream, check_token).await {
+        Ok(ws_stream) => ws_stream,
+        Err(_) => return,
+    };
+    let mut events = server.events.subscribe();
+    let mut disconnect = server.disconnect.subscribe();
+    // Event registrations of this client, with the id to prefix the event name with.
+    let mut registrations: HashMap<(String, String), Option<String>> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            message = ws_stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                if let Ok(request) = serde_json::from_str::<Value>(&text) {
+                    let method = normalize_method(request["method"].as_str().unwrap_or_default());
+                    let event = request["params"]["event"].as_str().unwrap_or_default().to_string();
+                    let id = request["params"]["id"].as_str().map(|id| id.to_string());
+                    if let Some(callsign) = method.strip_suffix(".register") {
+                        registrations.insert((callsign.to_string(), event), id);
+                    } else if let Some(callsign) = method.strip_suffix(".unregister") {
+                        registrations.remove(&(callsign.to_string(), event));
+                    }
+                }
+                let response = dispatch(&server, &text);
+                if ws_stream.send(Message::Text(response)).await.is_err() {
+                    break;
+                }
+            }
+            _ = disconnect.recv() => break,
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let method = match registrations.get(&(event.callsign.clone(), event.event.clone())) {
+                    Some(Some(id)) => format!("{}.{}", id, event.event),
+                    Some(None) => event.event.clone(),
+                    None => continue,
+                };
+                let notification = json!({"jsonrpc": "2.0", "method": method, "params": event.params});
+                if ws_stream.send(Message::Text(notification.to_string())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
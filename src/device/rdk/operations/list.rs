@@ -50,21 +50,21 @@ pub fn process(_dab_request: OperationsListRequest) -> Result<String, DabError>
         .operations
         .push("input/long-key-press".to_string());
     ResponseOperator.operations.push("output/image".to_string());
-    // ResponseOperator
-    //     .operations
-    //     .push("device-telemetry/start".to_string());
-    // ResponseOperator
-    //     .operations
-    //     .push("device-telemetry/stop".to_string());
+    ResponseOperator
+        .operations
+        .push("device-telemetry/start".to_string());
+    ResponseOperator
+        .operations
+        .push("device-telemetry/stop".to_string());
     // ResponseOperator
     //     .operations
     //     .push("device-telemetry/metrics".to_string());
-    // ResponseOperator
-    //     .operations
-    //     .push("app-telemetry/start".to_string());
-    // ResponseOperator
-    //     .operations
-    //     .push("app-telemetry/stop".to_string());
+    ResponseOperator
+        .operations
+        .push("app-telemetry/start".to_string());
+    ResponseOperator
+        .operations
+        .push("app-telemetry/stop".to_string());
     ResponseOperator
         .operations
         .push("health-check/get".to_string());
@@ -77,6 +77,24 @@ pub fn process(_dab_request: OperationsListRequest) -> Result<String, DabError>
         .operations
         .push("voice/send-text".to_string());
     ResponseOperator.operations.push("version".to_string());
+    #[cfg(feature = "2_1")]
+    {
+        ResponseOperator
+            .operations
+            .push("applications/install".to_string());
+        ResponseOperator
+            .operations
+            .push("applications/uninstall".to_string());
+        ResponseOperator
+            .operations
+            .push("applications/clear-data".to_string());
+        ResponseOperator
+            .operations
+            .push("system/power-mode/get".to_string());
+        ResponseOperator
+            .operations
+            .push("system/power-mode/set".to_string());
+    }
     // ResponseOperator
     //     .operations
     //     .push("system/language/list".to_string());
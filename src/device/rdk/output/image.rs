@@ -1,11 +1,9 @@
 use crate::dab::structs::CaptureScreenshotRequest;
-use crate::dab::structs::CaptureScreenshotResponse;
 use crate::dab::structs::DabError;
-use crate::device::rdk::interface::http_post;
-use crate::device::rdk::interface::{service_activate, get_service_state};
+use crate::device::rdk::interface::ThunderClient;
+use crate::device::screenshot;
 use serde::{Deserialize, Serialize};
 
-use base64::{engine::general_purpose, Engine as _};
 use bytes::Bytes;
 use hyper::server::Server;
 use hyper::service::{make_service_fn, service_fn};
@@ -14,31 +12,42 @@ use hyper::{Method, StatusCode};
 use local_ip_address::local_ip;
 use std::convert::Infallible;
 use std::net::SocketAddr;
-use std::thread;
+use std::path::Path;
 use tokio::runtime::Runtime;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::{self, Duration};
 
+// Port where org.rdk.ScreenCapture uploads the capture; device firewalls open it.
+pub const CAPTURE_PORT: u16 = 7878;
+
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: CaptureScreenshotRequest) -> Result<String, DabError> {
+pub fn process(
+    thunder: &ThunderClient,
+    _dab_request: CaptureScreenshotRequest,
+    upload_dir: Option<&Path>,
+    capture_port: u16,
+) -> Result<String, DabError> {
+    // Bad options and refused file destinations fail before the capture.
+    screenshot::validate(&_dab_request)?;
+    if let Some(destination) = _dab_request.destination.as_deref() {
+        if !screenshot::is_http(destination) {
+            screenshot::file_destination(destination, upload_dir)?;
+        }
+    }
+
     //######### Activate org.rdk.ScreenCapture #########
-    if get_service_state("org.rdk.ScreenCapture")? != "activated" {
-        service_activate("org.rdk.ScreenCapture".to_string())?;
-        thread::sleep(Duration::from_millis(500));
+    if thunder.get_service_state("org.rdk.ScreenCapture")? != "activated" {
+        thunder.service_activate("org.rdk.ScreenCapture".to_string())?;
     }
 
     let rt = Runtime::new().unwrap();
     rt.block_on(async {
-        let mut ResponseOperator = CaptureScreenshotResponse::default();
-        // *** Fill in the fields of the struct CaptureScreenshotResponse here ***
-
         //######### Enable the Http server #########
         let my_local_ip = local_ip().unwrap();
-        let my_server: String =
-            "http://".to_string() + &my_local_ip.to_string() + &":7878/upload".to_string();
-        let addr = SocketAddr::from(([0, 0, 0, 0], 7878));
+        // ScreenCapture is told where to upload; port 0 takes any free one.
+        let addr = SocketAddr::from(([0, 0, 0, 0], capture_port));
         let (tx, mut rx) = mpsc::channel(10);
 
         let make_svc = make_service_fn(move |_conn| {
@@ -48,7 +57,10 @@ pub fn process(_dab_request: CaptureScreenshotRequest) -> Result<String, DabErro
 
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
 
-        let server = Server::bind(&addr).serve(make_svc);
+        let server = Server::try_bind(&addr)
+            .map_err(|e| DabError::Err500(format!("Failed to start the upload server: {}", e)))?
+            .serve(make_svc);
+        let my_server = format!("http://{}:{}/upload", my_local_ip, server.local_addr().port());
         let graceful = server.with_graceful_shutdown(async {
             shutdown_rx.await.ok();
         });
@@ -77,7 +89,7 @@ pub fn process(_dab_request: CaptureScreenshotRequest) -> Result<String, DabErro
 
         let request = UploadScreenCaptureRequest {
             jsonrpc: "2.0".into(),
-            id: 3,
+            id: thunder.next_id(),
             method: "org.rdk.ScreenCapture.uploadScreenCapture".into(),
             params: req_params,
         };
@@ -95,18 +107,16 @@ pub fn process(_dab_request: CaptureScreenshotRequest) -> Result<String, DabErro
         }
 
         let json_string = serde_json::to_string(&request).unwrap();
-        http_post(json_string)?;
+        thunder.http_post(json_string)?;
 
         //######### Listen for the base64 string from the request handler with a timeout. #########
         match time::timeout(Duration::from_secs(30), rx.recv()).await {
             Ok(Some(data)) => {
-                let b64 = general_purpose::STANDARD.encode(&data);
-                let b64 = format!("data:image/png;base64,{}", b64);
-                // After receiving the base64 string, signalize to close the server.
+                // After receiving the screen capture, signalize to close the server.
                 let _ = shutdown_tx.send(());
 
                 //######### Correlate Fields #########
-                ResponseOperator.outputImage = b64;
+                let ResponseOperator = screenshot::process(&data, &_dab_request, upload_dir)?;
 
                 // *******************************************************************
                 Ok(serde_json::to_string(&ResponseOperator).unwrap())
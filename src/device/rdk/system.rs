@@ -1,2 +1,3 @@
+pub mod power_mode;
 pub mod restart;
 pub mod settings;
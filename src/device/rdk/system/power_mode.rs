@@ -0,0 +1,99 @@
+// Power state of the device through org.rdk.System, and the sleep policy of the
+// operations that need the device awake.
+#[cfg(feature = "2_1")]
+pub mod get;
+#[cfg(feature = "2_1")]
+pub mod set;
+
+use crate::dab::structs::DabError;
+use crate::dab::structs::PowerMode;
+use crate::device::rdk::interface::RdkResponse;
+use crate::device::rdk::interface::RdkResponseSimple;
+use crate::device::rdk::interface::SleepPolicy;
+use crate::device::rdk::interface::ThunderClient;
+use serde::Deserialize;
+use serde_json::json;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a woken device has to report itself on.
+const WAKE_TIMEOUT: Duration = Duration::from_secs(5);
+const WAKE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn rdk_power_state(mode: PowerMode) -> &'static str {
+    match mode {
+        PowerMode::On => "ON",
+        PowerMode::Standby => "STANDBY",
+        PowerMode::LightSleep => "LIGHT_SLEEP",
+        PowerMode::DeepSleep => "DEEP_SLEEP",
+    }
+}
+
+pub fn get_power_mode(thunder: &ThunderClient) -> Result<PowerMode, DabError> {
+    #[allow(non_snake_case)]
+    #[derive(Deserialize)]
+    struct PowerState {
+        powerState: String,
+    }
+
+    let rdkresponse: RdkResponse<PowerState> = thunder.rdk_request("org.rdk.System.getPowerState")?;
+    match rdkresponse.result.powerState.as_str() {
+        "ON" => Ok(PowerMode::On),
+        "STANDBY" => Ok(PowerMode::Standby),
+        "LIGHT_SLEEP" => Ok(PowerMode::LightSleep),
+        "DEEP_SLEEP" => Ok(PowerMode::DeepSleep),
+        state => Err(DabError::Err500(format!("Unknown power state '{}'", state))),
+    }
+}
+
+pub fn set_power_mode(thunder: &ThunderClient, mode: PowerMode) -> Result<(), DabError> {
+    let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+        "org.rdk.System.setPowerState",
+        json!({"powerState": rdk_power_state(mode), "standbyReason": "DAB_POWER_MODE_REQUEST"}),
+    )?;
+    Ok(())
+}
+
+// Wakes the device up, or rejects the operation, when it isn't on. The operation
+// goes ahead when the power state can't be read.
+pub fn ensure_awake(thunder: &ThunderClient, policy: SleepPolicy, operation: &str) -> Result<(), DabError> {
+    let mode = match get_power_mode(thunder) {
+        Ok(PowerMode::On) => return Ok(()),
+        Ok(mode) => mode,
+        Err(e) => {
+            println!("Can't read the power state for {}, going ahead: {:?}", operation, e);
+            return Ok(());
+        }
+    };
+    match policy {
+        SleepPolicy::Wake => {
+            println!("Waking the device up from {:?} for {}", mode, operation);
+            set_power_mode(thunder, PowerMode::On)?;
+            wait_until_on(thunder)
+        }
+        SleepPolicy::Reject => Err(DabError::Err400(format!(
+            "{} is not available while the device is in {:?} power mode", operation, mode))),
+    }
+}
+
+fn current_power_mode(thunder: &ThunderClient) -> Result<PowerMode, DabError> {
+    get_power_mode(thunder).map_err(|e| {
+        DabError::Err500(format!("The power state of the device is unknown: {:?}", e))
+    })
+}
+
+// setPowerState returns before the device is on, so the operation waits for
+// getPowerState to report it.
+fn wait_until_on(thunder: &ThunderClient) -> Result<(), DabError> {
+    let deadline = Instant::now() + WAKE_TIMEOUT;
+    loop {
+        if current_power_mode(thunder)? == PowerMode::On {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(DabError::Err500(format!(
+                "The device didn't wake up within {} seconds", WAKE_TIMEOUT.as_secs())));
+        }
+        thread::sleep(WAKE_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,14 @@
+use crate::dab::structs::DabError;
+use crate::dab::structs::GetPowerModeRequest;
+use crate::dab::structs::GetPowerModeResponse;
+use crate::device::rdk::interface::ThunderClient;
+
+use super::get_power_mode;
+
+#[allow(non_snake_case)]
+pub fn process(thunder: &ThunderClient, _dab_request: GetPowerModeRequest) -> Result<String, DabError> {
+    let ResponseOperator = GetPowerModeResponse {
+        powerMode: get_power_mode(thunder)?,
+    };
+    Ok(serde_json::to_string(&ResponseOperator).unwrap())
+}
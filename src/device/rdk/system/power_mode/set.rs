@@ -0,0 +1,13 @@
+use crate::dab::structs::DabError;
+use crate::dab::structs::SetPowerModeRequest;
+use crate::dab::structs::SetPowerModeResponse;
+use crate::device::rdk::interface::ThunderClient;
+
+use super::set_power_mode;
+
+#[allow(non_snake_case)]
+pub fn process(thunder: &ThunderClient, _dab_request: SetPowerModeRequest) -> Result<String, DabError> {
+    let ResponseOperator = SetPowerModeResponse::default();
+    set_power_mode(thunder, _dab_request.powerMode)?;
+    Ok(serde_json::to_string(&ResponseOperator).unwrap())
+}
@@ -1,13 +1,13 @@
 use crate::dab::structs::DabError;
 use crate::dab::structs::RestartRequest;
 use crate::dab::structs::RestartResponse;
-use crate::device::rdk::interface::http_post;
+use crate::device::rdk::interface::ThunderClient;
 use serde::{Deserialize, Serialize};
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: RestartRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: RestartRequest) -> Result<String, DabError> {
     let mut ResponseOperator = RestartResponse::default();
     // *** Fill in the fields of the struct RestartResponse here ***
 
@@ -31,7 +31,7 @@ pub fn process(_dab_request: RestartRequest) -> Result<String, DabError> {
 
     let request = RebootRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.System.reboot".into(),
         params: req_params,
     };
@@ -50,7 +50,7 @@ pub fn process(_dab_request: RestartRequest) -> Result<String, DabError> {
     }
 
     let json_string = serde_json::to_string(&request).unwrap();
-    http_post(json_string)?;
+    thunder.http_post(json_string)?;
 
     // *******************************************************************
     Ok(serde_json::to_string(&ResponseOperator).unwrap())
@@ -1,3 +1,43 @@
+#[cfg(feature = "2_1")]
+pub mod accessibility;
+pub mod display;
 pub mod get;
+pub mod input;
 pub mod list;
 pub mod set;
+
+use crate::dab::structs::DabError;
+use crate::device::rdk::interface::ThunderClient;
+use serde::de::DeserializeOwned;
+
+// Whether the plugin is activated. Reading the settings doesn't change the device,
+// so a deactivated plugin reads as missing until a setting of it is set.
+fn plugin_available(thunder: &ThunderClient, callsign: &str) -> bool {
+    matches!(thunder.get_service_state(callsign), Ok(state) if state == "activated")
+}
+
+// Calls a method of an optional plugin. None when the device lacks the plugin, or
+// the method in its release, so an unsupported setting isn't reported as a failure.
+fn rdk_request_optional<R: DeserializeOwned>(thunder: &ThunderClient, method: &str) -> Result<Option<R>, DabError> {
+    match thunder.rdk_request(method) {
+        Ok(response) => Ok(Some(response)),
+        Err(DabError::Err500(message)) if message == "Unknown method." || message == "ERROR_UNKNOWN_KEY" => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Whether the device supports the setting, as read by its getter.
+fn is_supported<T>(setting: Result<Option<T>, DabError>) -> bool {
+    matches!(setting, Ok(Some(_)))
+}
+
+// Activates the plugin, if the device has it, before setting one of its settings.
+fn activate_plugin(thunder: &ThunderClient, callsign: &str) {
+    if let Ok(state) = thunder.get_service_state(callsign) {
+        if state != "activated" {
+            if let Err(e) = thunder.service_activate(callsign.to_string()) {
+                println!("Error activating {}: {:?}", callsign, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// Accessibility settings, a DAB 2.1 extension: closed captions, audio description and
+// high contrast through org.rdk.UserSettings, and the voice and rate of the
+// TextToSpeech engine. Like the display settings, each one is probed at runtime:
+// None means the device doesn't support it, and an error that it couldn't be read.
+
+use crate::dab::structs::DabError;
+use crate::dab::structs::TextToSpeechRate;
+use crate::device::rdk::interface::get_supported_languages;
+use crate::device::rdk::interface::RdkResponse;
+use crate::device::rdk::interface::RdkResponseSimple;
+use crate::device::rdk::interface::ThunderClient;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::rdk_request_optional;
+
+// Speech rate range of the TextToSpeech engine.
+const TTS_RATE_RANGE: TextToSpeechRate = TextToSpeechRate { min: 1, max: 100 };
+
+// UserSettings answers with the bare value.
+fn get_user_setting(thunder: &ThunderClient, method: &str) -> Result<Option<Value>, DabError> {
+    let rdkresponse: Option<RdkResponse<Value>> = rdk_request_optional(thunder, method)?;
+    Ok(rdkresponse.map(|rdkresponse| rdkresponse.result))
+}
+
+fn get_user_switch(thunder: &ThunderClient, method: &str) -> Result<Option<bool>, DabError> {
+    match get_user_setting(thunder, method)? {
+        Some(Value::Bool(enabled)) => Ok(Some(enabled)),
+        Some(value) => Err(DabError::Err500(format!("Invalid {} response: {}", method, value))),
+        None => Ok(None),
+    }
+}
+
+fn unsupported(name: &str) -> DabError {
+    DabError::Err400(format!("Setting '{}' is not supported", name))
+}
+
+fn set_user_setting(thunder: &ThunderClient, method: &str, params: Value) -> Result<(), DabError> {
+    let rdkresponse: RdkResponse<Option<String>> = thunder.rdk_request_with_params(method, params)?;
+    if let Some(msg) = rdkresponse.result {
+        return Err(DabError::Err500(format!("Error from {} {}", method, msg)));
+    }
+    Ok(())
+}
+
+pub fn get_closed_captions(thunder: &ThunderClient) -> Result<Option<bool>, DabError> {
+    get_user_switch(thunder, "org.rdk.UserSettings.getCaptions")
+}
+
+pub fn set_closed_captions(thunder: &ThunderClient, enabled: bool) -> Result<(), DabError> {
+    if get_closed_captions(thunder)?.is_none() {
+        return Err(unsupported("closedCaptions"));
+    }
+    set_user_setting(thunder, "org.rdk.UserSettings.setCaptions", json!({"enabled": enabled}))
+}
+
+// The first of the preferred captions languages, a comma-separated list.
+pub fn get_captions_language(thunder: &ThunderClient) -> Result<Option<String>, DabError> {
+    let method = "org.rdk.UserSettings.getPreferredCaptionsLanguages";
+    match get_user_setting(thunder, method)? {
+        Some(Value::String(languages)) => {
+            Ok(Some(languages.split(',').next().unwrap_or_default().trim().to_string()))
+        }
+        Some(value) => Err(DabError::Err500(format!("Invalid {} response: {}", method, value))),
+        None => Ok(None),
+    }
+}
+
+// The captions can be in any of the languages of the device.
+pub fn list_captions_languages(thunder: &ThunderClient) -> Vec<String> {
+    match get_captions_language(thunder) {
+        Ok(Some(_)) => get_supported_languages(),
+        _ => vec![],
+    }
+}
+
+pub fn set_captions_language(thunder: &ThunderClient, language: String) -> Result<(), DabError> {
+    if get_captions_language(thunder)?.is_none() {
+        return Err(unsupported("captionsLanguage"));
+    }
+    set_user_setting(
+        thunder,
+        "org.rdk.UserSettings.setPreferredCaptionsLanguages",
+        json!({"preferredLanguages": language}),
+    )
+}
+
+pub fn get_audio_description(thunder: &ThunderClient) -> Result<Option<bool>, DabError> {
+    get_user_switch(thunder, "org.rdk.UserSettings.getAudioDescription")
+}
+
+pub fn set_audio_description(thunder: &ThunderClient, enabled: bool) -> Result<(), DabError> {
+    if get_audio_description(thunder)?.is_none() {
+        return Err(unsupported("audioDescription"));
+    }
+    set_user_setting(thunder, "org.rdk.UserSettings.setAudioDescription", json!({"enabled": enabled}))
+}
+
+pub fn get_high_contrast_text(thunder: &ThunderClient) -> Result<Option<bool>, DabError> {
+    get_user_switch(thunder, "org.rdk.UserSettings.getHighContrast")
+}
+
+pub fn set_high_contrast_text(thunder: &ThunderClient, enabled: bool) -> Result<(), DabError> {
+    if get_high_contrast_text(thunder)?.is_none() {
+        return Err(unsupported("highContrastText"));
+    }
+    set_user_setting(thunder, "org.rdk.UserSettings.setHighContrast", json!({"enabled": enabled}))
+}
+
+#[derive(Deserialize)]
+struct TtsConfiguration {
+    #[serde(default)]
+    language: String,
+    #[serde(default)]
+    voice: String,
+    #[serde(default)]
+    rate: u32,
+}
+
+fn get_tts_configuration(thunder: &ThunderClient) -> Result<Option<TtsConfiguration>, DabError> {
+    let rdkresponse: Option<RdkResponse<TtsConfiguration>> =
+        rdk_request_optional(thunder, "org.rdk.TextToSpeech.getttsconfiguration")?;
+    Ok(rdkresponse.map(|rdkresponse| rdkresponse.result))
+}
+
+pub fn get_text_to_speech_voice(thunder: &ThunderClient) -> Result<Option<String>, DabError> {
+    Ok(get_tts_configuration(thunder)?.map(|configuration| configuration.voice))
+}
+
+// The voices of the current TextToSpeech language.
+pub fn list_text_to_speech_voices(thunder: &ThunderClient) -> Vec<String> {
+    #[derive(Deserialize)]
+    struct ListVoices {
+        voices: Vec<String>,
+    }
+
+    let Ok(Some(configuration)) = get_tts_configuration(thunder) else {
+        return vec![];
+    };
+    let rdkresponse: Result<RdkResponse<ListVoices>, DabError> = thunder.rdk_request_with_params(
+        "org.rdk.TextToSpeech.listvoices",
+        json!({"language": configuration.language}),
+    );
+    match rdkresponse {
+        Ok(rdkresponse) => rdkresponse.result.voices,
+        Err(_) => vec![],
+    }
+}
+
+pub fn get_text_to_speech_rate(thunder: &ThunderClient) -> Result<Option<u32>, DabError> {
+    Ok(get_tts_configuration(thunder)?.map(|configuration| configuration.rate))
+}
+
+pub fn list_text_to_speech_rates(thunder: &ThunderClient) -> Option<TextToSpeechRate> {
+    get_tts_configuration(thunder).ok()?.map(|_| TTS_RATE_RANGE)
+}
+
+fn set_tts_configuration(thunder: &ThunderClient, name: &str, params: Value) -> Result<(), DabError> {
+    if get_tts_configuration(thunder)?.is_none() {
+        return Err(unsupported(name));
+    }
+
+    let _rdkresponse: RdkResponseSimple =
+        thunder.rdk_request_with_params("org.rdk.TextToSpeech.setttsconfiguration", params)?;
+    Ok(())
+}
+
+pub fn set_text_to_speech_voice(thunder: &ThunderClient, voice: String) -> Result<(), DabError> {
+    set_tts_configuration(thunder, "textToSpeechVoice", json!({"voice": voice}))
+}
+
+pub fn set_text_to_speech_rate(thunder: &ThunderClient, rate: u32) -> Result<(), DabError> {
+    if !(TTS_RATE_RANGE.min..=TTS_RATE_RANGE.max).contains(&rate) {
+        return Err(DabError::Err400("Unsupported text to speech rate value".to_string()));
+    }
+    set_tts_configuration(thunder, "textToSpeechRate", json!({"rate": rate}))
+}
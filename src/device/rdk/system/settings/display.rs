@@ -0,0 +1,193 @@
+// Display processing settings: picture mode and MEMC through org.rdk.tv.ControlSettings,
+// low latency mode (ALLM) through DisplaySettings and frame rate matching through
+// org.rdk.FrameRate. Set-top boxes lack some of these plugins or methods, so each
+// setting is probed at runtime; None means the device doesn't support it, or its
+// plugin is deactivated until system/settings/set activates it. An error means the
+// setting is supported but couldn't be read.
+
+use crate::dab::structs::DabError;
+use crate::dab::structs::MatchContentFrameRate;
+use crate::dab::structs::PictureMode;
+use crate::device::rdk::interface::RdkResponseSimple;
+use crate::device::rdk::interface::ThunderClient;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::plugin_available;
+use super::rdk_request_optional;
+
+pub(super) const CONTROL_SETTINGS: &str = "org.rdk.tv.ControlSettings";
+pub(super) const DISPLAY_SETTINGS: &str = "org.rdk.DisplaySettings";
+pub(super) const FRAME_RATE: &str = "org.rdk.FrameRate";
+
+// The plugins report switches as booleans, numbers or "On"/"Off" strings.
+fn parse_enabled(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(enabled) => Some(*enabled),
+        Value::Number(number) => number.as_i64().map(|number| number != 0),
+        Value::String(text) => match text.to_lowercase().as_str() {
+            "on" | "enabled" | "true" | "1" => Some(true),
+            "off" | "disabled" | "false" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Reads a setting of the plugin, if it's activated.
+fn get_setting(thunder: &ThunderClient, callsign: &str, method: &str) -> Result<Option<Value>, DabError> {
+    if !plugin_available(thunder, callsign) {
+        return Ok(None);
+    }
+    rdk_request_optional(thunder, method)
+}
+
+fn invalid_response(method: &str, response: &Value) -> DabError {
+    DabError::Err500(format!("Invalid {} response: {}", method, response["result"]))
+}
+
+// A switch of the setting read, if the device supports it.
+fn get_enabled(thunder: &ThunderClient, callsign: &str, method: &str, key: &str) -> Result<Option<bool>, DabError> {
+    let Some(response) = get_setting(thunder, callsign, method)? else {
+        return Ok(None);
+    };
+    match parse_enabled(&response["result"][key]) {
+        Some(enabled) => Ok(Some(enabled)),
+        None => Err(invalid_response(method, &response)),
+    }
+}
+
+fn rdk_picture_mode_to_dab(mode: &str) -> Option<PictureMode> {
+    match mode.to_lowercase().as_str() {
+        "standard" => Some(PictureMode::Standard),
+        "vivid" | "dynamic" => Some(PictureMode::Dynamic),
+        "movie" | "theater" | "cinema" => Some(PictureMode::Movie),
+        "sports" => Some(PictureMode::Sports),
+        "filmmaker" => Some(PictureMode::FilmMaker),
+        "game" => Some(PictureMode::Game),
+        "auto" => Some(PictureMode::Auto),
+        _ => None,
+    }
+}
+
+// The picture modes of the device with a DAB equivalent, with their RDK names.
+fn get_rdk_picture_modes(thunder: &ThunderClient) -> Vec<(String, PictureMode)> {
+    let response = match get_setting(thunder, CONTROL_SETTINGS, "org.rdk.tv.ControlSettings.1.getSupportedPictureModes") {
+        Ok(Some(response)) => response,
+        _ => return vec![],
+    };
+
+    let mut modes: Vec<(String, PictureMode)> = vec![];
+    for name in response["result"]["pictureModes"].as_array().into_iter().flatten() {
+        let Some(name) = name.as_str() else { continue };
+        if let Some(mode) = rdk_picture_mode_to_dab(name) {
+            if !modes.iter().any(|(_, known)| *known == mode) {
+                modes.push((name.to_string(), mode));
+            }
+        }
+    }
+    modes
+}
+
+pub fn list_picture_modes(thunder: &ThunderClient) -> Vec<PictureMode> {
+    get_rdk_picture_modes(thunder).into_iter().map(|(_, mode)| mode).collect()
+}
+
+pub fn get_picture_mode(thunder: &ThunderClient) -> Result<Option<PictureMode>, DabError> {
+    let method = "org.rdk.tv.ControlSettings.1.getPictureMode";
+    let Some(response) = get_setting(thunder, CONTROL_SETTINGS, method)? else {
+        return Ok(None);
+    };
+    match response["result"]["pictureMode"].as_str().and_then(rdk_picture_mode_to_dab) {
+        Some(mode) => Ok(Some(mode)),
+        None => Err(invalid_response(method, &response)),
+    }
+}
+
+pub fn set_picture_mode(thunder: &ThunderClient, mode: PictureMode) -> Result<(), DabError> {
+    #[allow(non_snake_case)]
+    #[derive(Serialize)]
+    struct Param {
+        pictureMode: String,
+    }
+
+    let Some((name, _)) = get_rdk_picture_modes(thunder).into_iter().find(|(_, supported)| *supported == mode) else {
+        return Err(DabError::Err400(format!(
+            "Setting picture mode '{:?}' is not supported", mode)));
+    };
+
+    let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+        "org.rdk.tv.ControlSettings.1.setPictureMode",
+        Param { pictureMode: name },
+    )?;
+    Ok(())
+}
+
+pub fn get_memc(thunder: &ThunderClient) -> Result<Option<bool>, DabError> {
+    get_enabled(thunder, CONTROL_SETTINGS, "org.rdk.tv.ControlSettings.1.getMEMC", "memc")
+}
+
+pub fn set_memc(thunder: &ThunderClient, enabled: bool) -> Result<(), DabError> {
+    if get_memc(thunder)?.is_none() {
+        return Err(DabError::Err400("Setting 'memc' is not supported".to_string()));
+    }
+
+    let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+        "org.rdk.tv.ControlSettings.1.setMEMC",
+        json!({"memc": enabled}),
+    )?;
+    Ok(())
+}
+
+// Auto low latency mode of the HDMI sink.
+pub fn get_low_latency_mode(thunder: &ThunderClient) -> Result<Option<bool>, DabError> {
+    get_enabled(thunder, DISPLAY_SETTINGS, "org.rdk.DisplaySettings.getAllmEnabled", "allmEnabled")
+}
+
+pub fn set_low_latency_mode(thunder: &ThunderClient, enabled: bool) -> Result<(), DabError> {
+    if get_low_latency_mode(thunder)?.is_none() {
+        return Err(DabError::Err400("Setting 'lowLatencyMode' is not supported".to_string()));
+    }
+
+    let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+        "org.rdk.DisplaySettings.setAllmEnabled",
+        json!({"allmEnabled": enabled}),
+    )?;
+    Ok(())
+}
+
+// Whether the output frame rate follows the content; RDK doesn't tell seamless switches apart.
+pub fn get_match_content_frame_rate(thunder: &ThunderClient) -> Result<Option<MatchContentFrameRate>, DabError> {
+    let enabled = get_enabled(thunder, FRAME_RATE, "org.rdk.FrameRate.1.getFrmMode", "auto-frm-mode")?;
+    Ok(enabled.map(|enabled| match enabled {
+        true => MatchContentFrameRate::EnabledAlways,
+        false => MatchContentFrameRate::Disabled,
+    }))
+}
+
+pub fn list_match_content_frame_rates(thunder: &ThunderClient) -> Vec<MatchContentFrameRate> {
+    match get_match_content_frame_rate(thunder) {
+        Ok(Some(_)) => vec![MatchContentFrameRate::EnabledAlways, MatchContentFrameRate::Disabled],
+        _ => vec![],
+    }
+}
+
+pub fn set_match_content_frame_rate(thunder: &ThunderClient, mode: MatchContentFrameRate) -> Result<(), DabError> {
+    let frmmode = match mode {
+        MatchContentFrameRate::EnabledAlways => 1,
+        MatchContentFrameRate::Disabled => 0,
+        MatchContentFrameRate::EnabledSeamlessOnly => {
+            return Err(DabError::Err400(format!(
+                "Setting match content frame rate '{:?}' is not supported", mode)));
+        }
+    };
+    if get_match_content_frame_rate(thunder)?.is_none() {
+        return Err(DabError::Err400("Setting 'matchContentFrameRate' is not supported".to_string()));
+    }
+
+    let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+        "org.rdk.FrameRate.1.setFrmMode",
+        json!({"frmmode": frmmode}),
+    )?;
+    Ok(())
+}
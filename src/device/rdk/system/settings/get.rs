@@ -1,5 +1,3 @@
-use std::thread;
-use tokio::time::Duration;
 
 use crate::dab::structs::AudioOutputMode;
 use crate::dab::structs::AudioOutputSource;
@@ -7,19 +5,24 @@ use crate::dab::structs::DabError;
 use crate::dab::structs::GetSystemSettingsRequest;
 use crate::dab::structs::GetSystemSettingsResponse;
 use crate::dab::structs::HdrOutputMode;
+use crate::dab::structs::MatchContentFrameRate;
 use crate::dab::structs::OutputResolution;
-use crate::device::rdk::interface::get_thunder_property;
-use crate::device::rdk::interface::rdk_request;
-use crate::device::rdk::interface::rdk_request_with_params;
 use crate::device::rdk::interface::rdk_sound_mode_to_dab;
 use crate::device::rdk::interface::RdkResponse;
-use crate::hw_specific::interface::get_service_state;
-use crate::hw_specific::interface::service_activate;
+use crate::device::rdk::interface::ThunderClient;
+#[cfg(feature = "2_1")]
+use crate::device::rdk::system::settings::accessibility;
+use crate::device::rdk::system::settings::display;
+use crate::device::rdk::system::settings::input;
+use crate::device::rdk::system::settings::input::ActiveInput;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
 
-pub fn get_rdk_language() -> Result<String, DabError> {
+pub fn get_rdk_language(thunder: &ThunderClient) -> Result<String, DabError> {
     let rdkresponse: RdkResponse<String> =
-        rdk_request("org.rdk.UserSettings.getPresentationLanguage")?;
+        thunder.rdk_request("org.rdk.UserSettings.getPresentationLanguage")?;
 
     Ok(rdkresponse.result)
 }
@@ -39,7 +42,7 @@ fn get_frequency_from_displayinfo_framerate(
     framerate.parse::<f32>()
 }
 
-fn get_displaysettings_resolution_widthheight() -> Result<(u32, u32), DabError> {
+fn get_displaysettings_resolution_widthheight(thunder: &ThunderClient) -> Result<(u32, u32), DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Serialize, Deserialize)]
@@ -57,19 +60,19 @@ fn get_displaysettings_resolution_widthheight() -> Result<(u32, u32), DabError>
         success: bool,
     }
     let req_params = Param {
-        videoDisplay: get_rdk_connected_video_displays()?,
+        videoDisplay: get_rdk_connected_video_displays(thunder)?,
     };
 
     let _rdkresponse: RdkResponse<ResolutionResponse> =
-        rdk_request_with_params("org.rdk.DisplaySettings.getCurrentResolution", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.getCurrentResolution", req_params)?;
     return Ok((_rdkresponse.result.w, _rdkresponse.result.h));
 }
 
-fn get_rdk_video_resolution() -> Result<OutputResolution, DabError> {
+fn get_rdk_video_resolution(thunder: &ThunderClient) -> Result<OutputResolution, DabError> {
     // DAB settings/list needs Video output resolution.
-    let (width, height) = get_displaysettings_resolution_widthheight()?;
+    let (width, height) = get_displaysettings_resolution_widthheight(thunder)?;
 
-    let displayinfo_framerate = get_thunder_property("DisplayInfo.framerate", "")?;
+    let displayinfo_framerate = thunder.get_thunder_property("DisplayInfo.framerate", "")?;
     let frequency = get_frequency_from_displayinfo_framerate(&displayinfo_framerate)
         .map_err(|_| DabError::Err400("Invalid framerate(parse to f32 failed)".to_string()))?;
 
@@ -80,7 +83,7 @@ fn get_rdk_video_resolution() -> Result<OutputResolution, DabError> {
     })
 }
 
-pub fn get_rdk_connected_video_displays() -> Result<String, DabError> {
+pub fn get_rdk_connected_video_displays(thunder: &ThunderClient) -> Result<String, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize)]
@@ -90,7 +93,7 @@ pub fn get_rdk_connected_video_displays() -> Result<String, DabError> {
     }
 
     let rdkresponse: RdkResponse<ConnectedVideoDisplays> =
-        rdk_request("org.rdk.DisplaySettings.getConnectedVideoDisplays")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getConnectedVideoDisplays")?;
 
     rdkresponse
         .result
@@ -102,7 +105,7 @@ pub fn get_rdk_connected_video_displays() -> Result<String, DabError> {
         ))
 }
 
-pub fn get_rdk_hdr_current_setting() -> Result<HdrOutputMode, DabError> {
+pub fn get_rdk_hdr_current_setting(thunder: &ThunderClient) -> Result<HdrOutputMode, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize, Debug)]
@@ -113,9 +116,9 @@ pub fn get_rdk_hdr_current_setting() -> Result<HdrOutputMode, DabError> {
     }
 
     let settop_hdr_response: RdkResponse<GetHDRSupport> =
-        rdk_request("org.rdk.DisplaySettings.getSettopHDRSupport")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getSettopHDRSupport")?;
     let tv_hdr_response: RdkResponse<GetHDRSupport> =
-        rdk_request("org.rdk.DisplaySettings.getTvHDRSupport")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getTvHDRSupport")?;
 
     if settop_hdr_response.result.supportsHDR & tv_hdr_response.result.supportsHDR {
         Ok(HdrOutputMode::AlwaysHdr)
@@ -124,7 +127,7 @@ pub fn get_rdk_hdr_current_setting() -> Result<HdrOutputMode, DabError> {
     }
 }
 
-pub fn get_rdk_audio_port() -> Result<String, DabError> {
+pub fn get_rdk_audio_port(thunder: &ThunderClient) -> Result<String, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize)]
@@ -134,7 +137,7 @@ pub fn get_rdk_audio_port() -> Result<String, DabError> {
     }
 
     let rdkresponse: RdkResponse<GetConnectedAudioPorts> =
-        rdk_request("org.rdk.DisplaySettings.getConnectedAudioPorts")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getConnectedAudioPorts")?;
 
     rdkresponse
         .result
@@ -146,7 +149,7 @@ pub fn get_rdk_audio_port() -> Result<String, DabError> {
         ))
 }
 
-fn get_rdk_audio_volume() -> Result<u32, DabError> {
+fn get_rdk_audio_volume(thunder: &ThunderClient) -> Result<u32, DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -162,11 +165,11 @@ fn get_rdk_audio_volume() -> Result<u32, DabError> {
     }
 
     let req_params = Param {
-        audioPort: get_rdk_audio_port()?,
+        audioPort: get_rdk_audio_port(thunder)?,
     };
 
     let rdkresponse: RdkResponse<GetVolumeLevel> =
-        rdk_request_with_params("org.rdk.DisplaySettings.getVolumeLevel", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.getVolumeLevel", req_params)?;
 
     match rdkresponse.result.volumeLevel.parse::<f32>() {
         Ok(volume) => Ok(volume as u32),
@@ -174,7 +177,7 @@ fn get_rdk_audio_volume() -> Result<u32, DabError> {
     }
 }
 
-fn get_rdk_mute() -> Result<bool, DabError> {
+fn get_rdk_mute(thunder: &ThunderClient) -> Result<bool, DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -189,16 +192,16 @@ fn get_rdk_mute() -> Result<bool, DabError> {
     }
 
     let req_params = Param {
-        audioPort: get_rdk_audio_port()?,
+        audioPort: get_rdk_audio_port(thunder)?,
     };
 
     let rdkresponse: RdkResponse<GetMuted> =
-        rdk_request_with_params("org.rdk.DisplaySettings.getMuted", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.getMuted", req_params)?;
 
     Ok(rdkresponse.result.muted)
 }
 
-pub fn get_rdk_tts() -> Result<bool, DabError> {
+pub fn get_rdk_tts(thunder: &ThunderClient) -> Result<bool, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize)]
@@ -208,17 +211,16 @@ pub fn get_rdk_tts() -> Result<bool, DabError> {
         success: bool,
     }
 
-    let rdkresponse: RdkResponse<TtsGetEnabled> = rdk_request("org.rdk.TextToSpeech.isttsenabled")?;
+    let rdkresponse: RdkResponse<TtsGetEnabled> = thunder.rdk_request("org.rdk.TextToSpeech.isttsenabled")?;
 
     Ok(rdkresponse.result.isenabled)
 }
 
-pub fn get_rdk_cec() -> Result<bool, DabError> {
-    match get_service_state("org.rdk.HdmiCecSource") {
+pub fn get_rdk_cec(thunder: &ThunderClient) -> Result<bool, DabError> {
+    match thunder.get_service_state("org.rdk.HdmiCecSource") {
         Ok(state) => {
             if state != "activated" {
-                service_activate("org.rdk.HdmiCecSource".to_string())?;
-                thread::sleep(Duration::from_millis(500));
+                thunder.service_activate("org.rdk.HdmiCecSource".to_string())?;
             }
         }
         Err(e) => {
@@ -234,12 +236,12 @@ pub fn get_rdk_cec() -> Result<bool, DabError> {
         success: bool,
     }
 
-    let rdkresponse: RdkResponse<CecGetEnabled> = rdk_request("org.rdk.HdmiCecSource.getEnabled")?;
+    let rdkresponse: RdkResponse<CecGetEnabled> = thunder.rdk_request("org.rdk.HdmiCecSource.getEnabled")?;
 
     Ok(rdkresponse.result.enabled)
 }
 
-fn get_rdk_connected_audio_source() -> Result<AudioOutputSource, DabError> {
+fn get_rdk_connected_audio_source(thunder: &ThunderClient) -> Result<AudioOutputSource, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize, Debug)]
@@ -250,7 +252,7 @@ fn get_rdk_connected_audio_source() -> Result<AudioOutputSource, DabError> {
     let mut response = vec![AudioOutputSource::default()];
 
     let rdkresponse: RdkResponse<GetConnectedAudioPorts> =
-        rdk_request("org.rdk.DisplaySettings.getConnectedAudioPorts")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getConnectedAudioPorts")?;
 
     for source in rdkresponse.result.connectedAudioPorts.iter() {
         let val = match source.as_str() {
@@ -268,7 +270,7 @@ fn get_rdk_connected_audio_source() -> Result<AudioOutputSource, DabError> {
     Ok(response.get(0).unwrap().clone())
 }
 
-fn get_rdk_audio_output_mode() -> Result<AudioOutputMode, DabError> {
+fn get_rdk_audio_output_mode(thunder: &ThunderClient) -> Result<AudioOutputMode, DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -284,11 +286,11 @@ fn get_rdk_audio_output_mode() -> Result<AudioOutputMode, DabError> {
     }
 
     let req_params = Param {
-        audioPort: get_rdk_audio_port()?,
+        audioPort: get_rdk_audio_port(thunder)?,
     };
 
     let rdkresponse: RdkResponse<GetSoundMode> =
-        rdk_request_with_params("org.rdk.DisplaySettings.getSoundMode", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.getSoundMode", req_params)?;
 
     match rdk_sound_mode_to_dab(&rdkresponse.result.soundMode) {
         Some(mode) => Ok(mode),
@@ -299,20 +301,157 @@ fn get_rdk_audio_output_mode() -> Result<AudioOutputMode, DabError> {
     }
 }
 
-pub fn process(_dab_request: GetSystemSettingsRequest) -> Result<String, DabError> {
-    let mut response = GetSystemSettingsResponse::default();
-    // *** Fill in the fields of the struct GetSystemSettingsResponse here ***
-
-    response.language = get_rdk_language()?;
-    response.outputResolution = get_rdk_video_resolution()?;
-    response.audioVolume = get_rdk_audio_volume()?;
-    response.mute = get_rdk_mute()?;
-    response.cec = get_rdk_cec()?;
-    response.hdrOutputMode = get_rdk_hdr_current_setting()?;
-    response.audioOutputMode = get_rdk_audio_output_mode()?;
-    response.audioOutputSource = get_rdk_connected_audio_source()?;
-    response.lowLatencyMode = false;
-    response.textToSpeech = get_rdk_tts()?;
-
-    Ok(serde_json::to_string(&response).unwrap())
+// Thunder calls in flight for one request.
+const SETTINGS_READERS: usize = 4;
+
+// The settings read so far, and the errors of the ones that couldn't be.
+#[derive(Default)]
+struct Readings {
+    response: GetSystemSettingsResponse,
+    errors: HashMap<String, String>,
+    read: Vec<&'static str>,
+}
+
+// Reads one setting, then stores it in the readings.
+type Reader<'a> = Box<dyn FnOnce() -> Box<dyn FnOnce(&mut Readings) + Send + 'a> + Send + 'a>;
+
+// Stores the setting read, or its error; a setting that can't be read, or that the
+// device doesn't support, keeps its default value.
+fn reader<'a, T: Send + 'a>(
+    name: &'static str,
+    read: impl FnOnce() -> Result<Option<T>, DabError> + Send + 'a,
+    store: impl FnOnce(&mut GetSystemSettingsResponse, T) + Send + 'a,
+) -> Reader<'a> {
+    Box::new(move || {
+        let result = read();
+        Box::new(move |readings: &mut Readings| match result {
+            Ok(Some(value)) => {
+                store(&mut readings.response, value);
+                readings.read.push(name);
+            }
+            Ok(None) => {}
+            Err(DabError::Err400(error) | DabError::Err500(error) | DabError::Err501(error)) => {
+                readings.errors.insert(name.to_string(), error);
+            }
+        })
+    })
+}
+
+// Runs the readers on a few threads, as the settings don't depend on each other.
+fn read_settings(readers: Vec<Reader>) -> Readings {
+    let queue = Mutex::new(readers.into_iter());
+    let readings = Mutex::new(Readings {
+        // Without frame rate matching, the output keeps its frame rate.
+        response: GetSystemSettingsResponse {
+            matchContentFrameRate: MatchContentFrameRate::Disabled,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    thread::scope(|scope| {
+        for _ in 0..SETTINGS_READERS {
+            scope.spawn(|| loop {
+                let Some(reader) = queue.lock().unwrap().next() else { break };
+                let store = reader();
+                store(&mut readings.lock().unwrap());
+            });
+        }
+    });
+    readings.into_inner().unwrap()
+}
+
+// Reads the settings concurrently. The settings that can't be read are reported in
+// `errors`; the request only fails when none can.
+pub fn get_rdk_settings(
+    thunder: &ThunderClient,
+    active_input: &ActiveInput,
+) -> Result<GetSystemSettingsResponse, DabError> {
+    read_rdk_settings(thunder, active_input).map(|(response, _)| response)
+}
+
+// The settings, with the names of the ones actually read from the device.
+pub fn read_rdk_settings(
+    thunder: &ThunderClient,
+    active_input: &ActiveInput,
+) -> Result<(GetSystemSettingsResponse, Vec<&'static str>), DabError> {
+    let readers: Vec<Reader> = vec![
+        reader("language", || get_rdk_language(thunder).map(Some), |response, value| response.language = value),
+        reader("outputResolution", || get_rdk_video_resolution(thunder).map(Some), |response, value| {
+            response.outputResolution = value
+        }),
+        reader("audioVolume", || get_rdk_audio_volume(thunder).map(Some), |response, value| {
+            response.audioVolume = value
+        }),
+        reader("mute", || get_rdk_mute(thunder).map(Some), |response, value| response.mute = value),
+        reader("cec", || get_rdk_cec(thunder).map(Some), |response, value| response.cec = value),
+        reader("hdrOutputMode", || get_rdk_hdr_current_setting(thunder).map(Some), |response, value| {
+            response.hdrOutputMode = value
+        }),
+        reader("audioOutputMode", || get_rdk_audio_output_mode(thunder).map(Some), |response, value| {
+            response.audioOutputMode = value
+        }),
+        reader("audioOutputSource", || get_rdk_connected_audio_source(thunder).map(Some), |response, value| {
+            response.audioOutputSource = value
+        }),
+        reader("textToSpeech", || get_rdk_tts(thunder).map(Some), |response, value| {
+            response.textToSpeech = value
+        }),
+        reader("lowLatencyMode", || display::get_low_latency_mode(thunder), |response, value| {
+            response.lowLatencyMode = value
+        }),
+        reader("memc", || display::get_memc(thunder), |response, value| response.memc = value),
+        reader("matchContentFrameRate", || display::get_match_content_frame_rate(thunder), |response, value| {
+            response.matchContentFrameRate = value
+        }),
+        reader("pictureMode", || display::get_picture_mode(thunder), |response, value| {
+            response.pictureMode = value
+        }),
+        reader("videoInputSource", || Ok(Some(input::get_video_input_source(thunder, active_input))), |response, value| {
+            response.videoInputSource = value
+        }),
+        #[cfg(feature = "2_1")]
+        reader("closedCaptions", || accessibility::get_closed_captions(thunder), |response, value| {
+            response.closedCaptions = value
+        }),
+        #[cfg(feature = "2_1")]
+        reader("captionsLanguage", || accessibility::get_captions_language(thunder), |response, value| {
+            response.captionsLanguage = value
+        }),
+        #[cfg(feature = "2_1")]
+        reader("audioDescription", || accessibility::get_audio_description(thunder), |response, value| {
+            response.audioDescription = value
+        }),
+        #[cfg(feature = "2_1")]
+        reader("highContrastText", || accessibility::get_high_contrast_text(thunder), |response, value| {
+            response.highContrastText = value
+        }),
+        #[cfg(feature = "2_1")]
+        reader("textToSpeechVoice", || accessibility::get_text_to_speech_voice(thunder), |response, value| {
+            response.textToSpeechVoice = value
+        }),
+        #[cfg(feature = "2_1")]
+        reader("textToSpeechRate", || accessibility::get_text_to_speech_rate(thunder), |response, value| {
+            response.textToSpeechRate = value
+        }),
+    ];
+    let Readings { mut response, errors, read } = read_settings(readers);
+
+    // The CEC setting reads as disabled and the video input as Home without Thunder,
+    // so they don't tell whether Thunder answers.
+    if !read.iter().any(|name| !["cec", "videoInputSource"].contains(name)) {
+        return Err(DabError::Err500(format!(
+            "Failed to read the system settings: {}",
+            errors["language"]
+        )));
+    }
+    response.errors = errors;
+    Ok((response, read))
+}
+
+pub fn process(
+    thunder: &ThunderClient,
+    active_input: &ActiveInput,
+    _dab_request: GetSystemSettingsRequest,
+) -> Result<String, DabError> {
+    Ok(serde_json::to_string(&get_rdk_settings(thunder, active_input)?).unwrap())
 }
@@ -0,0 +1,185 @@
+// Video input sources of RDK TVs: the HDMI ports through org.rdk.HdmiInput and the
+// composite input through org.rdk.AVInput. Only the ports the device reports are
+// available; Home shows the applications again. Neither plugin offers a tuner, so
+// Tuner is never listed. Thunder has no method for the input being shown, only the
+// onInputStatusChanged events of both plugins, which are followed.
+
+use crate::dab::structs::DabError;
+use crate::dab::structs::VideoInputSource;
+use crate::device::rdk::interface::RdkResponse;
+use crate::device::rdk::interface::RdkResponseSimple;
+use crate::device::rdk::interface::ThunderClient;
+use crossbeam::channel::Receiver;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::{Mutex, MutexGuard};
+
+use super::plugin_available;
+
+pub(super) const HDMI_INPUT: &str = "org.rdk.HdmiInput";
+pub(super) const AV_INPUT: &str = "org.rdk.AVInput";
+
+const INPUT_STATUS_EVENT: &str = "onInputStatusChanged";
+
+// The source being shown, Home until one is started. The input statuses of a
+// plugin are followed from the first video input request that finds it activated.
+#[derive(Default)]
+pub struct ActiveInput {
+    source: Mutex<VideoInputSource>,
+    statuses: Mutex<Vec<(&'static str, Receiver<Value>)>>,
+}
+
+// Like {"id": 0, "locator": "hdmiin://localhost/deviceid/0", "status": "started"}.
+#[derive(Deserialize)]
+struct InputStatus {
+    id: u32,
+    locator: String,
+    status: String,
+}
+
+impl ActiveInput {
+    // The source, after the input statuses received since the last request.
+    fn current(&self, thunder: &ThunderClient) -> MutexGuard<'_, VideoInputSource> {
+        let mut statuses = self.statuses.lock().unwrap();
+        for callsign in [HDMI_INPUT, AV_INPUT] {
+            if statuses.iter().any(|(watched, _)| *watched == callsign) || !plugin_available(thunder, callsign) {
+                continue;
+            }
+            let watched = thunder
+                .events()
+                .and_then(|events| events.watch(&format!("{}.1", callsign), INPUT_STATUS_EVENT));
+            if let Ok(events) = watched {
+                statuses.push((callsign, events));
+            }
+        }
+
+        let mut source = self.source.lock().unwrap();
+        for params in statuses.iter().flat_map(|(_, events)| events.try_iter()) {
+            let status: InputStatus = match serde_json::from_value(params) {
+                Ok(status) => status,
+                Err(_) => continue,
+            };
+            let input = if status.locator.starts_with("cvbsin:") {
+                Some(VideoInputSource::Composite)
+            } else {
+                hdmi_source(status.id)
+            };
+            match (input, status.status.as_str()) {
+                (Some(input), "started") => *source = input,
+                (Some(input), "stopped") if *source == input => *source = VideoInputSource::Home,
+                _ => {}
+            }
+        }
+        source
+    }
+}
+
+#[derive(Deserialize)]
+struct InputDevice {
+    id: u32,
+}
+
+#[derive(Deserialize)]
+struct InputDevices {
+    devices: Vec<InputDevice>,
+}
+
+fn hdmi_source(port: u32) -> Option<VideoInputSource> {
+    match port {
+        0 => Some(VideoInputSource::HDMI1),
+        1 => Some(VideoInputSource::HDMI2),
+        2 => Some(VideoInputSource::HDMI3),
+        3 => Some(VideoInputSource::HDMI4),
+        _ => None,
+    }
+}
+
+fn get_hdmi_ports(thunder: &ThunderClient) -> Vec<u32> {
+    if !plugin_available(thunder, HDMI_INPUT) {
+        return vec![];
+    }
+    let rdkresponse: Result<RdkResponse<InputDevices>, DabError> =
+        thunder.rdk_request("org.rdk.HdmiInput.1.getHDMIInputDevices");
+    match rdkresponse {
+        Ok(rdkresponse) => rdkresponse.result.devices.iter().map(|device| device.id).collect(),
+        Err(_) => vec![],
+    }
+}
+
+// The first composite port, which DAB knows as the only one.
+fn get_composite_port(thunder: &ThunderClient) -> Option<u32> {
+    if !plugin_available(thunder, AV_INPUT) {
+        return None;
+    }
+    let rdkresponse: RdkResponse<InputDevices> = thunder
+        .rdk_request_with_params("org.rdk.AVInput.1.getInputDevices", json!({"typeOfInput": "COMPOSITE"}))
+        .ok()?;
+    rdkresponse.result.devices.first().map(|device| device.id)
+}
+
+pub fn list_video_input_sources(thunder: &ThunderClient) -> Vec<VideoInputSource> {
+    let mut sources = vec![VideoInputSource::Home];
+    sources.extend(get_hdmi_ports(thunder).into_iter().filter_map(hdmi_source));
+    if get_composite_port(thunder).is_some() {
+        sources.push(VideoInputSource::Composite);
+    }
+    sources
+}
+
+// The source being shown, or Home when its port is gone.
+pub fn get_video_input_source(thunder: &ThunderClient, active: &ActiveInput) -> VideoInputSource {
+    let source = *active.current(thunder);
+    if list_video_input_sources(thunder).contains(&source) {
+        source
+    } else {
+        VideoInputSource::Home
+    }
+}
+
+fn stop_input(thunder: &ThunderClient, source: VideoInputSource) -> Result<(), DabError> {
+    let _rdkresponse: RdkResponseSimple = match source {
+        VideoInputSource::Home => return Ok(()),
+        VideoInputSource::Composite => thunder.rdk_request_with_params(
+            "org.rdk.AVInput.1.stopInput",
+            json!({"typeOfInput": "COMPOSITE"}),
+        )?,
+        _ => thunder.rdk_request("org.rdk.HdmiInput.1.stopHdmiInput")?,
+    };
+    Ok(())
+}
+
+pub fn set_video_input_source(
+    thunder: &ThunderClient,
+    active: &ActiveInput,
+    source: VideoInputSource,
+) -> Result<(), DabError> {
+    let mut current = active.current(thunder);
+
+    let hdmi_port = get_hdmi_ports(thunder).into_iter().find(|port| hdmi_source(*port) == Some(source));
+    let composite_port = match source {
+        VideoInputSource::Composite => get_composite_port(thunder),
+        _ => None,
+    };
+    if source != VideoInputSource::Home && hdmi_port.is_none() && composite_port.is_none() {
+        return Err(DabError::Err400(format!(
+            "Video input source '{:?}' is not available", source)));
+    }
+
+    stop_input(thunder, *current)?;
+    // Home until the new source is started.
+    *current = VideoInputSource::Home;
+
+    if let Some(port) = hdmi_port {
+        let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+            "org.rdk.HdmiInput.1.startHdmiInput",
+            json!({"portId": port}),
+        )?;
+    } else if let Some(port) = composite_port {
+        let _rdkresponse: RdkResponseSimple = thunder.rdk_request_with_params(
+            "org.rdk.AVInput.1.startInput",
+            json!({"portId": port, "typeOfInput": "COMPOSITE"}),
+        )?;
+    }
+    *current = source;
+    Ok(())
+}
@@ -5,25 +5,26 @@ use crate::dab::structs::DabError;
 use crate::dab::structs::HdrOutputMode;
 use crate::dab::structs::ListSystemSettingsRequest;
 use crate::dab::structs::ListSystemSettingsResponse;
-use crate::dab::structs::MatchContentFrameRate;
 use crate::dab::structs::OutputResolution;
-use crate::dab::structs::VideoInputSource;
-use crate::device::rdk::interface::rdk_request;
-use crate::device::rdk::interface::rdk_request_with_params;
 use crate::device::rdk::interface::rdk_sound_mode_to_dab;
-use crate::device::rdk::interface::service_is_available;
 use crate::device::rdk::interface::RdkResponse;
 use crate::device::rdk::system::settings::get::get_rdk_audio_port;
-use crate::hw_specific::interface::get_audio_volume_range;
-use crate::hw_specific::interface::get_supported_languages;
+use crate::device::rdk::interface::get_audio_volume_range;
+use crate::device::rdk::interface::get_supported_languages;
+use crate::device::rdk::interface::ThunderClient;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 
+#[cfg(feature = "2_1")]
+use super::accessibility;
+use super::display;
+use super::input;
 use super::get::get_rdk_tts;
+use super::is_supported;
 
-fn get_rdk_resolutions() -> Result<Vec<OutputResolution>, DabError> {
+fn get_rdk_resolutions(thunder: &ThunderClient) -> Result<Vec<OutputResolution>, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize)]
@@ -33,7 +34,7 @@ fn get_rdk_resolutions() -> Result<Vec<OutputResolution>, DabError> {
     }
 
     let rdkresponse: RdkResponse<GetSupportedResolutions> =
-        rdk_request("org.rdk.DisplaySettings.getSupportedResolutions")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getSupportedResolutions")?;
 
     lazy_static! {
         static ref RDK_RESOLUTION_MAP: HashMap<&'static str, [u32; 2]> = HashMap::from([
@@ -77,7 +78,7 @@ fn get_rdk_resolutions() -> Result<Vec<OutputResolution>, DabError> {
     Ok(res)
 }
 
-pub fn get_rdk_hdr_settings() -> Result<Vec<HdrOutputMode>, DabError> {
+pub fn get_rdk_hdr_settings(thunder: &ThunderClient) -> Result<Vec<HdrOutputMode>, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize, Debug)]
@@ -88,9 +89,9 @@ pub fn get_rdk_hdr_settings() -> Result<Vec<HdrOutputMode>, DabError> {
     }
 
     let settop_hdr_response: RdkResponse<GetHDRSupport> =
-        rdk_request("org.rdk.DisplaySettings.getSettopHDRSupport")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getSettopHDRSupport")?;
     let tv_hdr_response: RdkResponse<GetHDRSupport> =
-        rdk_request("org.rdk.DisplaySettings.getTvHDRSupport")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getTvHDRSupport")?;
 
     let mut response = Vec::new();
 
@@ -103,7 +104,7 @@ pub fn get_rdk_hdr_settings() -> Result<Vec<HdrOutputMode>, DabError> {
     Ok(response)
 }
 
-pub fn get_rdk_supported_audio_source() -> Result<Vec<AudioOutputSource>, DabError> {
+pub fn get_rdk_supported_audio_source(thunder: &ThunderClient) -> Result<Vec<AudioOutputSource>, DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Deserialize, Debug)]
@@ -113,7 +114,7 @@ pub fn get_rdk_supported_audio_source() -> Result<Vec<AudioOutputSource>, DabErr
     }
     let mut response = vec![AudioOutputSource::default()];
     let rdkresponse: RdkResponse<GetConnectedAudioPorts> =
-        rdk_request("org.rdk.DisplaySettings.getSupportedAudioPorts")?;
+        thunder.rdk_request("org.rdk.DisplaySettings.getSupportedAudioPorts")?;
 
     for source in rdkresponse.result.supportedAudioPorts.iter() {
         let val = match source.as_str() {
@@ -133,7 +134,7 @@ pub fn get_rdk_supported_audio_source() -> Result<Vec<AudioOutputSource>, DabErr
     Ok(response)
 }
 
-pub fn get_rdk_supported_audio_modes(port: &String) -> Result<Vec<String>, DabError> {
+pub fn get_rdk_supported_audio_modes(thunder: &ThunderClient, port: &String) -> Result<Vec<String>, DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -153,13 +154,13 @@ pub fn get_rdk_supported_audio_modes(port: &String) -> Result<Vec<String>, DabEr
     };
 
     let rdkresponse: RdkResponse<GetSupportedAudioModes> =
-        rdk_request_with_params("org.rdk.DisplaySettings.getSupportedAudioModes", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.getSupportedAudioModes", req_params)?;
 
     Ok(rdkresponse.result.supportedAudioModes)
 }
 
-fn get_rdk_audio_output_modes() -> Result<Vec<AudioOutputMode>, DabError> {
-    let mut res = get_rdk_supported_audio_modes(&get_rdk_audio_port()?)?
+fn get_rdk_audio_output_modes(thunder: &ThunderClient) -> Result<Vec<AudioOutputMode>, DabError> {
+    let mut res = get_rdk_supported_audio_modes(thunder, &get_rdk_audio_port(thunder)?)?
         .iter()
         .filter_map(|mode| rdk_sound_mode_to_dab(mode))
         .collect::<Vec<_>>();
@@ -174,57 +175,51 @@ fn get_rdk_audio_output_modes() -> Result<Vec<AudioOutputMode>, DabError> {
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: ListSystemSettingsRequest) -> Result<String, DabError> {
+pub fn get_rdk_settings_list(thunder: &ThunderClient) -> Result<ListSystemSettingsResponse, DabError> {
     let mut ResponseOperator = ListSystemSettingsResponse::default();
     // *** Fill in the fields of the struct ListSystemSettings here ***
 
     ResponseOperator.language = get_supported_languages();
 
-    ResponseOperator.outputResolution = get_rdk_resolutions()?;
+    ResponseOperator.outputResolution = get_rdk_resolutions(thunder)?;
 
-    ResponseOperator.memc = false;
+    ResponseOperator.memc = is_supported(display::get_memc(thunder));
 
-    ResponseOperator.cec = service_is_available("org.rdk.HdmiCecSource")?;
+    ResponseOperator.cec = thunder.service_is_available("org.rdk.HdmiCecSource")?;
 
-    ResponseOperator.lowLatencyMode = false;
+    ResponseOperator.lowLatencyMode = is_supported(display::get_low_latency_mode(thunder));
 
     ResponseOperator.mute = true;
 
-    ResponseOperator.textToSpeech = get_rdk_tts()?;
+    ResponseOperator.textToSpeech = get_rdk_tts(thunder)?;
 
-    ResponseOperator.hdrOutputMode = get_rdk_hdr_settings()?;
+    ResponseOperator.hdrOutputMode = get_rdk_hdr_settings(thunder)?;
 
     ResponseOperator.audioVolume = get_audio_volume_range();
 
-    ResponseOperator.matchContentFrameRate = vec![
-        MatchContentFrameRate::EnabledAlways,
-        // MatchContentFrameRate::EnabledSeamlessOnly,
-        // MatchContentFrameRate::Disabled,
-    ];
-
-    ResponseOperator.pictureMode = vec![
-        // PictureMode::Standard,
-        // PictureMode::Dynamic,
-        // PictureMode::Movie,
-        // PictureMode::Sports,
-        // PictureMode::FilmMaker,
-        // PictureMode::Game,
-        // PictureMode::Auto,
-    ];
-    ResponseOperator.audioOutputMode = get_rdk_audio_output_modes()?;
-    ResponseOperator.audioOutputSource = get_rdk_supported_audio_source()?;
-    ResponseOperator.videoInputSource = vec![
-        //VideoInputSource::Tuner,
-        // VideoInputSource::HDMI1,
-        // VideoInputSource::HDMI2,
-        // VideoInputSource::HDMI3,
-        // VideoInputSource::HDMI4,
-        // VideoInputSource::Composite,
-        // VideoInputSource::Component,
-        VideoInputSource::Home,
-        // VideoInputSource::Cast,
-    ];
+    ResponseOperator.matchContentFrameRate = display::list_match_content_frame_rates(thunder);
+
+    ResponseOperator.pictureMode = display::list_picture_modes(thunder);
+    ResponseOperator.audioOutputMode = get_rdk_audio_output_modes(thunder)?;
+    ResponseOperator.audioOutputSource = get_rdk_supported_audio_source(thunder)?;
+    ResponseOperator.videoInputSource = input::list_video_input_sources(thunder);
+
+    #[cfg(feature = "2_1")]
+    {
+        ResponseOperator.closedCaptions = is_supported(accessibility::get_closed_captions(thunder));
+        ResponseOperator.captionsLanguage = accessibility::list_captions_languages(thunder);
+        ResponseOperator.audioDescription = is_supported(accessibility::get_audio_description(thunder));
+        ResponseOperator.highContrastText = is_supported(accessibility::get_high_contrast_text(thunder));
+        // No RDK plugin magnifies the screen.
+        ResponseOperator.screenMagnification = false;
+        ResponseOperator.textToSpeechVoice = accessibility::list_text_to_speech_voices(thunder);
+        ResponseOperator.textToSpeechRate = accessibility::list_text_to_speech_rates(thunder);
+    }
 
     // *******************************************************************
-    Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    Ok(ResponseOperator)
+}
+
+pub fn process(thunder: &ThunderClient, _dab_request: ListSystemSettingsRequest) -> Result<String, DabError> {
+    Ok(serde_json::to_string(&get_rdk_settings_list(thunder)?).unwrap())
 }
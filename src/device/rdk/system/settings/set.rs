@@ -4,30 +4,39 @@ use crate::dab::structs::DabError;
 use crate::dab::structs::HdrOutputMode;
 use crate::dab::structs::OutputResolution;
 use crate::dab::structs::SetSystemSettingsRequest;
-use crate::device::rdk::interface::rdk_request_with_params;
 use crate::device::rdk::interface::RdkResponseSimple;
 
 use crate::device::rdk::system::settings::get::get_rdk_audio_port;
 use crate::device::rdk::system::settings::get::get_rdk_hdr_current_setting;
+use crate::device::rdk::system::settings::get::get_rdk_settings;
+use crate::device::rdk::system::settings::get::read_rdk_settings;
 use crate::device::rdk::system::settings::list::get_rdk_hdr_settings;
+use crate::device::rdk::system::settings::list::get_rdk_settings_list;
 use crate::device::rdk::system::settings::list::get_rdk_supported_audio_modes;
-use crate::hw_specific::interface::RdkResponse;
-use crate::hw_specific::interface::get_audio_volume_range;
-use crate::hw_specific::system::settings::get::get_rdk_connected_video_displays;
-
+use crate::device::rdk::interface::RdkResponse;
+use crate::device::rdk::interface::get_audio_volume_range;
+use crate::device::rdk::system::settings::get::get_rdk_connected_video_displays;
+use crate::device::rdk::interface::ThunderClient;
+#[cfg(feature = "2_1")]
+use crate::device::rdk::system::settings::accessibility;
+use crate::device::rdk::system::settings::display;
+use crate::device::rdk::system::settings::input;
+use crate::device::rdk::system::settings::input::ActiveInput;
+use crate::device::rdk::system::settings::activate_plugin;
+
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 use serde_json::Value;
-use std::collections::HashMap;
 
-fn set_rdk_language(language: String) -> Result<(), DabError> {
+fn set_rdk_language(thunder: &ThunderClient, language: String) -> Result<(), DabError> {
     #[derive(Serialize)]
     #[allow(non_snake_case)]
     struct Param {
         presentationLanguage: String,
     }
 
-    let rdkresponse: RdkResponse<Option<String>> = rdk_request_with_params(
+    let rdkresponse: RdkResponse<Option<String>> = thunder.rdk_request_with_params(
         "org.rdk.UserSettings.setPresentationLanguage",
         Param {
             presentationLanguage: language,
@@ -59,7 +68,7 @@ pub fn convert_resolution_to_string(resolution: &OutputResolution) -> Result<Str
     Err(DabError::Err500("Unsupported video format".to_string()))
 }
 
-fn set_rdk_resolution(resolution: &OutputResolution) -> Result<(), DabError> {
+fn set_rdk_resolution(thunder: &ThunderClient, resolution: &OutputResolution) -> Result<(), DabError> {
     #[allow(non_snake_case)]
     #[allow(dead_code)]
     #[derive(Serialize, Deserialize)]
@@ -71,19 +80,19 @@ fn set_rdk_resolution(resolution: &OutputResolution) -> Result<(), DabError> {
     }
 
     let req_params = Param {
-        videoDisplay: get_rdk_connected_video_displays()?,
+        videoDisplay: get_rdk_connected_video_displays(thunder)?,
         resolution: convert_resolution_to_string(resolution)?,
         persist: true,
         // ignoreEdid: false, Optional parameter; expected to fail the set call if resolution is not supported by the sink.
     };
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.DisplaySettings.setCurrentResolution", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.setCurrentResolution", req_params)?;
 
     Ok(())
 }
 
-fn set_rdk_audio_volume(volume: u32) -> Result<(), DabError> {
+fn set_rdk_audio_volume(thunder: &ThunderClient, volume: u32) -> Result<(), DabError> {
 
     let range = get_audio_volume_range();
     if !(range.min..=range.max).contains(&volume) {
@@ -99,16 +108,16 @@ fn set_rdk_audio_volume(volume: u32) -> Result<(), DabError> {
 
     let req_params = Param {
         volumeLevel: volume,
-        audioPort: get_rdk_audio_port()?,
+        audioPort: get_rdk_audio_port(thunder)?,
     };
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.DisplaySettings.setVolumeLevel", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.setVolumeLevel", req_params)?;
 
     Ok(())
 }
 
-fn set_rdk_mute(mute: bool) -> Result<(), DabError> {
+fn set_rdk_mute(thunder: &ThunderClient, mute: bool) -> Result<(), DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -118,16 +127,16 @@ fn set_rdk_mute(mute: bool) -> Result<(), DabError> {
 
     let req_params = Param {
         muted: mute,
-        audioPort: get_rdk_audio_port()?,
+        audioPort: get_rdk_audio_port(thunder)?,
     };
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.DisplaySettings.setMuted", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.setMuted", req_params)?;
 
     Ok(())
 }
 
-fn set_rdk_cec(enabled: bool) -> Result<(), DabError> {
+fn set_rdk_cec(thunder: &ThunderClient, enabled: bool) -> Result<(), DabError> {
     #[derive(Serialize)]
     struct Param {
         enabled: bool,
@@ -136,12 +145,12 @@ fn set_rdk_cec(enabled: bool) -> Result<(), DabError> {
     let req_params = Param { enabled };
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.HdmiCecSource.setEnabled", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.HdmiCecSource.setEnabled", req_params)?;
 
     Ok(())
 }
 
-fn set_rdk_audio_output_source(source: AudioOutputSource) -> Result<(), DabError> {
+fn set_rdk_audio_output_source(thunder: &ThunderClient, source: AudioOutputSource) -> Result<(), DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -159,12 +168,12 @@ fn set_rdk_audio_output_source(source: AudioOutputSource) -> Result<(), DabError
     }
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.DisplaySettings.setEnableAudioPort", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.setEnableAudioPort", req_params)?;
     Ok(())
 }
 
-fn set_rdk_hdr_mode(mode: HdrOutputMode) -> Result<(), DabError> {
-    match get_rdk_hdr_settings() {
+fn set_rdk_hdr_mode(thunder: &ThunderClient, mode: HdrOutputMode) -> Result<(), DabError> {
+    match get_rdk_hdr_settings(thunder) {
         Ok(supported_modes) => {
             if !supported_modes.contains(&mode) {
                 return Err(DabError::Err400(format!(
@@ -176,7 +185,7 @@ fn set_rdk_hdr_mode(mode: HdrOutputMode) -> Result<(), DabError> {
         }
     }
 
-    match get_rdk_hdr_current_setting() {
+    match get_rdk_hdr_current_setting(thunder) {
         Ok(current_mode) => {
             if mode == current_mode {
                 return Ok(());
@@ -212,18 +221,18 @@ fn set_rdk_hdr_mode(mode: HdrOutputMode) -> Result<(), DabError> {
     }
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.DisplaySettings.setForceHDRMode", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.setForceHDRMode", req_params)?;
     Ok(())
 }
 
-fn rdk_sound_mode_from_dab(mode: AudioOutputMode, port: &String) -> Result<String, DabError> {
+fn rdk_sound_mode_from_dab(thunder: &ThunderClient, mode: AudioOutputMode, port: &String) -> Result<String, DabError> {
     use AudioOutputMode::*;
 
     match mode {
         Stereo => Ok("STEREO".to_string()),
         PassThrough => Ok("PASSTHRU".to_string()),
         Auto => Ok("AUTO".to_string()),
-        MultichannelPcm => get_rdk_supported_audio_modes(port)?
+        MultichannelPcm => get_rdk_supported_audio_modes(thunder, port)?
             .iter()
             .find(|mode| ["SURROUND", "DOLBYDIGITAL", "DOLBYDIGITALPLUS"].contains(&mode.as_str()))
             .cloned()
@@ -233,7 +242,7 @@ fn rdk_sound_mode_from_dab(mode: AudioOutputMode, port: &String) -> Result<Strin
     }
 }
 
-fn set_rdk_audio_output_mode(mode: AudioOutputMode) -> Result<(), DabError> {
+fn set_rdk_audio_output_mode(thunder: &ThunderClient, mode: AudioOutputMode) -> Result<(), DabError> {
     #[allow(non_snake_case)]
     #[derive(Default, Serialize)]
     struct Param {
@@ -242,18 +251,18 @@ fn set_rdk_audio_output_mode(mode: AudioOutputMode) -> Result<(), DabError> {
     }
 
     let mut req_params = Param {
-        audioPort: get_rdk_audio_port()?,
+        audioPort: get_rdk_audio_port(thunder)?,
         ..Default::default()
     };
-    req_params.soundMode = rdk_sound_mode_from_dab(mode, &req_params.audioPort)?;
+    req_params.soundMode = rdk_sound_mode_from_dab(thunder, mode, &req_params.audioPort)?;
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.DisplaySettings.setSoundMode", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.DisplaySettings.setSoundMode", req_params)?;
 
     Ok(())
 }
 
-fn set_rdk_text_to_speech(val: bool) -> Result<(), DabError> {
+fn set_rdk_text_to_speech(thunder: &ThunderClient, val: bool) -> Result<(), DabError> {
     #[allow(non_snake_case)]
     #[derive(Serialize)]
     struct Param {
@@ -263,46 +272,167 @@ fn set_rdk_text_to_speech(val: bool) -> Result<(), DabError> {
     let req_params = Param { enabletts: val };
 
     let _rdkresponse: RdkResponseSimple =
-        rdk_request_with_params("org.rdk.TextToSpeech.enabletts", req_params)?;
+        thunder.rdk_request_with_params("org.rdk.TextToSpeech.enabletts", req_params)?;
 
     Ok(())
 }
 
-pub fn process(_dab_request: SetSystemSettingsRequest) -> Result<String, DabError> {
-    let _packet = serde_json::to_string(&_dab_request).unwrap();
-    let mut json_map: HashMap<&str, Value> = serde_json::from_str(&_packet).unwrap();
+// The settings in the order they are applied: the video input before the display
+// settings, the output resolution before the video processing, and the audio output
+// source before its mode, volume and mute, which apply to the selected port.
+const SETTINGS_ORDER: &[&str] = &[
+    "language",
+    "videoInputSource",
+    "outputResolution",
+    "hdrOutputMode",
+    "matchContentFrameRate",
+    "pictureMode",
+    "memc",
+    "lowLatencyMode",
+    "cec",
+    "audioOutputSource",
+    "audioOutputMode",
+    "audioVolume",
+    "mute",
+    "textToSpeech",
+    #[cfg(feature = "2_1")]
+    "closedCaptions",
+    #[cfg(feature = "2_1")]
+    "captionsLanguage",
+    #[cfg(feature = "2_1")]
+    "audioDescription",
+    #[cfg(feature = "2_1")]
+    "highContrastText",
+    #[cfg(feature = "2_1")]
+    "screenMagnification",
+    #[cfg(feature = "2_1")]
+    "textToSpeechVoice",
+    #[cfg(feature = "2_1")]
+    "textToSpeechRate",
+];
+
+// Checks the value against the capabilities reported by system/settings/list.
+fn validate_setting(capabilities: &Value, key: &str, value: &Value) -> Result<(), DabError> {
+    let supported = match &capabilities[key] {
+        Value::Array(values) => values.contains(value),
+        Value::Bool(supported) => *supported,
+        // The audio volume range.
+        Value::Object(range) => match (range["min"].as_u64(), range["max"].as_u64(), value.as_u64()) {
+            (Some(min), Some(max), Some(value)) => (min..=max).contains(&value),
+            _ => false,
+        },
+        _ => false,
+    };
+    if !supported {
+        return Err(DabError::Err400(format!(
+            "Setting '{}' to {} is not supported", key, value)));
+    }
+    Ok(())
+}
 
-    for (key, value) in json_map.iter_mut() {
-        match *key {
-            "language" => {
-                set_rdk_language(serde_json::from_value::<String>(value.take()).unwrap())?
-            }
-            "outputResolution" => set_rdk_resolution(
-                &serde_json::from_value::<OutputResolution>(value.take()).unwrap(),
-            )?,
-            "audioVolume" => {
-                set_rdk_audio_volume(serde_json::from_value::<u32>(value.take()).unwrap())?
-            }
-            "mute" => set_rdk_mute(value.take().as_bool().unwrap())?,
-            "cec" => set_rdk_cec(value.take().as_bool().unwrap())?,
-            "audioOutputMode" => set_rdk_audio_output_mode(
-                serde_json::from_value::<AudioOutputMode>(value.take()).unwrap(),
-            )?,
-            "audioOutputSource" => set_rdk_audio_output_source(
-                serde_json::from_value::<AudioOutputSource>(value.take()).unwrap(),
-            )?,
-            "hdrOutputMode" => {
-                set_rdk_hdr_mode(serde_json::from_value::<HdrOutputMode>(value.take()).unwrap())?
-            }
-            "textToSpeech" => set_rdk_text_to_speech(value.take().as_bool().unwrap())?,
-            "pictureMode" | "videoInputSource" | "lowLatencyMode" | _ => {
-                return Err(DabError::Err400(format!(
-                    "Setting '{}' is not supported",
-                    key
-                )))
+// The optional plugins of a setting, which read as missing until activated.
+fn setting_plugins(key: &str) -> &'static [&'static str] {
+    match key {
+        "pictureMode" | "memc" => &[display::CONTROL_SETTINGS],
+        "lowLatencyMode" => &[display::DISPLAY_SETTINGS],
+        "matchContentFrameRate" => &[display::FRAME_RATE],
+        "videoInputSource" => &[input::HDMI_INPUT, input::AV_INPUT],
+        _ => &[],
+    }
+}
+
+fn setting_value<T: DeserializeOwned>(key: &str, value: Value) -> Result<T, DabError> {
+    serde_json::from_value(value)
+        .map_err(|e| DabError::Err400(format!("Invalid value for setting '{}': {}", key, e)))
+}
+
+fn apply_setting(
+    thunder: &ThunderClient,
+    active_input: &ActiveInput,
+    key: &str,
+    value: Value,
+) -> Result<(), DabError> {
+    match key {
+        "language" => set_rdk_language(thunder, setting_value(key, value)?),
+        "outputResolution" => set_rdk_resolution(thunder, &setting_value(key, value)?),
+        "audioVolume" => set_rdk_audio_volume(thunder, setting_value(key, value)?),
+        "mute" => set_rdk_mute(thunder, setting_value(key, value)?),
+        "cec" => set_rdk_cec(thunder, setting_value(key, value)?),
+        "audioOutputMode" => set_rdk_audio_output_mode(thunder, setting_value(key, value)?),
+        "audioOutputSource" => set_rdk_audio_output_source(thunder, setting_value(key, value)?),
+        "hdrOutputMode" => set_rdk_hdr_mode(thunder, setting_value(key, value)?),
+        "textToSpeech" => set_rdk_text_to_speech(thunder, setting_value(key, value)?),
+        "pictureMode" => display::set_picture_mode(thunder, setting_value(key, value)?),
+        "memc" => display::set_memc(thunder, setting_value(key, value)?),
+        "lowLatencyMode" => display::set_low_latency_mode(thunder, setting_value(key, value)?),
+        "matchContentFrameRate" => display::set_match_content_frame_rate(thunder, setting_value(key, value)?),
+        "videoInputSource" => input::set_video_input_source(thunder, active_input, setting_value(key, value)?),
+        #[cfg(feature = "2_1")]
+        "closedCaptions" => accessibility::set_closed_captions(thunder, setting_value(key, value)?),
+        #[cfg(feature = "2_1")]
+        "captionsLanguage" => accessibility::set_captions_language(thunder, setting_value(key, value)?),
+        #[cfg(feature = "2_1")]
+        "audioDescription" => accessibility::set_audio_description(thunder, setting_value(key, value)?),
+        #[cfg(feature = "2_1")]
+        "highContrastText" => accessibility::set_high_contrast_text(thunder, setting_value(key, value)?),
+        #[cfg(feature = "2_1")]
+        "textToSpeechVoice" => accessibility::set_text_to_speech_voice(thunder, setting_value(key, value)?),
+        #[cfg(feature = "2_1")]
+        "textToSpeechRate" => accessibility::set_text_to_speech_rate(thunder, setting_value(key, value)?),
+        _ => Err(DabError::Err400(format!(
+            "Setting '{}' is not supported",
+            key
+        ))),
+    }
+}
+
+// Applies the requested settings, all or none: every value is validated before the
+// device is touched, and the settings already applied are restored when one fails.
+// Responds with the settings read back from the device.
+pub fn process(
+    thunder: &ThunderClient,
+    active_input: &ActiveInput,
+    _dab_request: SetSystemSettingsRequest,
+) -> Result<String, DabError> {
+    let requested = serde_json::to_value(&_dab_request).unwrap();
+    let requested = requested.as_object().cloned().unwrap_or_default();
+
+    for key in requested.keys() {
+        for plugin in setting_plugins(key) {
+            activate_plugin(thunder, plugin);
+        }
+    }
+    let capabilities = serde_json::to_value(get_rdk_settings_list(thunder)?).unwrap();
+    for (key, value) in requested.iter() {
+        if !SETTINGS_ORDER.contains(&key.as_str()) {
+            return Err(DabError::Err400(format!(
+                "Setting '{}' is not supported",
+                key
+            )));
+        }
+        validate_setting(&capabilities, key, value)?;
+    }
+
+    let (previous, read) = read_rdk_settings(thunder, active_input)?;
+    let previous = serde_json::to_value(previous).unwrap();
+    let mut applied: Vec<&str> = vec![];
+    for &key in SETTINGS_ORDER {
+        let Some(value) = requested.get(key) else { continue };
+        if let Err(e) = apply_setting(thunder, active_input, key, value.clone()) {
+            for key in applied.iter().rev() {
+                // A setting whose previous value wasn't read is left as is.
+                if !read.contains(key) {
+                    println!("Can't restore setting '{}': its previous value is unknown", key);
+                    continue;
+                }
+                if let Err(rollback_error) = apply_setting(thunder, active_input, key, previous[*key].clone()) {
+                    println!("Failed to restore setting '{}': {:?}", key, rollback_error);
+                }
             }
+            return Err(e);
         }
+        applied.push(key);
     }
 
-    Ok("{}".to_string())
+    Ok(serde_json::to_string(&get_rdk_settings(thunder, active_input)?).unwrap())
 }
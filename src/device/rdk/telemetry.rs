@@ -0,0 +1,213 @@
+// Collectors of the device telemetry metrics, enabled by name in the
+// "telemetry_metrics" list of /etc/dab/settings.json.
+
+use crate::dab::structs::DabError;
+use crate::device::rdk::interface::{get_device_cpu, get_device_memory, ThunderClient};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub const DEFAULT_METRICS: [&str; 2] = ["memory", "cpu"];
+
+// Persistent storage of the RDK devices.
+const STORAGE_PATH: &str = "/opt";
+
+// A source of device telemetry metrics; a collector may report several metrics,
+// e.g. one per network interface.
+pub trait MetricCollector: Send + Sync {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError>;
+}
+
+// Used memory in KB.
+struct MemoryCollector;
+
+impl MetricCollector for MemoryCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        Ok(vec![("memory".to_string(), get_device_memory(thunder)? as f64)])
+    }
+}
+
+// CPU load in percent.
+struct CpuCollector;
+
+impl MetricCollector for CpuCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        Ok(vec![("cpu".to_string(), get_device_cpu(thunder)? as f64)])
+    }
+}
+
+// SoC temperature in degrees Celsius.
+struct TemperatureCollector;
+
+impl MetricCollector for TemperatureCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        let temperature = thunder.get_thunder_property("org.rdk.System.1.getCoreTemperature", "temperature")?;
+        let temperature = temperature.trim().parse::<f64>()
+            .map_err(|_| DabError::Err500("Failed to parse temperature".to_string()))?;
+        Ok(vec![("temperature".to_string(), temperature)])
+    }
+}
+
+// Used storage in percent. Read with df, so only when Thunder runs on this device.
+struct StorageCollector;
+
+impl MetricCollector for StorageCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        if !thunder.is_local() {
+            return Err(DabError::Err501("storage metric requires a local Thunder".to_string()));
+        }
+        let output = Command::new("df")
+            .args(["-kP", STORAGE_PATH])
+            .output()
+            .map_err(|e| DabError::Err500(format!("Failed to run df: {}", e)))?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        // Filesystem 1024-blocks Used Available Capacity Mounted on
+        let usage = output
+            .lines()
+            .nth(1)
+            .and_then(|line| line.split_whitespace().nth(4))
+            .and_then(|capacity| capacity.trim_end_matches('%').parse::<f64>().ok())
+            .ok_or(DabError::Err500(format!("Failed to read the storage usage of {}", STORAGE_PATH)))?;
+        Ok(vec![("storage".to_string(), usage)])
+    }
+}
+
+// Received and transmitted bytes of each network interface.
+type InterfaceCounters = HashMap<String, (u64, u64)>;
+
+// Received and transmitted bytes per second of each network interface, from the
+// counters of /proc/net/dev; the first collection only records them.
+pub(super) struct NetworkCollector {
+    path: PathBuf,
+    previous: Mutex<Option<(Instant, InterfaceCounters)>>,
+}
+
+impl NetworkCollector {
+    pub(super) fn new(path: PathBuf) -> NetworkCollector {
+        NetworkCollector {
+            path,
+            previous: Mutex::new(None),
+        }
+    }
+}
+
+fn parse_net_dev(text: &str) -> InterfaceCounters {
+    // Two header lines, then "iface: rx_bytes rx_packets ... (8 rx fields) tx_bytes ..."
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (interface, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .map(|counter| counter.parse().unwrap_or(0))
+                .collect();
+            Some((interface.trim().to_string(), (*counters.first()?, *counters.get(8)?)))
+        })
+        .filter(|(interface, _)| interface != "lo")
+        .collect()
+}
+
+impl MetricCollector for NetworkCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        if !thunder.is_local() {
+            return Err(DabError::Err501("network metrics require a local Thunder".to_string()));
+        }
+        let text = fs::read_to_string(&self.path)
+            .map_err(|e| DabError::Err500(format!("Failed to read {}: {}", self.path.display(), e)))?;
+        let now = Instant::now();
+        let counters = parse_net_dev(&text);
+
+        let mut metrics = vec![];
+        let mut previous = self.previous.lock().unwrap();
+        if let Some((then, previous_counters)) = previous.as_ref() {
+            let seconds = now.duration_since(*then).as_secs_f64();
+            for (interface, (rx, tx)) in &counters {
+                if let Some((previous_rx, previous_tx)) = previous_counters.get(interface) {
+                    // A counter reset, on overflow or when the interface restarts, reports 0.
+                    let rx_rate = rx.saturating_sub(*previous_rx) as f64 / seconds;
+                    let tx_rate = tx.saturating_sub(*previous_tx) as f64 / seconds;
+                    metrics.push((format!("network_rx_{}", interface), rx_rate));
+                    metrics.push((format!("network_tx_{}", interface), tx_rate));
+                }
+            }
+            metrics.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        *previous = Some((now, counters));
+        Ok(metrics)
+    }
+}
+
+// Number of activated Thunder plugins.
+struct PluginsCollector;
+
+impl MetricCollector for PluginsCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        let response: Value = thunder.rdk_request("Controller.1.status")?;
+        let plugins = response["result"]
+            .as_array()
+            .ok_or(DabError::Err500("Failed to read the Thunder plugins".to_string()))?;
+        let activated = plugins
+            .iter()
+            .filter(|plugin| plugin["state"] == "activated")
+            .count();
+        Ok(vec![("plugins".to_string(), activated as f64)])
+    }
+}
+
+// Time since boot in seconds.
+struct UptimeCollector;
+
+impl MetricCollector for UptimeCollector {
+    fn collect(&self, thunder: &ThunderClient) -> Result<Vec<(String, f64)>, DabError> {
+        let uptime = thunder.get_thunder_property("DeviceInfo.systeminfo", "uptime")?;
+        let uptime = uptime.parse::<f64>()
+            .map_err(|_| DabError::Err500("Failed to parse uptime".to_string()))?;
+        Ok(vec![("uptime".to_string(), uptime)])
+    }
+}
+
+pub fn collector(name: &str) -> Option<Box<dyn MetricCollector>> {
+    match name {
+        "memory" => Some(Box::new(MemoryCollector)),
+        "cpu" => Some(Box::new(CpuCollector)),
+        "temperature" => Some(Box::new(TemperatureCollector)),
+        "storage" => Some(Box::new(StorageCollector)),
+        "network" => Some(Box::new(NetworkCollector::new(PathBuf::from("/proc/net/dev")))),
+        "plugins" => Some(Box::new(PluginsCollector)),
+        "uptime" => Some(Box::new(UptimeCollector)),
+        _ => None,
+    }
+}
+
+pub fn collectors<S: AsRef<str>>(names: &[S]) -> Vec<Box<dyn MetricCollector>> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let collector = collector(name.as_ref());
+            if collector.is_none() {
+                eprintln!("Unknown telemetry metric: {}", name.as_ref());
+            }
+            collector
+        })
+        .collect()
+}
+
+// The metrics of all the collectors; the metrics of a failing collector are left out.
+pub fn collect(thunder: &ThunderClient, collectors: &[Box<dyn MetricCollector>]) -> Vec<(String, f64)> {
+    let mut metrics = vec![];
+    for collector in collectors {
+        match collector.collect(thunder) {
+            Ok(mut collected) => metrics.append(&mut collected),
+            Err(e) => {
+                if thunder.debug() {
+                    println!("Skipping telemetry metric: {:?}", e);
+                }
+            }
+        }
+    }
+    metrics
+}
@@ -0,0 +1,129 @@
+// Runs the DAB operations of the RDK backend against the mock Thunder server.
+
+use super::mock_thunder::{MockThunder, Reply};
+use crate::dab::call_function;
+use crate::dab::structs::{DabError, RequestTypes};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+mod applications;
+mod device;
+mod health_check;
+mod input;
+mod operations;
+mod output;
+mod settings;
+mod system;
+mod telemetry;
+mod thunder;
+mod version;
+mod voice;
+
+fn call(mock: &MockThunder, request_type: RequestTypes, request: Value) -> Result<Value, DabError> {
+    call_function(mock.platform(), request.to_string(), request_type)
+        .map(|response| serde_json::from_str(&response).unwrap())
+}
+
+fn assert_err400(result: Result<Value, DabError>) {
+    match result {
+        Err(DabError::Err400(_)) => {}
+        other => panic!("expected a 400 error, got {:?}", other),
+    }
+}
+
+fn assert_err500(result: Result<Value, DabError>, message: &str) {
+    match result {
+        Err(DabError::Err500(error)) => assert!(
+            error.contains(message),
+            "expected a 500 error containing {:?}, got {:?}",
+            message,
+            error
+        ),
+        other => panic!("expected a 500 error, got {:?}", other),
+    }
+}
+
+// Scripts RDKShell with one application, whose state follows the lifecycle
+// requests: None when it's not running, otherwise the RDKShell state. The
+// lifecycle events are sent like RDKShell does.
+fn script_rdkshell(mock: &MockThunder, callsign: &str, state: Option<&str>) -> Arc<Mutex<Option<String>>> {
+    let app_state = Arc::new(Mutex::new(state.map(|state| state.to_string())));
+
+    let client = callsign.to_string();
+    let current = app_state.clone();
+    mock.handle("org.rdk.RDKShell.getState", move |_| {
+        let state = match current.lock().unwrap().as_ref() {
+            Some(state) => vec![json!({"callsign": client, "state": state, "uri": ""})],
+            None => vec![],
+        };
+        Reply::Result(json!({"state": state, "success": true}))
+    });
+    let current = app_state.clone();
+    mock.handle("org.rdk.RDKShell.getVisibility", move |_| {
+        let visible = current.lock().unwrap().as_deref() == Some("resumed");
+        Reply::Result(json!({"visible": visible, "success": true}))
+    });
+    for (method, next_state, event) in [
+        ("org.rdk.RDKShell.launch", Some("resumed"), Some("onLaunched")),
+        ("org.rdk.RDKShell.suspend", Some("suspended"), Some("onSuspended")),
+        ("org.rdk.RDKShell.restore", Some("suspended"), None),
+        ("org.rdk.RDKShell.destroy", None, Some("onDestroyed")),
+    ] {
+        let current = app_state.clone();
+        let events = mock.events();
+        let client = callsign.to_string();
+        mock.handle(method, move |_| {
+            *current.lock().unwrap() = next_state.map(|state| state.to_string());
+            if let Some(event) = event {
+                events.send("org.rdk.RDKShell", event, json!({"client": client}));
+            }
+            Reply::Result(json!({"launchType": "activate", "success": true}))
+        });
+    }
+    for method in [
+        "org.rdk.RDKShell.moveToFront",
+        "org.rdk.RDKShell.setFocus",
+        "org.rdk.RDKShell.setVisibility",
+    ] {
+        mock.on_result(method, json!({"success": true}));
+    }
+    mock.on_result("org.rdk.UserSettings.getPresentationLanguage", json!("en-US"));
+
+    app_state
+}
+
+fn script_voice_status(mock: &MockThunder, ptt_status: &str) {
+    mock.on_result(
+        "org.rdk.VoiceControl.voiceStatus",
+        json!({
+            "capabilities": ["PRV"],
+            "urlPtt": "avs://amazon-alexa",
+            "urlHf": "",
+            "prv": true,
+            "wwFeedback": false,
+            "ptt": {"status": ptt_status},
+            "ff": {"status": "disabled"},
+            "success": true
+        }),
+    );
+}
+
+fn script_applications(mock: &MockThunder) {
+    mock.on_result(
+        "org.rdk.RDKShell.getAvailableTypes",
+        json!({"types": ["YouTube"], "success": true}),
+    );
+}
+
+// getPowerState reports the state last set, starting from this one.
+fn script_power_state(mock: &MockThunder, state: &str) {
+    let power_state = Arc::new(Mutex::new(state.to_string()));
+    let current = power_state.clone();
+    mock.handle("org.rdk.System.getPowerState", move |_| {
+        Reply::Result(json!({"powerState": *current.lock().unwrap(), "success": true}))
+    });
+    mock.handle("org.rdk.System.setPowerState", move |params| {
+        *power_state.lock().unwrap() = params["powerState"].as_str().unwrap().to_string();
+        Reply::Result(json!({"success": true}))
+    });
+}
@@ -0,0 +1,617 @@
+use super::*;
+#[cfg(feature = "2_1")]
+use crate::device::DabPlatform;
+use crate::device::rdk::applications::registry::{parse_apps, TemplateValues};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn applications_list_maps_rdkshell_types() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.RDKShell.getAvailableTypes",
+        json!({"types": ["YouTube", "Amazon", "Netflix", "HtmlApp"], "success": true}),
+    );
+
+    let response = call(&mock, RequestTypes::ApplicationListRequest, json!({})).unwrap();
+    assert_eq!(
+        response,
+        json!({"applications": [{"appId": "YouTube"}, {"appId": "PrimeVideo"}, {"appId": "Netflix"}]})
+    );
+}
+
+#[test]
+fn applications_list_reports_thunder_error() {
+    let mock = MockThunder::start();
+    mock.on_error("org.rdk.RDKShell.getAvailableTypes", 2, "ERROR_UNAVAILABLE");
+
+    let result = call(&mock, RequestTypes::ApplicationListRequest, json!({}));
+    assert_err500(result, "ERROR_UNAVAILABLE");
+}
+
+#[test]
+fn applications_get_state_maps_rdkshell_states() {
+    let mock = MockThunder::start();
+
+    for (state, expected) in [
+        (None, "STOPPED"),
+        (Some("suspended"), "BACKGROUND"),
+        (Some("hibernated"), "BACKGROUND"),
+        (Some("resumed"), "FOREGROUND"),
+    ] {
+        script_rdkshell(&mock, "YouTube", state);
+        let response = call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": "YouTube"})).unwrap();
+        assert_eq!(response, json!({"state": expected}), "RDKShell state {:?}", state);
+    }
+
+    // Running but hidden.
+    script_rdkshell(&mock, "YouTube", Some("activated"));
+    mock.on_result("org.rdk.RDKShell.getVisibility", json!({"visible": false, "success": true}));
+    let response = call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(response, json!({"state": "BACKGROUND"}));
+}
+
+#[test]
+fn applications_get_state_rejects_unknown_rdkshell_state() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "YouTube", Some("deactivating"));
+
+    let result = call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": "YouTube"}));
+    assert_err500(result, "invalid state deactivating");
+}
+
+#[test]
+fn applications_get_state_rejects_malformed_response() {
+    let mock = MockThunder::start();
+    mock.on_result("org.rdk.RDKShell.getState", json!({"success": true}));
+
+    let result = call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": "YouTube"}));
+    assert_err500(result, "state");
+
+    mock.on_raw("org.rdk.RDKShell.getState", "<html>Bad Gateway</html>");
+    let result = call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": "YouTube"}));
+    assert_err500(result, "");
+}
+
+#[test]
+fn applications_get_state_requires_app_id() {
+    let mock = MockThunder::start();
+
+    assert_err400(call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": ""})));
+}
+
+#[test]
+fn applications_launch_cold_starts_cobalt() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "YouTube", None);
+
+    let response = call(
+        &mock,
+        RequestTypes::ApplicationLaunchRequest,
+        json!({"appId": "YouTube", "parameters": ["launch%3Dmenu"]}),
+    )
+    .unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(app_state.lock().unwrap().as_deref(), Some("resumed"));
+
+    let launch = &mock.calls("org.rdk.RDKShell.launch")[0];
+    assert_eq!(launch["callsign"], "YouTube");
+    assert_eq!(launch["type"], "Cobalt");
+    let configuration: Value = serde_json::from_str(launch["configuration"].as_str().unwrap()).unwrap();
+    assert_eq!(configuration["url"], "https://www.youtube.com/tv?launch=menu");
+    assert_eq!(configuration["language"], "en-US");
+    assert_eq!(mock.calls("org.rdk.RDKShell.setFocus")[0]["client"], "YouTube");
+}
+
+#[test]
+fn applications_launch_uses_registry_callsign_and_type() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Amazon", None);
+
+    let response = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "PrimeVideo"})).unwrap();
+    assert_eq!(response, json!({}));
+
+    let launch = &mock.calls("org.rdk.RDKShell.launch")[0];
+    assert_eq!(launch["callsign"], "Amazon");
+    assert_eq!(launch["type"], "Amazon");
+    assert_eq!(launch["configuration"], Value::Null);
+
+    let response = call(&mock, RequestTypes::ApplicationGetStateRequest, json!({"appId": "PrimeVideo"})).unwrap();
+    assert_eq!(response, json!({"state": "FOREGROUND"}));
+}
+
+#[test]
+fn applications_launch_resolves_aliases() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "YouTube", Some("suspended"));
+
+    call(
+        &mock,
+        RequestTypes::ApplicationLaunchRequest,
+        json!({"appId": "cobalt", "parameters": ["v%3DdQw4w9WgXcQ"]}),
+    )
+    .unwrap();
+    assert_eq!(app_state.lock().unwrap().as_deref(), Some("resumed"));
+    assert_eq!(
+        mock.calls("YouTube.deeplink"),
+        vec![json!("https://www.youtube.com/tv?v=dQw4w9WgXcQ")]
+    );
+}
+
+#[test]
+fn app_registry_file_replaces_and_adds_apps() {
+    let apps = parse_apps(
+        r#"[
+            {"app_id": "Netflix", "type": "Netflix", "callsign": "netflix",
+             "lifecycle_timeouts": {"cold_launch_timeout_ms": 8000}},
+            {"app_id": "uk.co.bbc.iPlayer", "aliases": ["iPlayer"], "type": "HtmlApp",
+             "launch_configuration": {"url": "https://www.bbc.co.uk/iplayer/tv?{parameters}"}}
+        ]"#,
+    )
+    .unwrap();
+
+    let app_ids: Vec<&str> = apps.iter().map(|app| app.app_id.as_str()).collect();
+    assert_eq!(app_ids, ["YouTube", "PrimeVideo", "Netflix", "uk.co.bbc.iPlayer"]);
+
+    let netflix = &apps[2];
+    assert_eq!(netflix.callsign, "netflix");
+    assert_eq!(netflix.launch_configuration, None);
+    assert_eq!(netflix.lifecycle_timeouts["cold_launch_timeout_ms"], 8000);
+
+    let iplayer = apps.iter().find(|app| app.matches("iplayer")).unwrap();
+    assert_eq!(iplayer.callsign, "uk.co.bbc.iPlayer");
+    assert_eq!(iplayer.available_type, "HtmlApp");
+    let mut values = TemplateValues::new();
+    values.insert("parameters", "a=1&b=2".to_string());
+    assert_eq!(
+        iplayer.launch_configuration(&values).unwrap(),
+        json!({"url": "https://www.bbc.co.uk/iplayer/tv?a=1&b=2"}).to_string()
+    );
+
+    assert!(parse_apps(r#"[{"app_id": "NoType"}]"#).is_err());
+}
+
+#[test]
+fn applications_launch_waits_for_launched_event() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "Netflix", None);
+    // The plugin is still starting when the launch request returns.
+    let events = mock.events();
+    mock.handle("org.rdk.RDKShell.launch", move |_| {
+        let app_state = app_state.clone();
+        let events = events.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            *app_state.lock().unwrap() = Some("resumed".to_string());
+            events.send("org.rdk.RDKShell", "onLaunched", json!({"client": "netflix", "launchType": "create"}));
+        });
+        Reply::Result(json!({"launchType": "create", "success": true}))
+    });
+
+    let response = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "Netflix"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(mock.calls("org.rdk.RDKShell.getState").len(), 3);
+    assert_eq!(mock.calls("org.rdk.RDKShell.register")[0]["event"], "onLaunched");
+}
+
+#[test]
+fn applications_launch_polls_the_state_after_launched_event() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "Netflix", None);
+    // RDKShell notifies onLaunched before the app is resumed.
+    let events = mock.events();
+    mock.handle("org.rdk.RDKShell.launch", move |_| {
+        let app_state = app_state.clone();
+        events.send("org.rdk.RDKShell", "onLaunched", json!({"client": "Netflix", "launchType": "create"}));
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(600));
+            *app_state.lock().unwrap() = Some("resumed".to_string());
+        });
+        Reply::Result(json!({"launchType": "create", "success": true}))
+    });
+
+    let response = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "Netflix"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(mock.calls("org.rdk.RDKShell.setFocus")[0]["client"], "Netflix");
+}
+
+#[test]
+fn applications_launch_polls_the_state_without_events() {
+    let mock = MockThunder::start();
+    mock.refuse_websockets();
+    let app_state = script_rdkshell(&mock, "YouTube", None);
+
+    let response = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(app_state.lock().unwrap().as_deref(), Some("resumed"));
+    assert!(mock.calls("org.rdk.RDKShell.register").is_empty());
+}
+
+#[test]
+fn applications_launch_reports_app_not_launched() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Netflix", None);
+    mock.on_result("org.rdk.RDKShell.launch", json!({"launchType": "create", "success": true}));
+
+    let result = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "Netflix"}));
+    assert_err500(result, "app may not be visible");
+}
+
+#[test]
+fn applications_launch_reports_rdkshell_failure() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Netflix", None);
+    mock.on_result(
+        "org.rdk.RDKShell.launch",
+        json!({"message": "failed to create plugin", "success": false}),
+    );
+
+    let result = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "Netflix"}));
+    assert_err500(result, "failed to create plugin");
+}
+
+#[test]
+fn applications_launch_rejects_malformed_rdkshell_response() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Netflix", None);
+    mock.on_raw("org.rdk.RDKShell.launch", "{\"jsonrpc\":\"2.0\",\"id\":3}");
+
+    let result = call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "Netflix"}));
+    assert_err500(result, "org.rdk.RDKShell.launch");
+}
+
+#[test]
+fn applications_launch_deeplink_requires_app_support() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "MyApp", Some("resumed"));
+
+    let result = call(
+        &mock,
+        RequestTypes::ApplicationLaunchRequest,
+        json!({"appId": "MyApp", "parameters": ["source_type=12"]}),
+    );
+    assert_err500(result, "deeplinking");
+}
+
+#[test]
+fn applications_launch_with_content_cold_starts_cobalt() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "YouTube", None);
+
+    let response = call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "YouTube", "contentId": "dQw4w9WgXcQ"}),
+    )
+    .unwrap();
+    assert_eq!(response, json!({}));
+
+    let launch = &mock.calls("org.rdk.RDKShell.launch")[0];
+    let configuration: Value = serde_json::from_str(launch["configuration"].as_str().unwrap()).unwrap();
+    assert_eq!(configuration["url"], "https://www.youtube.com/tv?v=dQw4w9WgXcQ");
+}
+
+#[test]
+fn applications_launch_with_content_cold_starts_netflix() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Netflix", None);
+
+    call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "Netflix", "contentId": "80057281"}),
+    )
+    .unwrap();
+
+    let launch = &mock.calls("org.rdk.RDKShell.launch")[0];
+    let configuration: Value = serde_json::from_str(launch["configuration"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        configuration["querystring"],
+        "m=https://api-global.netflix.com/catalog/titles/movie/80057281&source_type=4"
+    );
+}
+
+#[test]
+fn applications_launch_with_content_deeplinks_running_netflix() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "Netflix", Some("suspended"));
+
+    call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "Netflix", "contentId": "80057281"}),
+    )
+    .unwrap();
+    assert_eq!(app_state.lock().unwrap().as_deref(), Some("resumed"));
+    assert_eq!(
+        mock.calls("Netflix.systemcommand"),
+        vec![json!({"command": "m=https://api-global.netflix.com/catalog/titles/movie/80057281&source_type=4"})]
+    );
+}
+
+#[test]
+fn applications_launch_with_content_deeplinks_prime_video_once_started() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Amazon", None);
+
+    call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "PrimeVideo", "contentId": "B0DKTFF815"}),
+    )
+    .unwrap();
+
+    assert_eq!(mock.calls("org.rdk.RDKShell.launch")[0]["configuration"], Value::Null);
+    assert_eq!(mock.calls("Amazon.deeplink"), vec![json!("contentId=B0DKTFF815")]);
+}
+
+#[test]
+fn applications_launch_with_content_loads_web_app_page() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "MyApp", None);
+
+    call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "MyApp", "contentId": "https://example.com/app", "parameters": ["autoplay=1"]}),
+    )
+    .unwrap();
+
+    let launch = &mock.calls("org.rdk.RDKShell.launch")[0];
+    assert_eq!(launch["type"], "LightningApp");
+    assert_eq!(launch["uri"], "https://example.com/app?autoplay=1");
+
+    // A running web application is sent to the page.
+    script_rdkshell(&mock, "MyApp", Some("resumed"));
+    call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "MyApp", "contentId": "https://example.com/app?id=42"}),
+    )
+    .unwrap();
+    assert_eq!(mock.calls("MyApp.url"), vec![json!("https://example.com/app?id=42")]);
+}
+
+#[test]
+fn applications_launch_with_content_requires_content_id() {
+    let mock = MockThunder::start();
+
+    let result = call(
+        &mock,
+        RequestTypes::ApplicationLaunchWithContentRequest,
+        json!({"appId": "Netflix", "contentId": ""}),
+    );
+    assert_err400(result);
+}
+
+#[test]
+fn app_registry_content_support() {
+    let apps = parse_apps(
+        r#"[
+            {"app_id": "Tuner", "type": "Native"},
+            {"app_id": "Player", "type": "HtmlApp", "uri": "https://player.example.com/#/play/{contentId}"}
+        ]"#,
+    )
+    .unwrap();
+    assert!(apps.iter().take(3).all(|app| app.supports_content()));
+    assert!(!apps[3].supports_content());
+
+    let mut values = TemplateValues::new();
+    values.insert("contentId", "42".to_string());
+    assert_eq!(apps[4].content_uri(&values).unwrap(), "https://player.example.com/#/play/42");
+}
+
+#[test]
+fn applications_exit_destroys_app() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "YouTube", Some("resumed"));
+
+    let response = call(&mock, RequestTypes::ApplicationExitRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(response, json!({"state": "STOPPED"}));
+    assert_eq!(*app_state.lock().unwrap(), None);
+    assert_eq!(mock.calls("org.rdk.RDKShell.destroy"), vec![json!({"callsign": "YouTube"})]);
+}
+
+#[test]
+fn applications_exit_waits_for_destroyed_event() {
+    let mock = MockThunder::start();
+    let app_state = script_rdkshell(&mock, "YouTube", Some("resumed"));
+    let events = mock.events();
+    mock.handle("org.rdk.RDKShell.destroy", move |_| {
+        let app_state = app_state.clone();
+        let events = events.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            *app_state.lock().unwrap() = None;
+            events.send("org.rdk.RDKShell", "onDestroyed", json!({"client": "YouTube"}));
+        });
+        Reply::Result(json!({"success": true}))
+    });
+
+    let response = call(&mock, RequestTypes::ApplicationExitRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(response, json!({"state": "STOPPED"}));
+}
+
+#[test]
+fn applications_exit_polls_the_state_without_events() {
+    let mock = MockThunder::start();
+    mock.refuse_websockets();
+    let app_state = script_rdkshell(&mock, "YouTube", Some("resumed"));
+    mock.handle("org.rdk.RDKShell.destroy", move |_| {
+        let app_state = app_state.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            *app_state.lock().unwrap() = None;
+        });
+        Reply::Result(json!({"success": true}))
+    });
+
+    let response = call(&mock, RequestTypes::ApplicationExitRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(response, json!({"state": "STOPPED"}));
+    assert!(mock.calls("org.rdk.RDKShell.register").is_empty());
+}
+
+#[test]
+fn applications_exit_to_background_suspends_app() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "YouTube", Some("resumed"));
+
+    let response = call(
+        &mock,
+        RequestTypes::ApplicationExitRequest,
+        json!({"appId": "YouTube", "background": true}),
+    )
+    .unwrap();
+    assert_eq!(response, json!({"state": "BACKGROUND"}));
+    assert_eq!(mock.calls("org.rdk.RDKShell.suspend"), vec![json!({"callsign": "YouTube"})]);
+}
+
+// LISA answers with a handle and reports the outcome of the operation with an event.
+#[cfg(feature = "2_1")]
+fn script_lisa_operation(mock: &MockThunder, method: &str, status: &'static str) {
+    let events = mock.events();
+    mock.handle(method, move |params| {
+        let events = events.clone();
+        let app_id = params["id"].clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            let event = json!({"handle": "1001", "id": app_id, "status": "Progress", "details": "50"});
+            events.send("LISA", "operationStatus", event);
+            let event = json!({"handle": "1001", "id": app_id, "status": status, "details": "done"});
+            events.send("LISA", "operationStatus", event);
+        });
+        Reply::Result(json!("1001"))
+    });
+}
+
+#[cfg(feature = "2_1")]
+fn script_installed_package(mock: &MockThunder) {
+    mock.on_result(
+        "LISA.getList",
+        json!({"apps": [{
+            "id": "com.acme.game",
+            "type": "application/vnd.rdk-app.dac.native",
+            "installed": [{"version": "1.0", "url": "http://acme/game.tar.gz", "appName": "Game"}]
+        }]}),
+    );
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn applications_install_waits_for_lisa_status() {
+    let mock = MockThunder::start();
+    script_lisa_operation(&mock, "LISA.install", "Success");
+
+    let request = json!({"appId": "com.acme.game", "url": "http://acme/game.tar.gz"});
+    let response = call(&mock, RequestTypes::ApplicationInstallRequest, request).unwrap();
+    assert_eq!(response, json!({}));
+    let installs = mock.calls("LISA.install");
+    assert_eq!(installs.len(), 1);
+    assert_eq!(installs[0]["id"], "com.acme.game");
+    assert_eq!(installs[0]["url"], "http://acme/game.tar.gz");
+    assert_eq!(installs[0]["type"], "application/vnd.rdk-app.dac.native");
+
+    assert_err400(call(&mock, RequestTypes::ApplicationInstallRequest, json!({"appId": "com.acme.game", "url": ""})));
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn applications_install_verifies_checksum_and_reports_progress() {
+    use crate::dab::structs::NotificationLevel;
+    use sha2::{Digest, Sha256};
+
+    let mock = MockThunder::start();
+    script_lisa_operation(&mock, "LISA.install", "Success");
+    mock.serve_file("/game.tar.gz", b"game package");
+    let messages = Arc::new(Mutex::new(vec![]));
+    let published = messages.clone();
+    mock.platform().set_notifier(Arc::new(move |_: NotificationLevel, message: String| {
+        published.lock().unwrap().push(message);
+    }));
+
+    let checksum = format!("sha256:{:x}", Sha256::digest(b"game package"));
+    let request = json!({
+        "appId": "com.acme.game", "url": mock.file_url("/game.tar.gz"),
+        "version": "2.1.0", "appName": "Game", "checksum": checksum
+    });
+    let response = call(&mock, RequestTypes::ApplicationInstallRequest, request).unwrap();
+    assert_eq!(response, json!({}));
+    let install = &mock.calls("LISA.install")[0];
+    // The local Thunder installs the verified copy.
+    let package = install["url"].as_str().unwrap().strip_prefix("file://").unwrap();
+    assert!(!std::path::Path::new(package).exists(), "{} was left behind", package);
+    assert_eq!(install["version"], "2.1.0");
+    assert_eq!(install["appName"], "Game");
+    let messages = messages.lock().unwrap().clone();
+    for message in ["com.acme.game: downloaded 100%", "com.acme.game: checksum verified", "com.acme.game: installing 50"] {
+        assert!(messages.contains(&message.to_string()), "missing '{}' in {:?}", message, messages);
+    }
+
+    let request = json!({
+        "appId": "com.acme.game", "url": mock.file_url("/game.tar.gz"),
+        "checksum": format!("{:x}", Sha256::digest(b"another package"))
+    });
+    assert_err400(call(&mock, RequestTypes::ApplicationInstallRequest, request));
+    assert_eq!(mock.calls("LISA.install").len(), 1);
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn applications_list_includes_installed_packages() {
+    let mock = MockThunder::start();
+    script_applications(&mock);
+    script_installed_package(&mock);
+
+    let response = call(&mock, RequestTypes::ApplicationListRequest, json!({})).unwrap();
+    let applications = response["applications"].as_array().unwrap();
+    assert!(applications.contains(&json!({"appId": "YouTube"})));
+    assert!(applications.contains(&json!({"appId": "com.acme.game", "friendlyName": "Game", "version": "1.0"})));
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn applications_install_reports_failed_status() {
+    let mock = MockThunder::start();
+    script_lisa_operation(&mock, "LISA.install", "Failed");
+
+    let request = json!({"appId": "com.acme.game", "url": "http://acme/game.tar.gz"});
+    assert_err500(call(&mock, RequestTypes::ApplicationInstallRequest, request), "failed");
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn applications_uninstall_removes_installed_versions() {
+    let mock = MockThunder::start();
+    script_installed_package(&mock);
+    script_lisa_operation(&mock, "LISA.uninstall", "Success");
+
+    let response = call(&mock, RequestTypes::ApplicationUninstallRequest, json!({"appId": "com.acme.game"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(
+        mock.calls("LISA.uninstall"),
+        vec![json!({
+            "type": "application/vnd.rdk-app.dac.native",
+            "id": "com.acme.game",
+            "version": "1.0",
+            "uninstallType": "full"
+        })]
+    );
+
+    mock.on_result("LISA.getList", json!({"apps": []}));
+    assert_err400(call(&mock, RequestTypes::ApplicationUninstallRequest, json!({"appId": "com.acme.game"})));
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn applications_clear_data_resets_installed_package() {
+    let mock = MockThunder::start();
+    script_installed_package(&mock);
+    mock.on_result("LISA.reset", Value::Null);
+
+    let response = call(&mock, RequestTypes::ApplicationClearDataRequest, json!({"appId": "com.acme.game"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(mock.calls("LISA.reset")[0]["id"], "com.acme.game");
+    assert_eq!(mock.calls("LISA.reset")[0]["version"], "1.0");
+
+    assert_err400(call(&mock, RequestTypes::ApplicationClearDataRequest, json!({"appId": ""})));
+}
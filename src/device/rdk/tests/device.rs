@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn device_info_correlates_thunder_responses() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.System.getDeviceInfo",
+        json!({"estb_mac": "AA:BB:CC:DD:EE:FF", "success": true}),
+    );
+    mock.on_result("DeviceInfo.make", json!({"make": "Acme"}));
+    mock.on_result("DeviceInfo.modelid", json!({"sku": "ACME-1"}));
+    mock.on_result("DeviceInfo.serialnumber", json!({"serialnumber": "SN1234"}));
+    mock.on_result("DeviceInfo.socname", json!({"socname": "BCM72180"}));
+    mock.on_result("DeviceInfo.firmwareversion", json!({"imagename": "ACME_VBN_2024"}));
+    mock.on_result("DeviceInfo.systeminfo", json!({"uptime": 3600, "cpuload": "5"}));
+    mock.on_result("DeviceInfo.devicetype", json!({"devicetype": "IpTv"}));
+    mock.on_result(
+        "org.rdk.DisplaySettings.getConnectedVideoDisplays",
+        json!({"connectedVideoDisplays": ["HDMI0"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.RDKShell.getScreenResolution",
+        json!({"w": 1920, "h": 1080, "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.Network.getInterfaces",
+        json!({"interfaces": [
+            {"interface": "ETHERNET", "macAddress": "aa:bb:cc:dd:ee:ff", "enabled": true, "connected": true},
+            {"interface": "WIFI", "macAddress": "aa:bb:cc:dd:ee:00", "enabled": true, "connected": false}
+        ], "success": true}),
+    );
+    mock.handle("org.rdk.Network.getIPSettings", |params| {
+        if params["interface"] == "ETHERNET" {
+            Reply::Result(json!({
+                "interface": "ETHERNET", "ipversion": "IPv4", "autoconfig": true,
+                "ipaddr": "192.168.0.10", "netmask": "255.255.255.0", "gateway": "192.168.0.1",
+                "primarydns": "192.168.0.1", "secondarydns": "", "success": true
+            }))
+        } else {
+            Reply::Result(json!({"interface": "WIFI", "success": true}))
+        }
+    });
+
+    let response = call(&mock, RequestTypes::DeviceInfoRequest, json!({})).unwrap();
+    assert_eq!(response["manufacturer"], "Acme");
+    assert_eq!(response["model"], "ACME-1");
+    assert_eq!(response["serialNumber"], "SN1234");
+    assert_eq!(response["chipset"], "BCM72180");
+    assert_eq!(response["firmwareVersion"], "ACME_VBN_2024");
+    assert_eq!(response["deviceId"], "AABBCCDDEEFF");
+    assert_eq!(response["displayType"], "External");
+    assert_eq!(response["screenWidthPixels"], 1920);
+    assert_eq!(response["screenHeightPixels"], 1080);
+    assert_eq!(
+        response["networkInterfaces"],
+        json!([
+            {"connected": true, "macAddress": "aa:bb:cc:dd:ee:ff", "ipAddress": "192.168.0.10", "dns": ["192.168.0.1"], "type": "Ethernet"},
+            {"connected": false, "macAddress": "aa:bb:cc:dd:ee:00", "ipAddress": "", "dns": [], "type": "Wifi"}
+        ])
+    );
+    assert!(response["uptimeSince"].as_u64().unwrap() > 0);
+    #[cfg(feature = "2_1")]
+    {
+        assert_eq!(response["deviceType"], "IpTv");
+        assert!(response.get("modelName").is_none());
+    }
+}
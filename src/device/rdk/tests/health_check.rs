@@ -0,0 +1,9 @@
+use super::*;
+
+#[test]
+fn health_check_reports_healthy() {
+    let mock = MockThunder::start();
+
+    let response = call(&mock, RequestTypes::HealthCheckGetRequest, json!({})).unwrap();
+    assert_eq!(response, json!({"healthy": true}));
+}
@@ -0,0 +1,137 @@
+use super::*;
+use crate::device::rdk::interface::SleepPolicy;
+use std::time::{Duration, Instant};
+
+#[test]
+fn input_key_list_includes_dab_keys() {
+    let mock = MockThunder::start();
+
+    let response = call(&mock, RequestTypes::InputKeyListRequest, json!({})).unwrap();
+    let key_codes = response["keyCodes"].as_array().unwrap();
+    assert!(key_codes.contains(&json!("KEY_HOME")));
+    assert!(key_codes.contains(&json!("KEY_ENTER")));
+}
+
+#[test]
+fn input_key_press_injects_rdk_key_code() {
+    let mock = MockThunder::start();
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    let response = call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(mock.calls("org.rdk.RDKShell.injectKey"), vec![json!({"keyCode": 37})]);
+}
+
+#[test]
+fn input_key_press_wakes_sleeping_device() {
+    let mock = MockThunder::start();
+    script_power_state(&mock, "STANDBY");
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    let response = call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(mock.calls("org.rdk.System.setPowerState")[0]["powerState"], "ON");
+    assert_eq!(mock.calls("org.rdk.RDKShell.injectKey"), vec![json!({"keyCode": 37})]);
+
+    // Already awake.
+    call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.System.setPowerState").len(), 1);
+}
+
+// The device reports itself on some time after setPowerState, or never.
+fn script_slow_wake(mock: &MockThunder, wake_after: Option<Duration>) {
+    let woken_at = Arc::new(Mutex::new(None::<Instant>));
+    let set_at = woken_at.clone();
+    mock.handle("org.rdk.System.getPowerState", move |_| {
+        let on = matches!((*woken_at.lock().unwrap(), wake_after), (Some(at), Some(after)) if at.elapsed() >= after);
+        Reply::Result(json!({"powerState": if on { "ON" } else { "STANDBY" }, "success": true}))
+    });
+    mock.handle("org.rdk.System.setPowerState", move |_| {
+        *set_at.lock().unwrap() = Some(Instant::now());
+        Reply::Result(json!({"success": true}))
+    });
+}
+
+#[test]
+fn input_key_press_waits_for_the_device_to_wake_up() {
+    let mock = MockThunder::start();
+    script_slow_wake(&mock, Some(Duration::from_millis(300)));
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    let started = Instant::now();
+    call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    assert!(mock.calls("org.rdk.System.getPowerState").len() > 2);
+    assert_eq!(mock.calls("org.rdk.RDKShell.injectKey"), vec![json!({"keyCode": 37})]);
+}
+
+#[test]
+fn input_key_press_fails_when_the_device_does_not_wake_up() {
+    let mock = MockThunder::start();
+    script_slow_wake(&mock, None);
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    assert_err500(call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})), "didn't wake up");
+    assert!(mock.calls("org.rdk.RDKShell.injectKey").is_empty());
+}
+
+#[test]
+fn input_key_press_goes_ahead_when_the_power_state_is_unknown() {
+    let mock = MockThunder::start();
+    mock.on_error("org.rdk.System.getPowerState", -32601, "Unknown method.");
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.RDKShell.injectKey"), vec![json!({"keyCode": 37})]);
+    assert!(mock.calls("org.rdk.System.setPowerState").is_empty());
+}
+
+#[test]
+fn input_key_press_rejected_while_asleep() {
+    let mut mock = MockThunder::start();
+    mock.use_sleep_policy(SleepPolicy::Reject);
+    script_power_state(&mock, "DEEP_SLEEP");
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    assert_err400(call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})));
+    assert_err400(call(&mock, RequestTypes::ApplicationLaunchRequest, json!({"appId": "YouTube"})));
+    assert!(mock.calls("org.rdk.RDKShell.injectKey").is_empty());
+    assert!(mock.calls("org.rdk.RDKShell.launch").is_empty());
+
+    // The power key is how the device is woken up.
+    call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_POWER"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.RDKShell.injectKey"), vec![json!({"keyCode": 116})]);
+    assert!(mock.calls("org.rdk.System.setPowerState").is_empty());
+}
+
+#[test]
+fn input_key_press_rejects_unknown_key() {
+    let mock = MockThunder::start();
+
+    assert_err400(call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_TELEPORT"})));
+    assert_err400(call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": ""})));
+    assert!(mock.calls("org.rdk.RDKShell.injectKey").is_empty());
+}
+
+#[test]
+fn input_long_key_press_repeats_key() {
+    let mock = MockThunder::start();
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    let response = call(
+        &mock,
+        RequestTypes::InputLongKeyPressRequest,
+        json!({"keyCode": "KEY_RIGHT", "durationMs": 300}),
+    )
+    .unwrap();
+    assert_eq!(response, json!({}));
+    let calls = mock.calls("org.rdk.RDKShell.injectKey");
+    assert!(calls.len() >= 2, "key injected {} times", calls.len());
+    assert!(calls.iter().all(|call| *call == json!({"keyCode": 39})));
+
+    assert_err400(call(
+        &mock,
+        RequestTypes::InputLongKeyPressRequest,
+        json!({"keyCode": "KEY_RIGHT", "durationMs": 0}),
+    ));
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn malformed_request_is_rejected() {
+    let mock = MockThunder::start();
+
+    let result = call_function(mock.platform(), "{".to_string(), RequestTypes::ApplicationListRequest);
+    assert!(matches!(result, Err(DabError::Err400(_))));
+    assert_err400(call(&mock, RequestTypes::ApplicationGetStateRequest, json!({})));
+    assert_err400(call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": 36})));
+}
+
+#[test]
+fn operations_list_includes_supported_operations() {
+    let mock = MockThunder::start();
+
+    let response = call(&mock, RequestTypes::OperationsListRequest, json!({})).unwrap();
+    let operations = response["operations"].as_array().unwrap();
+    for operation in ["applications/launch", "system/settings/get", "output/image", "device-telemetry/start", "app-telemetry/start", "version"] {
+        assert!(operations.contains(&json!(operation)), "missing {}", operation);
+    }
+}
+
+#[test]
+fn operations_list_reports_2_1_operations_with_the_feature() {
+    let mock = MockThunder::start();
+
+    let response = call(&mock, RequestTypes::OperationsListRequest, json!({})).unwrap();
+    let operations = response["operations"].as_array().unwrap();
+    for operation in [
+        "applications/install",
+        "applications/uninstall",
+        "applications/clear-data",
+        "system/power-mode/get",
+        "system/power-mode/set",
+    ] {
+        assert_eq!(operations.contains(&json!(operation)), cfg!(feature = "2_1"), "{}", operation);
+    }
+}
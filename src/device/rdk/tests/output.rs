@@ -0,0 +1,243 @@
+use super::*;
+use base64::{engine::general_purpose, Engine as _};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+// Uploads a file the way org.rdk.ScreenCapture does.
+fn upload(url: &str, content: &[u8]) {
+    let url = Url::parse(url).unwrap();
+    let mut stream = TcpStream::connect((url.host_str().unwrap(), url.port().unwrap())).unwrap();
+    let header = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        url.path(),
+        url.host_str().unwrap(),
+        content.len()
+    );
+    stream.write_all(header.as_bytes()).unwrap();
+    stream.write_all(content).unwrap();
+    // Wait for the response, ScreenCapture doesn't drop the connection early either.
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+}
+
+fn screen_png(width: u32, height: u32) -> Vec<u8> {
+    let screen = image::RgbImage::from_fn(width, height, |x, y| image::Rgb([x as u8, y as u8, 128]));
+    let mut png = vec![];
+    screen.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png).unwrap();
+    png
+}
+
+fn script_screen_capture(mock: &MockThunder, png: Vec<u8>) {
+    mock.on_result(
+        "Controller.1.status@org.rdk.ScreenCapture",
+        json!([{"callsign": "org.rdk.ScreenCapture", "state": "activated"}]),
+    );
+    let png = Arc::new(png);
+    mock.handle("org.rdk.ScreenCapture.uploadScreenCapture", move |params| {
+        let url = params["url"].as_str().unwrap().to_string();
+        let png = png.clone();
+        thread::spawn(move || upload(&url, &png));
+        Reply::Result(json!({"success": true}))
+    });
+}
+
+fn decode_output_image(response: &Value) -> image::DynamicImage {
+    let (_, data) = response["outputImage"].as_str().unwrap().split_once(";base64,").unwrap();
+    image::load_from_memory(&general_purpose::STANDARD.decode(data).unwrap()).unwrap()
+}
+
+#[test]
+fn output_image_returns_uploaded_png() {
+    let png = screen_png(64, 32);
+    let mock = MockThunder::start();
+    script_screen_capture(&mock, png.clone());
+
+    let response = call(&mock, RequestTypes::OutputImageRequest, json!({})).unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "outputImage": format!("data:image/png;base64,{}", general_purpose::STANDARD.encode(&png)),
+            "width": 64,
+            "height": 32,
+            "mimeType": "image/png"
+        })
+    );
+}
+
+#[test]
+fn output_image_crops_scales_and_converts() {
+    let mock = MockThunder::start();
+    script_screen_capture(&mock, screen_png(64, 32));
+
+    let request = json!({"format": "jpeg", "quality": 50, "crop": {"x": 16, "y": 0, "width": 32, "height": 32}, "maxWidth": 16});
+    let response = call(&mock, RequestTypes::OutputImageRequest, request).unwrap();
+    assert_eq!(response["mimeType"], "image/jpeg");
+    assert!(response["outputImage"].as_str().unwrap().starts_with("data:image/jpeg;base64,"));
+    assert_eq!((response["width"].clone(), response["height"].clone()), (json!(16), json!(16)));
+    assert_eq!(image::GenericImageView::dimensions(&decode_output_image(&response)), (16, 16));
+
+    // Downscaled to fit both bounds, keeping the aspect ratio.
+    let request = json!({"format": "webp", "maxWidth": 48, "maxHeight": 8});
+    let response = call(&mock, RequestTypes::OutputImageRequest, request).unwrap();
+    assert_eq!(response["mimeType"], "image/webp");
+    assert_eq!((response["width"].clone(), response["height"].clone()), (json!(16), json!(8)));
+
+    // Never upscaled.
+    let response = call(&mock, RequestTypes::OutputImageRequest, json!({"maxWidth": 1000})).unwrap();
+    assert_eq!((response["width"].clone(), response["height"].clone()), (json!(64), json!(32)));
+}
+
+#[test]
+fn output_image_rejects_invalid_options() {
+    let mock = MockThunder::start();
+    script_screen_capture(&mock, screen_png(64, 32));
+
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, json!({"format": "gif"})));
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, json!({"quality": 50})));
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, json!({"format": "jpeg", "quality": 0})));
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, json!({"maxHeight": 0})));
+    let crop = json!({"crop": {"x": 48, "y": 0, "width": 32, "height": 32}});
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, crop));
+    assert_eq!(mock.calls("org.rdk.ScreenCapture.uploadScreenCapture").len(), 1);
+}
+
+#[test]
+fn output_image_rejects_invalid_options_without_capture() {
+    let mock = MockThunder::start();
+    script_screen_capture(&mock, screen_png(64, 32));
+
+    for request in [
+        json!({"quality": 50}),
+        json!({"format": "jpeg", "quality": 101}),
+        json!({"maxWidth": 0}),
+        json!({"crop": {"x": 0, "y": 0, "width": 0, "height": 32}}),
+        json!({"uploadMethod": "PUT"}),
+        json!({"destination": "/tmp/dab-screenshot.png"}),
+    ] {
+        assert_err400(call(&mock, RequestTypes::OutputImageRequest, request));
+    }
+    assert!(mock.calls("org.rdk.ScreenCapture.uploadScreenCapture").is_empty());
+}
+
+// Stands in for an upload destination: answers every request with the status, and
+// the location when given, and reports its method, path and body.
+fn http_stand_in(status: &'static str, location: Option<&'static str>) -> (String, std::sync::mpsc::Receiver<(String, String, Vec<u8>)>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (requests, received) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut head = vec![];
+            let mut byte = [0u8];
+            while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                head.push(byte[0]);
+            }
+            let head = String::from_utf8(head).unwrap();
+            let length = head
+                .lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse().unwrap()))
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).unwrap();
+
+            let location = location.map(|location| format!("Location: {}\r\n", location)).unwrap_or_default();
+            let response = format!("HTTP/1.1 {}\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n", status, location);
+            stream.write_all(response.as_bytes()).unwrap();
+            let mut request_line = head.lines().next().unwrap().split(' ');
+            let method = request_line.next().unwrap().to_string();
+            let path = request_line.next().unwrap().to_string();
+            let _ = requests.send((method, path, body));
+        }
+    });
+    (url, received)
+}
+
+#[test]
+fn output_image_uploads_to_destination() {
+    use sha2::{Digest, Sha256};
+
+    let png = screen_png(64, 32);
+    let mock = MockThunder::start();
+    script_screen_capture(&mock, png.clone());
+    let (url, uploads) = http_stand_in("200 OK", None);
+
+    let destination = format!("{}/shots/1.png", url);
+    let response = call(&mock, RequestTypes::OutputImageRequest, json!({"destination": destination})).unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "width": 64,
+            "height": 32,
+            "mimeType": "image/png",
+            "location": destination,
+            "size": png.len(),
+            "sha256": format!("{:x}", Sha256::digest(&png))
+        })
+    );
+    assert_eq!(uploads.recv_timeout(Duration::from_secs(5)).unwrap(), ("PUT".to_string(), "/shots/1.png".to_string(), png));
+
+    // A POST may store the image elsewhere.
+    let (url, uploads) = http_stand_in("201 Created", Some("http://storage/shots/42.jpg"));
+    let request = json!({"destination": format!("{}/shots", url), "uploadMethod": "POST", "format": "jpeg"});
+    let response = call(&mock, RequestTypes::OutputImageRequest, request).unwrap();
+    assert_eq!(response["location"], "http://storage/shots/42.jpg");
+    assert_eq!(response["mimeType"], "image/jpeg");
+    let (method, _, body) = uploads.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(method, "POST");
+    assert_eq!(response["size"], body.len());
+    assert_eq!(response["sha256"], format!("{:x}", Sha256::digest(&body)));
+}
+
+#[test]
+fn output_image_writes_file_destination() {
+    let png = screen_png(64, 32);
+    let mut mock = MockThunder::start();
+    script_screen_capture(&mock, png.clone());
+    let outside = std::env::temp_dir().canonicalize().unwrap();
+    let upload_dir = outside.join(format!("dab-screenshots-{}", std::process::id()));
+    std::fs::create_dir_all(&upload_dir).unwrap();
+    let path = upload_dir.join("1.png");
+
+    // Refused until the operator gives a directory.
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, json!({"destination": path.to_str().unwrap()})));
+    assert!(!path.exists());
+
+    mock.use_upload_dir(upload_dir.clone());
+    let request = json!({"destination": format!("file://{}", path.display())});
+    let response = call(&mock, RequestTypes::OutputImageRequest, request).unwrap();
+    assert_eq!(response["location"], path.to_str().unwrap());
+    assert_eq!(std::fs::read(&path).unwrap(), png);
+    // Nothing is left of the file written before the rename.
+    assert_eq!(std::fs::read_dir(&upload_dir).unwrap().count(), 1);
+
+    std::os::unix::fs::symlink(&outside, upload_dir.join("outside")).unwrap();
+    std::os::unix::fs::symlink(outside.join("dab-screenshot.png"), upload_dir.join("2.png")).unwrap();
+    for destination in [
+        outside.join("dab-screenshot.png"),
+        upload_dir.join("../dab-screenshot.png"),
+        upload_dir.join("outside/dab-screenshot.png"),
+        upload_dir.join("2.png"),
+        PathBuf::from("shots/1.png"),
+    ] {
+        let request = json!({"destination": destination.to_str().unwrap()});
+        assert_err400(call(&mock, RequestTypes::OutputImageRequest, request));
+    }
+    assert!(!outside.join("dab-screenshot.png").exists());
+    std::fs::remove_dir_all(&upload_dir).unwrap();
+
+    assert_err400(call(&mock, RequestTypes::OutputImageRequest, json!({"uploadMethod": "PUT"})));
+}
+
+#[test]
+fn output_image_reports_failed_upload() {
+    let mock = MockThunder::start();
+    script_screen_capture(&mock, screen_png(64, 32));
+    let (url, _uploads) = http_stand_in("507 Insufficient Storage", None);
+
+    let request = json!({"destination": format!("{}/shots/1.png", url)});
+    assert_err500(call(&mock, RequestTypes::OutputImageRequest, request), "Error uploading");
+}
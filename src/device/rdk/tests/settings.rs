@@ -0,0 +1,775 @@
+use super::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn script_audio_port(mock: &MockThunder) {
+    mock.on_result(
+        "org.rdk.DisplaySettings.getConnectedAudioPorts",
+        json!({"connectedAudioPorts": ["HDMI0"], "success": true}),
+    );
+}
+
+fn script_hdr_support(mock: &MockThunder, settop: bool, tv: bool) {
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSettopHDRSupport",
+        json!({"standards": ["HDR10"], "supportsHDR": settop, "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getTvHDRSupport",
+        json!({"standards": ["HDR10"], "supportsHDR": tv, "success": true}),
+    );
+}
+
+#[test]
+fn system_settings_list_reports_capabilities() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedResolutions",
+        json!({"supportedResolutions": ["720p", "1080p60", "1080i50", "2160p30", "VGA"], "success": true}),
+    );
+    mock.on_result(
+        "Controller.1.status@org.rdk.HdmiCecSource",
+        json!([{"autostart": false, "callsign": "org.rdk.HdmiCecSource", "state": "activated"}]),
+    );
+    mock.on_result(
+        "org.rdk.TextToSpeech.isttsenabled",
+        json!({"isenabled": true, "TTS_Status": 0, "success": true}),
+    );
+    script_hdr_support(&mock, true, true);
+    script_audio_port(&mock);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioModes",
+        json!({"supportedAudioModes": ["STEREO", "SURROUND", "DOLBYDIGITAL", "PASSTHRU", "AUTO (Stereo)"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioPorts",
+        json!({"supportedAudioPorts": ["HDMI0", "SPDIF0", "SPEAKER0"], "success": true}),
+    );
+
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(
+        response["outputResolution"],
+        json!([
+            {"width": 1280, "height": 720, "frequency": 60.0},
+            {"width": 1920, "height": 1080, "frequency": 60.0},
+            {"width": 1920, "height": 1080, "frequency": 50.0},
+            {"width": 3840, "height": 2160, "frequency": 30.0}
+        ])
+    );
+    assert_eq!(response["cec"], true);
+    assert_eq!(response["textToSpeech"], true);
+    assert_eq!(response["hdrOutputMode"], json!(["AlwaysHdr"]));
+    assert_eq!(
+        response["audioOutputMode"],
+        json!(["Stereo", "MultichannelPcm", "PassThrough", "Auto"])
+    );
+    assert_eq!(
+        response["audioOutputSource"],
+        json!(["HDMI", "Optical", "NativeSpeaker"])
+    );
+}
+
+#[test]
+fn system_settings_list_reports_missing_cec_plugin() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedResolutions",
+        json!({"supportedResolutions": ["1080p"], "success": true}),
+    );
+    mock.on_error("Controller.1.status@org.rdk.HdmiCecSource", 22, "ERROR_UNKNOWN_KEY");
+    mock.on_result(
+        "org.rdk.TextToSpeech.isttsenabled",
+        json!({"isenabled": false, "TTS_Status": 0, "success": true}),
+    );
+    script_hdr_support(&mock, true, false);
+    script_audio_port(&mock);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioModes",
+        json!({"supportedAudioModes": ["STEREO"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioPorts",
+        json!({"supportedAudioPorts": ["HDMI0"], "success": true}),
+    );
+
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["cec"], false);
+    assert_eq!(response["hdrOutputMode"], json!(["DisableHdr"]));
+}
+
+#[test]
+fn system_settings_list_rejects_malformed_resolutions() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedResolutions",
+        json!({"supportedResolutions": "1080p60", "success": true}),
+    );
+
+    let result = call(&mock, RequestTypes::SystemSettingsListRequest, json!({}));
+    assert_err500(result, "invalid type");
+}
+
+fn script_current_settings(mock: &MockThunder) {
+    mock.on_result("org.rdk.UserSettings.getPresentationLanguage", json!("fr-FR"));
+    mock.on_result(
+        "org.rdk.DisplaySettings.getConnectedVideoDisplays",
+        json!({"connectedVideoDisplays": ["HDMI0"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getCurrentResolution",
+        json!({"resolution": "1080p60", "w": 1920, "h": 1080, "progressive": true, "success": true}),
+    );
+    mock.on_result("DisplayInfo.framerate", json!("Framerate5994"));
+    script_audio_port(mock);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getVolumeLevel",
+        json!({"volumeLevel": "35.0", "success": true}),
+    );
+    mock.on_result("org.rdk.DisplaySettings.getMuted", json!({"muted": true, "success": true}));
+    mock.on_result(
+        "Controller.1.status@org.rdk.HdmiCecSource",
+        json!([{"autostart": false, "callsign": "org.rdk.HdmiCecSource", "state": "activated"}]),
+    );
+    mock.on_result("org.rdk.HdmiCecSource.getEnabled", json!({"enabled": true, "success": true}));
+    script_hdr_support(mock, true, false);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSoundMode",
+        json!({"soundMode": "SURROUND", "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.TextToSpeech.isttsenabled",
+        json!({"isenabled": false, "TTS_Status": 0, "success": true}),
+    );
+}
+
+#[test]
+fn system_settings_get_reports_current_settings() {
+    let mock = MockThunder::start();
+    script_current_settings(&mock);
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["language"], "fr-FR");
+    assert_eq!(response["outputResolution"]["width"], 1920);
+    assert_eq!(response["outputResolution"]["height"], 1080);
+    assert!((response["outputResolution"]["frequency"].as_f64().unwrap() - 59.94).abs() < 0.001);
+    assert_eq!(response["audioVolume"], 35);
+    assert_eq!(response["mute"], true);
+    assert_eq!(response["cec"], true);
+    assert_eq!(response["hdrOutputMode"], "DisableHdr");
+    assert_eq!(response["audioOutputMode"], "MultichannelPcm");
+    assert_eq!(response["audioOutputSource"], "HDMI");
+    assert_eq!(response["textToSpeech"], false);
+    assert!(response.get("errors").is_none());
+}
+
+#[test]
+fn system_settings_get_reports_unreadable_settings() {
+    let mock = MockThunder::start();
+    script_current_settings(&mock);
+    mock.on_error("org.rdk.DisplaySettings.getVolumeLevel", 1, "ERROR_GENERAL");
+    mock.on_error("org.rdk.HdmiCecSource.getEnabled", 1, "ERROR_UNAVAILABLE");
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["language"], "fr-FR");
+    assert_eq!(response["mute"], true);
+    assert_eq!(response["audioOutputMode"], "MultichannelPcm");
+    assert!(response["errors"]["audioVolume"].as_str().unwrap().contains("ERROR_GENERAL"));
+    assert!(response["errors"]["cec"].as_str().unwrap().contains("ERROR_UNAVAILABLE"));
+    assert_eq!(response["errors"].as_object().unwrap().len(), 2);
+}
+
+#[test]
+fn system_settings_get_reports_unknown_sound_mode() {
+    let mock = MockThunder::start();
+    script_current_settings(&mock);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSoundMode",
+        json!({"soundMode": "DTS_X", "success": true}),
+    );
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert!(response["errors"]["audioOutputMode"].as_str().unwrap().contains("DTS_X"));
+    assert_eq!(response["audioVolume"], 35);
+}
+
+#[test]
+fn system_settings_get_fails_when_no_setting_can_be_read() {
+    let mock = MockThunder::start();
+
+    let result = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({}));
+    assert_err500(result, "Failed to read the system settings");
+}
+
+// The capabilities checked by system/settings/set, and the settings it reads back.
+fn script_settings_capabilities(mock: &MockThunder) {
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedResolutions",
+        json!({"supportedResolutions": ["720p", "1080p60"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioModes",
+        json!({"supportedAudioModes": ["STEREO", "SURROUND"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioPorts",
+        json!({"supportedAudioPorts": ["HDMI0", "SPDIF0"], "success": true}),
+    );
+    script_current_settings(mock);
+}
+
+#[test]
+fn system_settings_set_applies_settings() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    let volume = Arc::new(Mutex::new(35));
+    let current = volume.clone();
+    mock.handle("org.rdk.DisplaySettings.getVolumeLevel", move |_| {
+        Reply::Result(json!({"volumeLevel": format!("{}.0", current.lock().unwrap()), "success": true}))
+    });
+    let current = volume.clone();
+    mock.handle("org.rdk.DisplaySettings.setVolumeLevel", move |params| {
+        *current.lock().unwrap() = params["volumeLevel"].as_u64().unwrap();
+        Reply::Result(json!({"success": true}))
+    });
+    mock.on_result("org.rdk.UserSettings.setPresentationLanguage", Value::Null);
+    mock.on_result("org.rdk.DisplaySettings.setMuted", json!({"success": true}));
+
+    let response = call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"language": "en-US", "audioVolume": 40, "mute": false}),
+    )
+    .unwrap();
+    assert_eq!(
+        mock.calls("org.rdk.UserSettings.setPresentationLanguage"),
+        vec![json!({"presentationLanguage": "en-US"})]
+    );
+    assert_eq!(
+        mock.calls("org.rdk.DisplaySettings.setVolumeLevel"),
+        vec![json!({"volumeLevel": 40, "audioPort": "HDMI0"})]
+    );
+    assert_eq!(
+        mock.calls("org.rdk.DisplaySettings.setMuted"),
+        vec![json!({"muted": false, "audioPort": "HDMI0"})]
+    );
+    // The settings as read back from the device.
+    assert_eq!(response["audioVolume"], 40);
+    assert_eq!(response["language"], "fr-FR");
+    assert_eq!(response["outputResolution"]["width"], 1920);
+}
+
+#[test]
+fn system_settings_set_applies_audio_source_before_mode() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    let applied = Arc::new(Mutex::new(vec![]));
+    for method in [
+        "org.rdk.DisplaySettings.setSoundMode",
+        "org.rdk.DisplaySettings.setVolumeLevel",
+        "org.rdk.DisplaySettings.setEnableAudioPort",
+    ] {
+        let applied = applied.clone();
+        mock.handle(method, move |_| {
+            applied.lock().unwrap().push(method);
+            Reply::Result(json!({"success": true}))
+        });
+    }
+
+    call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"audioVolume": 20, "audioOutputMode": "Stereo", "audioOutputSource": "HDMI"}),
+    )
+    .unwrap();
+    assert_eq!(
+        *applied.lock().unwrap(),
+        vec![
+            "org.rdk.DisplaySettings.setEnableAudioPort",
+            "org.rdk.DisplaySettings.setSoundMode",
+            "org.rdk.DisplaySettings.setVolumeLevel",
+        ]
+    );
+}
+
+#[test]
+fn system_settings_set_reports_failed_call() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    mock.on_result("org.rdk.DisplaySettings.setMuted", json!({"success": false}));
+
+    let result = call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"mute": true}));
+    assert_err500(result, "org.rdk.DisplaySettings.setMuted failed");
+}
+
+#[test]
+fn system_settings_set_restores_applied_settings_on_failure() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    mock.on_result("org.rdk.DisplaySettings.setVolumeLevel", json!({"success": true}));
+    mock.on_result("org.rdk.DisplaySettings.setMuted", json!({"success": false}));
+
+    let result = call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"audioVolume": 40, "mute": false}),
+    );
+    assert_err500(result, "org.rdk.DisplaySettings.setMuted failed");
+    assert_eq!(
+        mock.calls("org.rdk.DisplaySettings.setVolumeLevel"),
+        vec![
+            json!({"volumeLevel": 40, "audioPort": "HDMI0"}),
+            json!({"volumeLevel": 35, "audioPort": "HDMI0"}),
+        ]
+    );
+}
+
+#[test]
+fn system_settings_set_leaves_unread_settings_on_failure() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_tv_display_settings(&mock);
+    mock.on_result("org.rdk.DisplaySettings.setMuted", json!({"success": false}));
+    // MEMC is readable to validate and set it, but not when the previous settings are read.
+    let reads = Arc::new(Mutex::new(0));
+    mock.handle("org.rdk.tv.ControlSettings.getMEMC", move |_| {
+        let mut reads = reads.lock().unwrap();
+        *reads += 1;
+        match *reads {
+            2 => Reply::Error(1, "ERROR_GENERAL".to_string()),
+            _ => Reply::Result(json!({"memc": "On", "success": true})),
+        }
+    });
+
+    let result = call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"memc": false, "mute": false}));
+    assert_err500(result, "org.rdk.DisplaySettings.setMuted failed");
+    assert_eq!(mock.calls("org.rdk.tv.ControlSettings.setMEMC"), vec![json!({"memc": false})]);
+}
+
+#[test]
+fn system_settings_set_rejects_invalid_values() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"audioVolume": 1000})));
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"pictureMode": "Movie"})));
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"hdrOutputMode": "Always"})));
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"hdrOutputMode": "AlwaysHdr"})));
+    assert_err400(call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"outputResolution": {"width": 3840, "height": 2160, "frequency": 60}}),
+    ));
+    // Nothing is applied when one of the values is rejected.
+    assert_err400(call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"language": "en-US", "audioVolume": 1000}),
+    ));
+    assert!(mock.calls("org.rdk.UserSettings.setPresentationLanguage").is_empty());
+    assert!(mock.calls("org.rdk.DisplaySettings.setVolumeLevel").is_empty());
+}
+
+fn script_tv_display_settings(mock: &MockThunder) {
+    mock.on_result(
+        "Controller.1.status@org.rdk.tv.ControlSettings",
+        json!([{"autostart": false, "callsign": "org.rdk.tv.ControlSettings", "state": "activated"}]),
+    );
+    mock.on_result(
+        "org.rdk.tv.ControlSettings.getSupportedPictureModes",
+        json!({"pictureModes": ["Standard", "Vivid", "EnergySaving", "Theater", "Game", "Sports"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.tv.ControlSettings.getPictureMode",
+        json!({"pictureMode": "Theater", "success": true}),
+    );
+    mock.on_result("org.rdk.tv.ControlSettings.setPictureMode", json!({"success": true}));
+    mock.on_result("org.rdk.tv.ControlSettings.getMEMC", json!({"memc": "On", "success": true}));
+    mock.on_result("org.rdk.tv.ControlSettings.setMEMC", json!({"success": true}));
+    mock.on_result(
+        "Controller.1.status@org.rdk.DisplaySettings",
+        json!([{"autostart": true, "callsign": "org.rdk.DisplaySettings", "state": "activated"}]),
+    );
+    mock.on_result("org.rdk.DisplaySettings.getAllmEnabled", json!({"allmEnabled": false, "success": true}));
+    mock.on_result("org.rdk.DisplaySettings.setAllmEnabled", json!({"success": true}));
+    mock.on_result(
+        "Controller.1.status@org.rdk.FrameRate",
+        json!([{"autostart": false, "callsign": "org.rdk.FrameRate", "state": "activated"}]),
+    );
+    mock.on_result("org.rdk.FrameRate.getFrmMode", json!({"auto-frm-mode": 1, "success": true}));
+    mock.on_result("org.rdk.FrameRate.setFrmMode", json!({"success": true}));
+}
+
+#[test]
+fn system_settings_report_tv_display_settings() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedResolutions",
+        json!({"supportedResolutions": ["1080p"], "success": true}),
+    );
+    mock.on_error("Controller.1.status@org.rdk.HdmiCecSource", 22, "ERROR_UNKNOWN_KEY");
+    mock.on_result(
+        "org.rdk.TextToSpeech.isttsenabled",
+        json!({"isenabled": false, "TTS_Status": 0, "success": true}),
+    );
+    script_hdr_support(&mock, true, false);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioModes",
+        json!({"supportedAudioModes": ["STEREO"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioPorts",
+        json!({"supportedAudioPorts": ["HDMI0"], "success": true}),
+    );
+    script_current_settings(&mock);
+    script_tv_display_settings(&mock);
+
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["pictureMode"], json!(["Standard", "Dynamic", "Movie", "Game", "Sports"]));
+    assert_eq!(response["memc"], true);
+    assert_eq!(response["lowLatencyMode"], true);
+    assert_eq!(response["matchContentFrameRate"], json!(["EnabledAlways", "Disabled"]));
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["pictureMode"], "Movie");
+    assert_eq!(response["memc"], true);
+    assert_eq!(response["lowLatencyMode"], false);
+    assert_eq!(response["matchContentFrameRate"], "EnabledAlways");
+}
+
+#[test]
+fn system_settings_get_reports_unreadable_tv_display_settings() {
+    let mock = MockThunder::start();
+    script_current_settings(&mock);
+    script_tv_display_settings(&mock);
+    mock.on_error("org.rdk.tv.ControlSettings.getMEMC", 1, "ERROR_GENERAL");
+    mock.on_result(
+        "org.rdk.tv.ControlSettings.getPictureMode",
+        json!({"pictureMode": "EnergySaving", "success": true}),
+    );
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert!(response["errors"]["memc"].as_str().unwrap().contains("ERROR_GENERAL"));
+    assert!(response["errors"]["pictureMode"].as_str().unwrap().contains("EnergySaving"));
+    assert_eq!(response["errors"].as_object().unwrap().len(), 2);
+    assert_eq!(response["lowLatencyMode"], false);
+    assert_eq!(response["matchContentFrameRate"], "EnabledAlways");
+}
+
+#[test]
+fn system_settings_set_applies_tv_display_settings() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_tv_display_settings(&mock);
+
+    let response = call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"pictureMode": "Movie", "memc": false, "lowLatencyMode": true, "matchContentFrameRate": "Disabled"}),
+    )
+    .unwrap();
+    assert_eq!(response["pictureMode"], "Movie");
+    assert_eq!(
+        mock.calls("org.rdk.tv.ControlSettings.setPictureMode"),
+        vec![json!({"pictureMode": "Theater"})]
+    );
+    assert_eq!(mock.calls("org.rdk.tv.ControlSettings.setMEMC"), vec![json!({"memc": false})]);
+    assert_eq!(
+        mock.calls("org.rdk.DisplaySettings.setAllmEnabled"),
+        vec![json!({"allmEnabled": true})]
+    );
+    assert_eq!(mock.calls("org.rdk.FrameRate.setFrmMode"), vec![json!({"frmmode": 0})]);
+
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"pictureMode": "FilmMaker"})));
+    assert_err400(call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"matchContentFrameRate": "EnabledSeamlessOnly"}),
+    ));
+}
+
+#[test]
+fn system_settings_report_missing_tv_display_plugins() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    mock.on_error("Controller.1.status@org.rdk.tv.ControlSettings", 22, "ERROR_UNKNOWN_KEY");
+    mock.on_error("Controller.1.status@org.rdk.FrameRate", 22, "ERROR_UNKNOWN_KEY");
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["pictureMode"], "Standard");
+    assert_eq!(response["memc"], false);
+    assert_eq!(response["lowLatencyMode"], false);
+    assert_eq!(response["matchContentFrameRate"], "Disabled");
+
+    for setting in [
+        json!({"memc": true}),
+        json!({"lowLatencyMode": true}),
+        json!({"matchContentFrameRate": "EnabledAlways"}),
+    ] {
+        assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, setting));
+    }
+}
+
+#[test]
+fn system_settings_activate_plugins_only_to_set() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_tv_display_settings(&mock);
+    let activated = Arc::new(Mutex::new(false));
+    let state = activated.clone();
+    mock.handle("Controller.1.status@org.rdk.tv.ControlSettings", move |_| {
+        let state = if *state.lock().unwrap() { "activated" } else { "deactivated" };
+        Reply::Result(json!([{"callsign": "org.rdk.tv.ControlSettings", "state": state}]))
+    });
+    let events = mock.events();
+    mock.handle("Controller.activate", move |_| {
+        *activated.lock().unwrap() = true;
+        events.send(
+            "Controller",
+            "statechange",
+            json!({"callsign": "org.rdk.tv.ControlSettings", "state": "Activated", "reason": "Requested"}),
+        );
+        Reply::Result(Value::Null)
+    });
+
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["memc"], false);
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["memc"], false);
+    assert!(mock.calls("Controller.activate").is_empty());
+    assert!(mock.calls("org.rdk.tv.ControlSettings.getMEMC").is_empty());
+
+    let response = call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"memc": false})).unwrap();
+    assert_eq!(response["memc"], true);
+    assert_eq!(mock.calls("Controller.activate"), vec![json!({"callsign": "org.rdk.tv.ControlSettings"})]);
+    assert_eq!(mock.calls("org.rdk.tv.ControlSettings.setMEMC"), vec![json!({"memc": false})]);
+}
+
+#[test]
+fn system_settings_low_latency_mode_needs_display_settings() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_tv_display_settings(&mock);
+    mock.on_error("Controller.1.status@org.rdk.DisplaySettings", 22, "ERROR_UNKNOWN_KEY");
+
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["lowLatencyMode"], false);
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"lowLatencyMode": true})));
+    assert!(mock.calls("org.rdk.DisplaySettings.getAllmEnabled").is_empty());
+    assert!(mock.calls("org.rdk.DisplaySettings.setAllmEnabled").is_empty());
+}
+
+fn script_video_inputs(mock: &MockThunder) {
+    mock.on_result(
+        "Controller.1.status@org.rdk.HdmiInput",
+        json!([{"autostart": false, "callsign": "org.rdk.HdmiInput", "state": "activated"}]),
+    );
+    mock.on_result(
+        "org.rdk.HdmiInput.getHDMIInputDevices",
+        json!({"devices": [
+            {"id": 0, "locator": "hdmiin://localhost/deviceid/0", "connected": "true"},
+            {"id": 1, "locator": "hdmiin://localhost/deviceid/1", "connected": "false"}
+        ], "success": true}),
+    );
+    mock.on_result("org.rdk.HdmiInput.startHdmiInput", json!({"success": true}));
+    mock.on_result("org.rdk.HdmiInput.stopHdmiInput", json!({"success": true}));
+    mock.on_result(
+        "Controller.1.status@org.rdk.AVInput",
+        json!([{"autostart": false, "callsign": "org.rdk.AVInput", "state": "activated"}]),
+    );
+    mock.on_result(
+        "org.rdk.AVInput.getInputDevices",
+        json!({"devices": [{"id": 0, "locator": "cvbsin://localhost/deviceid/0", "connected": true}], "success": true}),
+    );
+    mock.on_result("org.rdk.AVInput.startInput", json!({"success": true}));
+    mock.on_result("org.rdk.AVInput.stopInput", json!({"success": true}));
+}
+
+#[test]
+fn system_settings_switch_video_input_source() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_video_inputs(&mock);
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["videoInputSource"], "Home");
+
+    call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"videoInputSource": "HDMI2"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.HdmiInput.startHdmiInput"), vec![json!({"portId": 1})]);
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["videoInputSource"], "HDMI2");
+
+    call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"videoInputSource": "Composite"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.HdmiInput.stopHdmiInput").len(), 1);
+    assert_eq!(
+        mock.calls("org.rdk.AVInput.startInput"),
+        vec![json!({"portId": 0, "typeOfInput": "COMPOSITE"})]
+    );
+
+    call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"videoInputSource": "Home"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.AVInput.stopInput"), vec![json!({"typeOfInput": "COMPOSITE"})]);
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["videoInputSource"], "Home");
+
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"videoInputSource": "HDMI3"})));
+    // No RDK input plugin has a tuner.
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"videoInputSource": "Tuner"})));
+    assert_eq!(mock.calls("org.rdk.HdmiInput.startHdmiInput").len(), 1);
+}
+
+// Polls system/settings/get until it reports the source, as the input statuses
+// arrive in the background.
+fn wait_for_video_input_source(mock: &MockThunder, source: &str) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let response = call(mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+        if response["videoInputSource"] == source {
+            return;
+        }
+        assert!(Instant::now() < deadline, "expected {}, got {}", source, response["videoInputSource"]);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn system_settings_follow_video_input_switched_on_the_device() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_video_inputs(&mock);
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["videoInputSource"], "Home");
+
+    let events = mock.events();
+    let status = |locator: &str, status: &str| json!({"id": 0, "locator": locator, "status": status});
+    events.send("org.rdk.HdmiInput", "onInputStatusChanged", status("hdmiin://localhost/deviceid/0", "started"));
+    wait_for_video_input_source(&mock, "HDMI1");
+    events.send("org.rdk.HdmiInput", "onInputStatusChanged", status("hdmiin://localhost/deviceid/0", "stopped"));
+    wait_for_video_input_source(&mock, "Home");
+    events.send("org.rdk.AVInput", "onInputStatusChanged", status("cvbsin://localhost/deviceid/0", "started"));
+    wait_for_video_input_source(&mock, "Composite");
+
+    // The input shown on the device is the one stopped.
+    call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"videoInputSource": "HDMI1"})).unwrap();
+    assert_eq!(mock.calls("org.rdk.AVInput.stopInput"), vec![json!({"typeOfInput": "COMPOSITE"})]);
+    assert_eq!(mock.calls("org.rdk.HdmiInput.startHdmiInput"), vec![json!({"portId": 0})]);
+}
+
+#[test]
+fn system_settings_list_reports_video_input_ports() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedResolutions",
+        json!({"supportedResolutions": ["1080p"], "success": true}),
+    );
+    mock.on_error("Controller.1.status@org.rdk.HdmiCecSource", 22, "ERROR_UNKNOWN_KEY");
+    mock.on_result(
+        "org.rdk.TextToSpeech.isttsenabled",
+        json!({"isenabled": false, "TTS_Status": 0, "success": true}),
+    );
+    script_hdr_support(&mock, true, false);
+    script_audio_port(&mock);
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioModes",
+        json!({"supportedAudioModes": ["STEREO"], "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.DisplaySettings.getSupportedAudioPorts",
+        json!({"supportedAudioPorts": ["HDMI0"], "success": true}),
+    );
+
+    // A set-top box without input plugins.
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["videoInputSource"], json!(["Home"]));
+
+    script_video_inputs(&mock);
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["videoInputSource"], json!(["Home", "HDMI1", "HDMI2", "Composite"]));
+}
+
+#[cfg(feature = "2_1")]
+fn script_accessibility_settings(mock: &MockThunder) {
+    mock.on_result("org.rdk.UserSettings.getCaptions", json!(true));
+    mock.on_result("org.rdk.UserSettings.setCaptions", Value::Null);
+    mock.on_result("org.rdk.UserSettings.getPreferredCaptionsLanguages", json!("en-US,fr-FR"));
+    mock.on_result("org.rdk.UserSettings.getAudioDescription", json!(false));
+    mock.on_result(
+        "org.rdk.TextToSpeech.getttsconfiguration",
+        json!({"ttsendpoint": "", "language": "en-US", "voice": "carol", "volume": "100", "rate": 50, "success": true}),
+    );
+    mock.on_result(
+        "org.rdk.TextToSpeech.listvoices",
+        json!({"voices": ["Amber", "carol"], "success": true}),
+    );
+    mock.on_result("org.rdk.TextToSpeech.setttsconfiguration", json!({"TTS_Status": 0, "success": true}));
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn system_settings_report_accessibility_settings() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_accessibility_settings(&mock);
+
+    let response = call(&mock, RequestTypes::SystemSettingsListRequest, json!({})).unwrap();
+    assert_eq!(response["closedCaptions"], true);
+    assert_eq!(response["captionsLanguage"], json!(["en-US"]));
+    assert_eq!(response["audioDescription"], true);
+    assert_eq!(response["highContrastText"], false);
+    assert_eq!(response["screenMagnification"], false);
+    assert_eq!(response["textToSpeechVoice"], json!(["Amber", "carol"]));
+    assert_eq!(response["textToSpeechRate"], json!({"min": 1, "max": 100}));
+    assert_eq!(
+        mock.calls("org.rdk.TextToSpeech.listvoices"),
+        vec![json!({"language": "en-US"})]
+    );
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert_eq!(response["closedCaptions"], true);
+    assert_eq!(response["captionsLanguage"], "en-US");
+    assert_eq!(response["audioDescription"], false);
+    assert_eq!(response["highContrastText"], false);
+    assert_eq!(response["textToSpeechVoice"], "carol");
+    assert_eq!(response["textToSpeechRate"], 50);
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn system_settings_get_reports_unreadable_accessibility_settings() {
+    let mock = MockThunder::start();
+    script_current_settings(&mock);
+    script_accessibility_settings(&mock);
+    mock.on_error("org.rdk.UserSettings.getCaptions", 1, "ERROR_GENERAL");
+    mock.on_result("org.rdk.UserSettings.getAudioDescription", json!("yes"));
+
+    let response = call(&mock, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap();
+    assert!(response["errors"]["closedCaptions"].as_str().unwrap().contains("ERROR_GENERAL"));
+    assert!(response["errors"]["audioDescription"].as_str().unwrap().contains("yes"));
+    // Missing from this UserSettings release.
+    assert!(response["errors"].get("highContrastText").is_none());
+    assert_eq!(response["errors"].as_object().unwrap().len(), 2);
+    assert_eq!(response["textToSpeechVoice"], "carol");
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn system_settings_set_applies_accessibility_settings() {
+    let mock = MockThunder::start();
+    script_settings_capabilities(&mock);
+    script_accessibility_settings(&mock);
+
+    call(
+        &mock,
+        RequestTypes::SystemSettingsSetRequest,
+        json!({"closedCaptions": false, "textToSpeechVoice": "Amber", "textToSpeechRate": 70}),
+    )
+    .unwrap();
+    assert_eq!(mock.calls("org.rdk.UserSettings.setCaptions"), vec![json!({"enabled": false})]);
+    assert_eq!(
+        mock.calls("org.rdk.TextToSpeech.setttsconfiguration"),
+        vec![json!({"voice": "Amber"}), json!({"rate": 70})]
+    );
+
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"highContrastText": true})));
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"screenMagnification": true})));
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"textToSpeechRate": 0})));
+    assert_err400(call(&mock, RequestTypes::SystemSettingsSetRequest, json!({"textToSpeechVoice": "Bob"})));
+}
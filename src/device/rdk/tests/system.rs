@@ -0,0 +1,76 @@
+use super::*;
+#[cfg(feature = "2_1")]
+use std::thread;
+#[cfg(feature = "2_1")]
+use std::time::{Duration, Instant};
+
+#[test]
+fn system_restart_requests_reboot() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "org.rdk.System.reboot",
+        json!({"IARM_Bus_Call_STATUS": 0, "success": true}),
+    );
+
+    let response = call(&mock, RequestTypes::SystemRestartRequest, json!({})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(
+        mock.calls("org.rdk.System.reboot"),
+        vec![json!({"rebootReason": "DAB_REBOOT_REQUEST"})]
+    );
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn system_power_mode_maps_power_states() {
+    let mock = MockThunder::start();
+    mock.on_result("org.rdk.System.getPowerState", json!({"powerState": "LIGHT_SLEEP", "success": true}));
+    mock.on_result("org.rdk.System.setPowerState", json!({"success": true}));
+
+    let response = call(&mock, RequestTypes::SystemPowerModeGetRequest, json!({})).unwrap();
+    assert_eq!(response, json!({"powerMode": "LightSleep"}));
+
+    let response = call(&mock, RequestTypes::SystemPowerModeSetRequest, json!({"powerMode": "DeepSleep"})).unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(mock.calls("org.rdk.System.setPowerState")[0]["powerState"], "DEEP_SLEEP");
+
+    assert_err400(call(&mock, RequestTypes::SystemPowerModeSetRequest, json!({"powerMode": "Off"})));
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn system_power_mode_set_waits_for_the_wake_up_of_another_request() {
+    let mock = MockThunder::start();
+    // The device is on 300ms after setPowerState, unless it is put back to standby.
+    let state = Arc::new(Mutex::new((None::<Instant>, false)));
+    let current = state.clone();
+    mock.handle("org.rdk.System.getPowerState", move |_| {
+        let on = match *current.lock().unwrap() {
+            (Some(woken_at), false) => woken_at.elapsed() >= Duration::from_millis(300),
+            _ => false,
+        };
+        Reply::Result(json!({"powerState": if on { "ON" } else { "STANDBY" }, "success": true}))
+    });
+    mock.handle("org.rdk.System.setPowerState", move |params| {
+        match params["powerState"].as_str().unwrap() {
+            "ON" => state.lock().unwrap().0 = Some(Instant::now()),
+            _ => state.lock().unwrap().1 = true,
+        }
+        Reply::Result(json!({"success": true}))
+    });
+    mock.on_result("org.rdk.RDKShell.injectKey", json!({"success": true}));
+
+    thread::scope(|scope| {
+        let key_press = scope.spawn(|| call(&mock, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_LEFT"})));
+        thread::sleep(Duration::from_millis(100));
+        call(&mock, RequestTypes::SystemPowerModeSetRequest, json!({"powerMode": "Standby"})).unwrap();
+        key_press.join().unwrap().unwrap();
+    });
+    // The standby request didn't interrupt the wake-up.
+    let power_states: Vec<Value> = mock
+        .calls("org.rdk.System.setPowerState")
+        .iter()
+        .map(|params| params["powerState"].clone())
+        .collect();
+    assert_eq!(power_states, ["ON", "STANDBY"]);
+}
@@ -0,0 +1,129 @@
+use super::*;
+use crate::dab::device_telemetry::MetricSampler;
+use crate::device::DabPlatform;
+use crate::device::rdk::telemetry::{self, MetricCollector, NetworkCollector};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn app_telemetry_reports_app_metrics() {
+    let mock = MockThunder::start();
+    script_rdkshell(&mock, "Amazon", Some("suspended"));
+    mock.on_result(
+        "Monitor.status",
+        json!([{"measurements": {"resident": {"min": 0, "max": 0, "average": 0, "last": 52428800}}, "observable": "Amazon"}]),
+    );
+    mock.on_result(
+        "org.rdk.RDKShell.getSystemResourceInfo",
+        json!({"types": [{"callsign": "ResidentApp", "cpu": 3}, {"callsign": "Amazon", "cpu": 12}], "success": true}),
+    );
+
+    let platform = mock.platform();
+    assert_eq!(platform.get_app_state("PrimeVideo").unwrap(), "BACKGROUND");
+    assert_eq!(platform.get_app_memory("PrimeVideo").unwrap(), 51200);
+    assert_eq!(mock.calls("Monitor.status"), vec![json!({"callsign": "Amazon"})]);
+    assert_eq!(platform.get_app_cpu("PrimeVideo").unwrap(), 12);
+
+    // Applications not running have no metrics.
+    script_rdkshell(&mock, "Netflix", None);
+    assert_eq!(platform.get_app_state("Netflix").unwrap(), "STOPPED");
+    assert!(matches!(platform.get_app_cpu("Netflix"), Err(DabError::Err500(_))));
+}
+
+#[test]
+fn device_telemetry_collects_enabled_metrics() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "DeviceInfo.systeminfo",
+        json!({"totalram": 2097152000, "freeram": 1048576000, "cpuload": "7", "uptime": 3600}),
+    );
+    mock.on_result("org.rdk.System.getCoreTemperature", json!({"temperature": "48.500000", "success": true}));
+    mock.on_result(
+        "Controller.status",
+        json!([
+            {"callsign": "DeviceInfo", "state": "activated"},
+            {"callsign": "org.rdk.RDKShell", "state": "activated"},
+            {"callsign": "Netflix", "state": "deactivated"}
+        ]),
+    );
+
+    let collectors = telemetry::collectors(&["memory", "cpu", "temperature", "plugins", "uptime", "humidity"]);
+    assert_eq!(collectors.len(), 5);
+    let thunder = &mock.platform().thunder;
+    assert_eq!(
+        telemetry::collect(thunder, &collectors),
+        [
+            ("memory".to_string(), 1024000.0),
+            ("cpu".to_string(), 7.0),
+            ("temperature".to_string(), 48.5),
+            ("plugins".to_string(), 2.0),
+            ("uptime".to_string(), 3600.0),
+        ]
+    );
+
+    // A metric that can't be read is left out rather than reported as 0.
+    mock.on_error("org.rdk.System.getCoreTemperature", 2, "ERROR_UNAVAILABLE");
+    let metrics = telemetry::collect(thunder, &collectors);
+    assert!(metrics.iter().all(|(metric, _)| metric != "temperature"));
+    assert_eq!(metrics.len(), 4);
+}
+
+#[test]
+fn device_telemetry_sessions_share_samples() {
+    let mock = MockThunder::start();
+    mock.on_result(
+        "DeviceInfo.systeminfo",
+        json!({"totalram": 2097152000, "freeram": 1048576000, "cpuload": "7"}),
+    );
+    let sampler = MetricSampler::new();
+
+    let first = sampler.sample(mock.platform());
+    let second = sampler.sample(mock.platform());
+    assert_eq!(first, second);
+    // memory reads freeram and totalram, cpu reads cpuload.
+    assert_eq!(mock.calls("DeviceInfo.systeminfo").len(), 3);
+
+    thread::sleep(Duration::from_millis(150));
+    sampler.sample(mock.platform());
+    assert_eq!(mock.calls("DeviceInfo.systeminfo").len(), 6);
+
+    // Sessions tick on multiples of their period, so a 1s and a 2s session meet every 2s.
+    let until_second = sampler.next_tick(1000);
+    let until_two_seconds = sampler.next_tick(2000);
+    assert!(until_second >= Duration::from_millis(500) && until_second <= Duration::from_millis(1500));
+    assert!(until_two_seconds >= Duration::from_millis(1000) && until_two_seconds <= Duration::from_millis(3000));
+    let difference = until_two_seconds.abs_diff(until_second).as_millis();
+    assert!(difference % 1000 < 5 || difference % 1000 > 995, "{:?} {:?}", until_second, until_two_seconds);
+}
+
+#[test]
+fn device_telemetry_reports_network_throughput() {
+    let mock = MockThunder::start();
+    let path = std::env::temp_dir().join(format!("dab-adapter-{}-net-dev", std::process::id()));
+    let net_dev = |eth0: (u64, u64)| {
+        format!(
+            "Inter-|   Receive                                                |  Transmit\n \
+             face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n    \
+             lo:    5000      50    0    0    0     0          0         0     5000      50    0    0    0     0       0          0\n  \
+             eth0: {}     900    0    0    0     0          0         0 {}     400    0    0    0     0       0          0\n",
+            eth0.0, eth0.1
+        )
+    };
+
+    std::fs::write(&path, net_dev((1_000_000, 200_000))).unwrap();
+    let collector = NetworkCollector::new(path.clone());
+    let thunder = &mock.platform().thunder;
+    // The first collection only records the counters.
+    assert!(collector.collect(thunder).unwrap().is_empty());
+
+    thread::sleep(Duration::from_millis(200));
+    std::fs::write(&path, net_dev((1_100_000, 210_000))).unwrap();
+    let metrics = collector.collect(thunder).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let names: Vec<&str> = metrics.iter().map(|(metric, _)| metric.as_str()).collect();
+    assert_eq!(names, ["network_rx_eth0", "network_tx_eth0"]);
+    // 100000 and 10000 bytes over a bit more than 200ms.
+    assert!(metrics[0].1 > 100_000.0 && metrics[0].1 <= 500_000.0, "{:?}", metrics);
+    assert!(metrics[1].1 > 10_000.0 && metrics[1].1 <= 50_000.0, "{:?}", metrics);
+}
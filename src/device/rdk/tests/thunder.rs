@@ -0,0 +1,222 @@
+use super::*;
+use crate::device::rdk::interface::ThunderTokenSource;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn token_file(name: &str, token: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dab-adapter-{}-{}.token", std::process::id(), name));
+    std::fs::write(&path, token).unwrap();
+    path
+}
+
+#[test]
+fn thunder_token_is_sent_and_reloaded_when_rejected() {
+    let mut mock = MockThunder::start();
+    script_applications(&mock);
+    let path = token_file("reloaded", "token-1");
+    mock.require_token("token-1");
+    mock.use_token_source(ThunderTokenSource::File(path.clone()));
+
+    assert!(call(&mock, RequestTypes::ApplicationListRequest, json!({})).is_ok());
+
+    // The token is rotated on the device.
+    std::fs::write(&path, "token-2").unwrap();
+    mock.require_token("token-2");
+    assert!(call(&mock, RequestTypes::ApplicationListRequest, json!({})).is_ok());
+    assert_eq!(mock.calls("org.rdk.RDKShell.getAvailableTypes").len(), 2);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn thunder_token_is_requested_from_security_agent() {
+    let mut mock = MockThunder::start();
+    script_applications(&mock);
+    mock.on_result("SecurityAgent.createtoken", json!({"token": "agent-token"}));
+    mock.use_token_source(ThunderTokenSource::SecurityAgent);
+
+    // Not needed until Thunder requires it.
+    assert!(call(&mock, RequestTypes::ApplicationListRequest, json!({})).is_ok());
+    assert!(mock.calls("SecurityAgent.createtoken").is_empty());
+
+    mock.require_token("agent-token");
+    assert!(call(&mock, RequestTypes::ApplicationListRequest, json!({})).is_ok());
+    assert!(call(&mock, RequestTypes::ApplicationListRequest, json!({})).is_ok());
+    assert_eq!(mock.calls("SecurityAgent.createtoken").len(), 1);
+}
+
+#[test]
+fn thunder_token_is_refreshed_on_privileged_request_error() {
+    let mut mock = MockThunder::start();
+    mock.on_result("SecurityAgent.createtoken", json!({"token": "agent-token"}));
+    let rejected = AtomicUsize::new(0);
+    mock.handle("org.rdk.RDKShell.getAvailableTypes", move |_| {
+        if rejected.fetch_add(1, Ordering::SeqCst) == 0 {
+            Reply::Error(-32604, "Request needs authorization. Missing or invalid token.".to_string())
+        } else {
+            Reply::Result(json!({"types": ["Netflix"], "success": true}))
+        }
+    });
+    mock.use_token_source(ThunderTokenSource::SecurityAgent);
+
+    let response = call(&mock, RequestTypes::ApplicationListRequest, json!({})).unwrap();
+    assert_eq!(response, json!({"applications": [{"appId": "Netflix"}]}));
+    assert_eq!(mock.calls("SecurityAgent.createtoken").len(), 1);
+}
+
+#[test]
+fn thunder_rejection_without_token_is_reported() {
+    let mock = MockThunder::start();
+    script_applications(&mock);
+    mock.require_token("secret");
+
+    let result = call(&mock, RequestTypes::ApplicationListRequest, json!({}));
+    assert_err500(result, "SecurityAgent token");
+}
+
+#[test]
+fn thunder_token_is_sent_on_websocket() {
+    let mut mock = MockThunder::start();
+    mock.serve_file("/utterance.wav", b"RIFF....WAVEfmt ");
+    script_voice_status(&mock, "ready");
+    let events = mock.events();
+    mock.handle("org.rdk.VoiceControl.voiceSessionRequest", move |_| {
+        events.send("org.rdk.VoiceControl", "onSessionEnd", json!({"result": "success"}));
+        Reply::Result(json!({"success": true}))
+    });
+    let path = token_file("websocket", "ws-token");
+    mock.require_token("ws-token");
+    mock.use_token_source(ThunderTokenSource::File(path.clone()));
+
+    let response = call(
+        &mock,
+        RequestTypes::VoiceSendAudioRequest,
+        json!({"fileLocation": mock.file_url("/utterance.wav"), "voiceSystem": "AmazonAlexa"}),
+    );
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(response.unwrap(), json!({}));
+    assert_eq!(mock.calls("org.rdk.VoiceControl.register").len(), 1);
+}
+
+#[test]
+fn thunder_events_are_registered_again_after_reconnection() {
+    let mock = MockThunder::start();
+    let events = mock.platform().thunder.events().unwrap();
+    let launched = events.subscribe::<Value>("org.rdk.RDKShell.1", "onLaunched").unwrap();
+
+    // Thunder restarts.
+    mock.disconnect_websockets();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mock.calls("org.rdk.RDKShell.register").len() < 2 {
+        assert!(Instant::now() < deadline, "the event was not registered again");
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    mock.events().send("org.rdk.RDKShell", "onLaunched", json!({"client": "YouTube"}));
+    let event = launched.wait_for(Duration::from_secs(5), |_| true).unwrap();
+    assert_eq!(event, json!({"client": "YouTube"}));
+}
+
+#[test]
+fn thunder_events_are_delivered_to_each_subscriber() {
+    let mock = MockThunder::start();
+    let events = mock.platform().thunder.events().unwrap();
+    let first = events.subscribe::<Value>("Controller.1", "statechange").unwrap();
+    let second = events.subscribe::<Value>("Controller.1", "statechange").unwrap();
+    // One registration for both.
+    assert_eq!(mock.calls("Controller.register").len(), 1);
+
+    let state_change = json!({"callsign": "org.rdk.ScreenCapture", "state": "Activated"});
+    mock.events().send("Controller", "statechange", state_change.clone());
+    assert_eq!(first.wait_for(Duration::from_secs(5), |_| true).unwrap(), state_change);
+    assert_eq!(second.wait_for(Duration::from_secs(5), |_| true).unwrap(), state_change);
+
+    drop(first);
+    drop(second);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mock.calls("Controller.unregister").is_empty() {
+        assert!(Instant::now() < deadline, "the event was not unregistered");
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn thunder_watched_event_is_unregistered_once_its_receiver_is_dropped() {
+    let mock = MockThunder::start();
+    let events = mock.platform().thunder.events().unwrap();
+    let watched = events.watch("org.rdk.HdmiInput.1", "onInputStatusChanged").unwrap();
+    let status = json!({"id": 0, "locator": "hdmiin://localhost/deviceid/0", "status": "started"});
+    mock.events().send("org.rdk.HdmiInput", "onInputStatusChanged", status.clone());
+    assert_eq!(watched.recv_timeout(Duration::from_secs(5)).unwrap(), status);
+    assert!(mock.calls("org.rdk.HdmiInput.unregister").is_empty());
+
+    // The next event finds nobody to deliver it to.
+    drop(watched);
+    mock.events().send("org.rdk.HdmiInput", "onInputStatusChanged", status);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while mock.calls("org.rdk.HdmiInput.unregister").is_empty() {
+        assert!(Instant::now() < deadline, "the event was not unregistered");
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    // A new subscriber registers the event again.
+    let _watched = events.watch("org.rdk.HdmiInput.1", "onInputStatusChanged").unwrap();
+    assert_eq!(mock.calls("org.rdk.HdmiInput.register").len(), 2);
+}
+
+#[test]
+fn thunder_service_activation_waits_for_state_change() {
+    let mock = MockThunder::start();
+    let activated = Arc::new(Mutex::new(false));
+    let state = activated.clone();
+    mock.handle("Controller.1.status@org.rdk.ScreenCapture", move |_| {
+        let state = if *state.lock().unwrap() { "activated" } else { "deactivated" };
+        Reply::Result(json!([{"callsign": "org.rdk.ScreenCapture", "state": state}]))
+    });
+    let events = mock.events();
+    mock.handle("Controller.activate", move |_| {
+        let activated = activated.clone();
+        let events = events.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            *activated.lock().unwrap() = true;
+            events.send(
+                "Controller",
+                "statechange",
+                json!({"callsign": "org.rdk.ScreenCapture", "state": "Activated", "reason": "Requested"}),
+            );
+        });
+        Reply::Result(Value::Null)
+    });
+
+    let thunder = &mock.platform().thunder;
+    thunder.service_activate("org.rdk.ScreenCapture".to_string()).unwrap();
+    assert_eq!(thunder.get_service_state("org.rdk.ScreenCapture").unwrap(), "activated");
+}
+
+#[test]
+fn thunder_service_activation_polls_the_state_without_events() {
+    let mock = MockThunder::start();
+    mock.refuse_websockets();
+    let activated = Arc::new(Mutex::new(false));
+    let state = activated.clone();
+    mock.handle("Controller.1.status@org.rdk.ScreenCapture", move |_| {
+        let state = if *state.lock().unwrap() { "activated" } else { "deactivated" };
+        Reply::Result(json!([{"callsign": "org.rdk.ScreenCapture", "state": state}]))
+    });
+    mock.handle("Controller.activate", move |_| {
+        let activated = activated.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            *activated.lock().unwrap() = true;
+        });
+        Reply::Result(Value::Null)
+    });
+
+    let thunder = &mock.platform().thunder;
+    thunder.service_activate("org.rdk.ScreenCapture".to_string()).unwrap();
+    assert_eq!(thunder.get_service_state("org.rdk.ScreenCapture").unwrap(), "activated");
+    assert!(mock.calls("Controller.register").is_empty());
+}
@@ -0,0 +1,9 @@
+use super::*;
+
+#[test]
+fn version_lists_dab_versions() {
+    let mock = MockThunder::start();
+
+    let response = call(&mock, RequestTypes::VersionRequest, json!({})).unwrap();
+    assert!(response["versions"].as_array().unwrap().contains(&json!("2.0")));
+}
@@ -0,0 +1,107 @@
+use super::*;
+
+#[test]
+fn voice_list_reports_alexa_status() {
+    let mock = MockThunder::start();
+    script_voice_status(&mock, "ready");
+
+    let response = call(&mock, RequestTypes::VoiceListRequest, json!({})).unwrap();
+    assert_eq!(
+        response,
+        json!({"voiceSystems": [{"name": "AmazonAlexa", "enabled": true}]})
+    );
+}
+
+#[test]
+fn voice_set_configures_voice_control() {
+    let mock = MockThunder::start();
+    mock.on_result("org.rdk.VoiceControl.configureVoice", json!({"success": true}));
+
+    let response = call(
+        &mock,
+        RequestTypes::VoiceSetRequest,
+        json!({"voiceSystem": {"name": "AmazonAlexa", "enabled": false}}),
+    )
+    .unwrap();
+    assert_eq!(
+        response,
+        json!({"voiceSystem": {"name": "AmazonAlexa", "enabled": false}})
+    );
+    assert_eq!(
+        mock.calls("org.rdk.VoiceControl.configureVoice"),
+        vec![json!({"enable": false, "ptt": {"enable": false}})]
+    );
+
+    assert_err400(call(
+        &mock,
+        RequestTypes::VoiceSetRequest,
+        json!({"voiceSystem": {"name": "GoogleAssistant", "enabled": true}}),
+    ));
+}
+
+#[test]
+fn voice_send_audio_waits_for_session_end() {
+    let mock = MockThunder::start();
+    mock.serve_file("/utterance.wav", b"RIFF....WAVEfmt ");
+    script_voice_status(&mock, "idle");
+    mock.on_result("org.rdk.VoiceControl.configureVoice", json!({"success": true}));
+    let events = mock.events();
+    mock.handle("org.rdk.VoiceControl.voiceSessionRequest", move |_| {
+        events.send(
+            "org.rdk.VoiceControl",
+            "onSessionEnd",
+            json!({"remoteId": 255, "result": "success", "success": {"transcription": "what time is it"}}),
+        );
+        Reply::Result(json!({"success": true}))
+    });
+
+    let response = call(
+        &mock,
+        RequestTypes::VoiceSendAudioRequest,
+        json!({"fileLocation": mock.file_url("/utterance.wav"), "voiceSystem": "AmazonAlexa"}),
+    )
+    .unwrap();
+    assert_eq!(response, json!({}));
+    assert_eq!(
+        mock.calls("org.rdk.VoiceControl.configureVoice"),
+        vec![json!({"ptt": {"enable": true}})]
+    );
+    assert_eq!(
+        mock.calls("org.rdk.VoiceControl.voiceSessionRequest"),
+        vec![json!({"audio_file": "/tmp/tts.wav", "type": "ptt_audio_file"})]
+    );
+    let registrations = mock.calls("org.rdk.VoiceControl.register");
+    assert_eq!(registrations.len(), 1);
+    assert_eq!(registrations[0]["event"], "onSessionEnd");
+}
+
+#[test]
+fn voice_send_audio_reports_failed_session_request() {
+    let mock = MockThunder::start();
+    mock.serve_file("/utterance.wav", b"RIFF....WAVEfmt ");
+    script_voice_status(&mock, "ready");
+    mock.on_result("org.rdk.VoiceControl.voiceSessionRequest", json!({"success": false}));
+
+    let result = call(
+        &mock,
+        RequestTypes::VoiceSendAudioRequest,
+        json!({"fileLocation": mock.file_url("/utterance.wav"), "voiceSystem": "AmazonAlexa"}),
+    );
+    assert_err500(result, "voiceSessionRequest failed");
+}
+
+#[test]
+fn voice_send_rejects_unsupported_voice_system() {
+    let mock = MockThunder::start();
+
+    assert_err400(call(
+        &mock,
+        RequestTypes::VoiceSendAudioRequest,
+        json!({"fileLocation": "http://127.0.0.1/none.wav", "voiceSystem": "GoogleAssistant"}),
+    ));
+    assert_err400(call(
+        &mock,
+        RequestTypes::VoiceSendTextRequest,
+        json!({"requestText": "hello", "voiceSystem": "GoogleAssistant"}),
+    ));
+}
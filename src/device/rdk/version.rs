@@ -9,10 +9,16 @@ pub fn process(_dab_request: VersionRequest) -> Result<String, DabError> {
     let mut ResponseOperator = Version::default();
     // *** Fill in the fields of the struct Version here ***
 
-    ResponseOperator.versions.push("2.0".to_string());
-    #[cfg(feature = "2_1")]
-    ResponseOperator.versions.push("2.1".to_string());
+    ResponseOperator.versions = get_supported_versions();
 
     // *******************************************************************
     Ok(serde_json::to_string(&ResponseOperator).unwrap())
 }
+
+pub fn get_supported_versions() -> Vec<String> {
+    vec![
+        "2.0".to_string(),
+        #[cfg(feature = "2_1")]
+        "2.1".to_string(),
+    ]
+}
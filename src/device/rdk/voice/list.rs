@@ -2,13 +2,13 @@ use crate::dab::structs::DabError;
 use crate::dab::structs::ListVoiceSystemsResponse;
 use crate::dab::structs::VoiceListRequest;
 use crate::dab::structs::VoiceSystem;
-use crate::device::rdk::interface::http_post;
+use crate::device::rdk::interface::ThunderClient;
 use serde::{Deserialize, Serialize};
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: VoiceListRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: VoiceListRequest) -> Result<String, DabError> {
     let mut ResponseOperator = ListVoiceSystemsResponse::default();
     // *** Fill in the fields of the struct VoiceSystem here ***
 
@@ -22,7 +22,7 @@ pub fn process(_dab_request: VoiceListRequest) -> Result<String, DabError> {
 
     let request = RdkRequest {
         jsonrpc: "2.0".into(),
-        id: 3,
+        id: thunder.next_id(),
         method: "org.rdk.VoiceControl.voiceStatus".into(),
         params: "{}".into(),
     };
@@ -52,7 +52,7 @@ pub fn process(_dab_request: VoiceListRequest) -> Result<String, DabError> {
     }
 
     let json_string = serde_json::to_string(&request).unwrap();
-    let response = http_post(json_string)?;
+    let response = thunder.http_post(json_string)?;
 
     let rdkresponse: RdkResponse = serde_json::from_str(&response).unwrap();
     // Current Alexa solution is PTT & starts with protocol 'avs://'
@@ -1,17 +1,17 @@
 use super::voice_functions::sendVoiceCommand;
 use crate::dab::structs::DabError;
 use crate::dab::structs::SendAudioRequest;
-use crate::device::rdk::interface::http_download;
+use crate::device::rdk::interface::ThunderClient;
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: SendAudioRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: SendAudioRequest) -> Result<String, DabError> {
     if _dab_request.voiceSystem.to_string() != "AmazonAlexa" {
         return Err(DabError::Err400("Unsupported 'voiceSystem'.".to_string()));
     }
 
-    http_download(_dab_request.fileLocation)?;
-    sendVoiceCommand("/tmp/tts.wav".into())?;
+    thunder.http_download(_dab_request.fileLocation)?;
+    sendVoiceCommand(thunder, "/tmp/tts.wav".into())?;
     Ok("{}".to_string())
 }
@@ -1,6 +1,7 @@
 use super::voice_functions::sendVoiceCommand;
 use crate::dab::structs::DabError;
 use crate::dab::structs::SendTextRequest;
+use crate::device::rdk::interface::ThunderClient;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -12,7 +13,7 @@ use urlencoding::encode;
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: SendTextRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: SendTextRequest) -> Result<String, DabError> {
     // TODO: Add other RDK specific voice protocol support confirmation.
     if _dab_request.voiceSystem.to_string() != "AmazonAlexa" {
         return Err(DabError::Err400("Unsupported 'voiceSystem'.".to_string()));
@@ -59,7 +60,7 @@ pub fn process(_dab_request: SendTextRequest) -> Result<String, DabError> {
 
     child.wait().map_err(|_e| DabError::Err500("PCM S16LE gst-launch-1.0 conversion failed.".to_string()))?;
 
-    sendVoiceCommand("/tmp/tts.wav".into())?;
+    sendVoiceCommand(thunder, "/tmp/tts.wav".into())?;
 
     Ok("{}".to_string())
 }
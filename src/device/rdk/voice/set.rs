@@ -2,11 +2,12 @@ use super::voice_functions::configureVoice;
 use crate::dab::structs::DabError;
 use crate::dab::structs::SetVoiceSystemRequest;
 use crate::dab::structs::SetVoiceSystemResponse;
+use crate::device::rdk::interface::ThunderClient;
 
 #[allow(non_snake_case)]
 #[allow(dead_code)]
 #[allow(unused_mut)]
-pub fn process(_dab_request: SetVoiceSystemRequest) -> Result<String, DabError> {
+pub fn process(thunder: &ThunderClient, _dab_request: SetVoiceSystemRequest) -> Result<String, DabError> {
     let mut ResponseOperator = SetVoiceSystemResponse::default();
 
     // TODO: Add other RDK specific voice protocol support confirmation.
@@ -17,7 +18,7 @@ pub fn process(_dab_request: SetVoiceSystemRequest) -> Result<String, DabError>
         ));
     }
 
-    configureVoice(_dab_request.voiceSystem.enabled)?;
+    configureVoice(thunder, _dab_request.voiceSystem.enabled)?;
     // TODO: validation of response.
     // if response.success == false {
     //     return Err(DabError::Err400("Platform operation failed.".to_string());
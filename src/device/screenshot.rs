@@ -0,0 +1,256 @@
+// Encoding of screen captures for output/image: the requested region, downscaled to
+// fit the requested size, in the requested format, returned inline or sent to the
+// requested destination. Shared by the device platforms.
+
+use crate::dab::structs::CaptureScreenshotRequest;
+use crate::dab::structs::CaptureScreenshotResponse;
+use crate::dab::structs::DabError;
+use crate::dab::structs::OutputImageFormat;
+use crate::dab::structs::UploadMethod;
+use base64::{engine::general_purpose, Engine as _};
+use futures::executor::block_on;
+use image::imageops::FilterType;
+use image::io::Reader;
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageOutputFormat};
+use sha2::{Digest, Sha256};
+use std::fs::OpenOptions;
+use std::io::{Cursor, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+const DEFAULT_JPEG_QUALITY: u8 = 80;
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn validate(request: &CaptureScreenshotRequest) -> Result<(), DabError> {
+    if let Some(quality) = request.quality {
+        if request.format != Some(OutputImageFormat::Jpeg) {
+            return Err(DabError::Err400("'quality' only applies to the 'jpeg' format".to_string()));
+        }
+        if !(1..=100).contains(&quality) {
+            return Err(DabError::Err400("'quality' must be from 1 to 100".to_string()));
+        }
+    }
+    if request.maxWidth == Some(0) || request.maxHeight == Some(0) {
+        return Err(DabError::Err400("'maxWidth' and 'maxHeight' must be positive".to_string()));
+    }
+    if let Some(crop) = request.crop {
+        if crop.width == 0 || crop.height == 0 {
+            return Err(DabError::Err400("The crop region is empty".to_string()));
+        }
+    }
+    if request.destination.is_none() && request.uploadMethod.is_some() {
+        return Err(DabError::Err400("'uploadMethod' needs a 'destination'".to_string()));
+    }
+    Ok(())
+}
+
+pub fn is_http(destination: &str) -> bool {
+    destination.starts_with("http://") || destination.starts_with("https://")
+}
+
+fn file_path(destination: &str) -> &str {
+    destination.strip_prefix("file://").unwrap_or(destination)
+}
+
+// Files are only written in the upload directory of the operator, if any. Paths
+// with '..' or going through a symbolic link are refused.
+pub fn file_destination(destination: &str, upload_dir: Option<&Path>) -> Result<PathBuf, DabError> {
+    let upload_dir = upload_dir.ok_or_else(|| {
+        DabError::Err400("File destinations are not enabled on this device".to_string())
+    })?;
+    let path = Path::new(file_path(destination));
+    if !path.is_absolute() || path.components().any(|component| component == Component::ParentDir) {
+        return Err(DabError::Err400(
+            "'destination' must be an HTTP URL or an absolute file path without '..'".to_string(),
+        ));
+    }
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(DabError::Err400("'destination' must name a file".to_string()));
+    };
+
+    let upload_dir = upload_dir.canonicalize().map_err(|e| {
+        DabError::Err500(format!("Unable to use the upload directory {}: {}", upload_dir.display(), e))
+    })?;
+    let directory = parent
+        .canonicalize()
+        .map_err(|e| DabError::Err400(format!("Unable to use {}: {}", parent.display(), e)))?;
+    if directory != parent {
+        return Err(DabError::Err400("'destination' must not go through a symbolic link".to_string()));
+    }
+    if !directory.starts_with(&upload_dir) {
+        return Err(DabError::Err400(format!(
+            "'destination' must be in {}", upload_dir.display())));
+    }
+    let path = directory.join(name);
+    match path.symlink_metadata() {
+        Ok(metadata) if !metadata.is_file() => Err(DabError::Err400(format!(
+            "{} is not a regular file", path.display()))),
+        _ => Ok(path),
+    }
+}
+
+// Written to a new file in the same directory, then renamed over the destination:
+// a symbolic link put there meanwhile is replaced rather than followed.
+fn write_file(data: &[u8], path: &Path) -> Result<String, DabError> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    let write_error = |e: std::io::Error| DabError::Err500(format!("Error writing {}: {}", path.display(), e));
+    let directory = path.parent().unwrap_or(Path::new("/"));
+    let (temporary, mut file) = loop {
+        let name = format!(".dab-screenshot-{}-{}", std::process::id(), WRITES.fetch_add(1, Ordering::Relaxed));
+        let temporary = directory.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&temporary) {
+            Ok(file) => break (temporary, file),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(write_error(e)),
+        }
+    };
+    if let Err(e) = file.write_all(data).and_then(|_| std::fs::rename(&temporary, path)) {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(write_error(e));
+    }
+    Ok(path.display().to_string())
+}
+
+// Sends the image and returns where it is.
+fn upload(data: Vec<u8>, mime_type: &str, destination: &str, method: UploadMethod) -> Result<String, DabError> {
+    let upload_error = |e: surf::Error| DabError::Err500(format!("Error uploading to {}: {}", destination, e));
+    let request = match method {
+        UploadMethod::Put => surf::put(destination),
+        UploadMethod::Post => surf::post(destination),
+    };
+    let length = data.len();
+    let body = surf::Body::from_reader(futures::io::Cursor::new(data), Some(length));
+    let request = request.content_type(mime_type).body(body);
+    let response = block_on(async_std::future::timeout(UPLOAD_TIMEOUT, request))
+        .map_err(|_| DabError::Err500(format!("Timed out uploading to {}", destination)))?
+        .map_err(upload_error)?;
+    if !response.status().is_success() {
+        return Err(DabError::Err500(format!(
+            "Error uploading to {}: HTTP {}", destination, response.status())));
+    }
+    // A POST may create the image somewhere else.
+    match response.header("Location") {
+        Some(location) => Ok(location.as_str().to_string()),
+        None => Ok(destination.to_string()),
+    }
+}
+
+fn decode_error(e: impl std::fmt::Display) -> DabError {
+    DabError::Err500(format!("Unable to decode the screen capture: {}", e))
+}
+
+#[allow(non_snake_case)]
+fn response(
+    data: Vec<u8>,
+    format: ImageFormat,
+    (width, height): (u32, u32),
+    request: &CaptureScreenshotRequest,
+    file: Option<&Path>,
+) -> Result<CaptureScreenshotResponse, DabError> {
+    let mime_type = format.to_mime_type();
+    let mut ResponseOperator = CaptureScreenshotResponse {
+        width,
+        height,
+        mimeType: mime_type.to_string(),
+        ..Default::default()
+    };
+    match &request.destination {
+        Some(destination) => {
+            ResponseOperator.size = Some(data.len());
+            ResponseOperator.sha256 = Some(format!("{:x}", Sha256::digest(&data)));
+            ResponseOperator.location = Some(match file {
+                Some(path) => write_file(&data, path)?,
+                None => upload(data, mime_type, destination, request.uploadMethod.unwrap_or_default())?,
+            });
+        }
+        None => {
+            ResponseOperator.outputImage = Some(format!(
+                "data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(&data)));
+        }
+    }
+    Ok(ResponseOperator)
+}
+
+fn transform(image: DynamicImage, request: &CaptureScreenshotRequest) -> Result<DynamicImage, DabError> {
+    let mut image = image;
+    if let Some(crop) = request.crop {
+        let (width, height) = image.dimensions();
+        if crop.x.saturating_add(crop.width) > width || crop.y.saturating_add(crop.height) > height {
+            return Err(DabError::Err400(format!(
+                "The crop region is outside the {}x{} screen", width, height)));
+        }
+        image = image.crop_imm(crop.x, crop.y, crop.width, crop.height);
+    }
+
+    // Only ever downscaled, keeping the aspect ratio.
+    let (width, height) = image.dimensions();
+    let max_width = request.maxWidth.unwrap_or(width);
+    let max_height = request.maxHeight.unwrap_or(height);
+    if width > max_width || height > max_height {
+        image = image.resize(max_width, max_height, FilterType::Triangle);
+    }
+    Ok(image)
+}
+
+fn encode(image: DynamicImage, format: OutputImageFormat, quality: Option<u8>) -> Result<(Vec<u8>, ImageFormat), DabError> {
+    let (image, output_format, format) = match format {
+        OutputImageFormat::Png => (image, ImageOutputFormat::Png, ImageFormat::Png),
+        // JPEG has no alpha channel.
+        OutputImageFormat::Jpeg => (
+            DynamicImage::ImageRgb8(image.to_rgb8()),
+            ImageOutputFormat::Jpeg(quality.unwrap_or(DEFAULT_JPEG_QUALITY)),
+            ImageFormat::Jpeg,
+        ),
+        OutputImageFormat::Webp => (image, ImageOutputFormat::WebP, ImageFormat::WebP),
+    };
+
+    let mut data = vec![];
+    image
+        .write_to(&mut Cursor::new(&mut data), output_format)
+        .map_err(|e| DabError::Err500(format!("Unable to encode the screenshot: {}", e)))?;
+    Ok((data, format))
+}
+
+// File destinations are written in upload_dir; without it, they are refused.
+pub fn process(
+    data: &[u8],
+    request: &CaptureScreenshotRequest,
+    upload_dir: Option<&Path>,
+) -> Result<CaptureScreenshotResponse, DabError> {
+    validate(request)?;
+    let file = match &request.destination {
+        Some(destination) if !is_http(destination) => Some(file_destination(destination, upload_dir)?),
+        _ => None,
+    };
+
+    let reader = Reader::new(Cursor::new(data)).with_guessed_format().map_err(decode_error)?;
+    let source_format = reader.format().ok_or_else(|| decode_error("unknown format"))?;
+    let requested_format = request.format.map(|format| match format {
+        OutputImageFormat::Png => ImageFormat::Png,
+        OutputImageFormat::Jpeg => ImageFormat::Jpeg,
+        OutputImageFormat::Webp => ImageFormat::WebP,
+    });
+
+    // Returned as is, unless something else is requested.
+    let unchanged = request.crop.is_none()
+        && request.maxWidth.is_none()
+        && request.maxHeight.is_none()
+        && request.quality.is_none()
+        && requested_format.unwrap_or(source_format) == source_format;
+    if unchanged {
+        let dimensions = reader.into_dimensions().map_err(decode_error)?;
+        return response(data.to_vec(), source_format, dimensions, request, file.as_deref());
+    }
+
+    let image = transform(reader.decode().map_err(decode_error)?, request)?;
+    let dimensions = image.dimensions();
+    let format = request.format.unwrap_or(match source_format {
+        ImageFormat::Jpeg => OutputImageFormat::Jpeg,
+        ImageFormat::WebP => OutputImageFormat::Webp,
+        _ => OutputImageFormat::Png,
+    });
+    let (data, format) = encode(image, format, request.quality)?;
+    response(data, format, dimensions, request, file.as_deref())
+}
@@ -0,0 +1,654 @@
+use crate::dab::structs::*;
+use crate::device::rdk::applications::get_state::DABAppState;
+use crate::device::screenshot;
+use crate::device::DabPlatform;
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage};
+use local_ip_address::local_ip;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+mod tests;
+
+const DEVICE_ID: &str = "dab-simulator";
+
+const APPLICATIONS: [&str; 3] = ["YouTube", "Netflix", "PrimeVideo"];
+
+const LANGUAGES: [&str; 4] = ["en-US", "en-GB", "fr-FR", "es-ES"];
+
+const RESOLUTIONS: [(u32, u32); 3] = [(1280, 720), (1920, 1080), (3840, 2160)];
+
+const AUDIO_VOLUME_RANGE: AudioVolume = AudioVolume { min: 0, max: 100 };
+
+const MATCH_CONTENT_FRAME_RATES: [MatchContentFrameRate; 1] = [MatchContentFrameRate::EnabledAlways];
+
+const HDR_OUTPUT_MODES: [HdrOutputMode; 1] = [HdrOutputMode::DisableHdr];
+
+const AUDIO_OUTPUT_MODES: [AudioOutputMode; 1] = [AudioOutputMode::Stereo];
+
+const AUDIO_OUTPUT_SOURCES: [AudioOutputSource; 1] = [AudioOutputSource::HDMI];
+
+const KEYMAP: [&str; 41] = [
+    "KEY_POWER", "KEY_HOME", "KEY_VOLUME_UP", "KEY_VOLUME_DOWN", "KEY_MUTE",
+    "KEY_EXIT", "KEY_UP", "KEY_PAGE_UP", "KEY_PAGE_DOWN", "KEY_RIGHT",
+    "KEY_DOWN", "KEY_LEFT", "KEY_ENTER", "KEY_BACK", "KEY_PLAY",
+    "KEY_PLAY_PAUSE", "KEY_PAUSE", "KEY_STOP", "KEY_REWIND", "KEY_FAST_FORWARD",
+    "KEY_SKIP_REWIND", "KEY_SKIP_FAST_FORWARD", "KEY_0", "KEY_1", "KEY_2",
+    "KEY_3", "KEY_4", "KEY_5", "KEY_6", "KEY_7",
+    "KEY_8", "KEY_9", "KEY_CHANNEL_UP", "KEY_CHANNEL_DOWN", "KEY_MENU",
+    "KEY_INFO", "KEY_GUIDE", "KEY_RED", "KEY_GREEN", "KEY_YELLOW",
+    "KEY_BLUE",
+];
+
+// Size of the synthetic framebuffer returned by output/image.
+const SCREEN_WIDTH: u32 = 1280;
+const SCREEN_HEIGHT: u32 = 720;
+
+struct SimulatedApp {
+    app_id: String,
+    state: DABAppState,
+}
+
+struct SimulatorState {
+    apps: Vec<SimulatedApp>,
+    settings: GetSystemSettingsResponse,
+    voice_systems: Vec<VoiceSystem>,
+    #[cfg(feature = "2_1")]
+    power_mode: PowerMode,
+}
+
+impl SimulatorState {
+    fn find_app(&mut self, app_id: &str) -> Result<&mut SimulatedApp, DabError> {
+        self.apps
+            .iter_mut()
+            .find(|app| app.app_id.eq_ignore_ascii_case(app_id))
+            .ok_or_else(|| DabError::Err400(format!("Application {} is not available", app_id)))
+    }
+
+    fn foreground_app(&self) -> Option<&SimulatedApp> {
+        self.apps
+            .iter()
+            .find(|app| app.state == DABAppState::Foreground)
+    }
+
+    // Only one application is visible at a time; bringing one to the foreground
+    // sends the current one to the background.
+    fn bring_to_foreground(&mut self, app_id: &str) -> Result<(), DabError> {
+        self.find_app(app_id)?;
+        for app in self.apps.iter_mut() {
+            if app.app_id.eq_ignore_ascii_case(app_id) {
+                app.state = DABAppState::Foreground;
+            } else if app.state == DABAppState::Foreground {
+                app.state = DABAppState::Background;
+            }
+        }
+        Ok(())
+    }
+
+    fn press_key(&mut self, key_code: &str) -> Result<(), DabError> {
+        if !KEYMAP.contains(&key_code) {
+            return Err(DabError::Err400("keyCode' not found".to_string()));
+        }
+
+        match key_code {
+            "KEY_HOME" => {
+                for app in self.apps.iter_mut() {
+                    if app.state == DABAppState::Foreground {
+                        app.state = DABAppState::Background;
+                    }
+                }
+            }
+            "KEY_VOLUME_UP" => {
+                self.settings.audioVolume =
+                    (self.settings.audioVolume + 1).min(AUDIO_VOLUME_RANGE.max);
+            }
+            "KEY_VOLUME_DOWN" => {
+                self.settings.audioVolume = self.settings.audioVolume.saturating_sub(1);
+            }
+            "KEY_MUTE" => self.settings.mute = !self.settings.mute,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// In-memory virtual device, so the adapter can run without RDK hardware.
+pub struct SimulatorPlatform {
+    state: Mutex<SimulatorState>,
+    started_at: u64,
+    upload_dir: Option<PathBuf>,
+}
+
+impl SimulatorPlatform {
+    pub fn new() -> SimulatorPlatform {
+        let apps = APPLICATIONS
+            .iter()
+            .map(|app_id| SimulatedApp {
+                app_id: app_id.to_string(),
+                state: DABAppState::Stopped,
+            })
+            .collect();
+
+        let settings = GetSystemSettingsResponse {
+            language: LANGUAGES[0].to_string(),
+            outputResolution: OutputResolution {
+                width: 1920,
+                height: 1080,
+                frequency: 60.0,
+            },
+            audioVolume: 20,
+            ..Default::default()
+        };
+
+        let voice_systems = vec![VoiceSystem {
+            name: "AmazonAlexa".to_string(),
+            enabled: true,
+        }];
+
+        SimulatorPlatform {
+            state: Mutex::new(SimulatorState {
+                apps,
+                settings,
+                voice_systems,
+                #[cfg(feature = "2_1")]
+                power_mode: PowerMode::On,
+            }),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            upload_dir: None,
+        }
+    }
+
+    // Directory where output/image may write files, none by default.
+    pub fn with_upload_dir(mut self, upload_dir: Option<PathBuf>) -> SimulatorPlatform {
+        self.upload_dir = upload_dir;
+        self
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SimulatorState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+// Solid background identifying the foreground application, over a strip of color bars.
+fn render_framebuffer(foreground_app: Option<&str>) -> Result<Vec<u8>, DabError> {
+    const COLOR_BARS: [[u8; 3]; 7] = [
+        [192, 192, 192],
+        [192, 192, 0],
+        [0, 192, 192],
+        [0, 192, 0],
+        [192, 0, 192],
+        [192, 0, 0],
+        [0, 0, 192],
+    ];
+
+    let background = match foreground_app {
+        Some(app_id) => {
+            let hash = app_id
+                .bytes()
+                .fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32));
+            [(hash >> 16) as u8, (hash >> 8) as u8, hash as u8]
+        }
+        None => [16, 16, 16],
+    };
+
+    let framebuffer = RgbImage::from_fn(SCREEN_WIDTH, SCREEN_HEIGHT, |x, y| {
+        if y >= SCREEN_HEIGHT * 3 / 4 {
+            let bar = (x * COLOR_BARS.len() as u32 / SCREEN_WIDTH) as usize;
+            Rgb(COLOR_BARS[bar])
+        } else {
+            Rgb(background)
+        }
+    });
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(framebuffer)
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|e| DabError::Err500(format!("Error while encoding the framebuffer: {}", e)))?;
+    Ok(png)
+}
+
+// Rejects a value that system/settings/list doesn't report.
+fn check_listed<T: PartialEq + serde::Serialize>(setting: &str, value: &Option<T>, listed: &[T]) -> Result<(), DabError> {
+    match value {
+        Some(value) if !listed.contains(value) => Err(DabError::Err400(format!(
+            "Setting '{}' to {} is not supported",
+            setting,
+            serde_json::to_string(value).unwrap()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[allow(non_snake_case)]
+impl DabPlatform for SimulatorPlatform {
+    fn get_device_id(&self) -> Result<String, DabError> {
+        Ok(DEVICE_ID.to_string())
+    }
+    fn get_ip_address(&self) -> String {
+        local_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|_| "127.0.0.1".to_string())
+    }
+    fn get_supported_versions(&self) -> Vec<String> {
+        vec![
+            "2.0".to_string(),
+            #[cfg(feature = "2_1")]
+            "2.1".to_string(),
+        ]
+    }
+
+    // Memory grows with the number of running applications and the CPU load
+    // follows the foreground application, with some jitter.
+    fn get_device_memory(&self) -> Result<u32, DabError> {
+        let state = self.lock_state();
+        let memory = state.apps.iter().fold(350_000, |memory, app| match app.state {
+            DABAppState::Foreground => memory + 180_000,
+            DABAppState::Background => memory + 60_000,
+            DABAppState::Stopped => memory,
+        });
+        Ok(memory)
+    }
+    fn get_device_cpu(&self) -> Result<u32, DabError> {
+        let state = self.lock_state();
+        let jitter = (SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            % 5) as u32;
+        let load = if state.foreground_app().is_some() { 25 } else { 4 };
+        Ok(load + jitter)
+    }
+    fn get_app_state(&self, app_id: &str) -> Result<String, DabError> {
+        let mut state = self.lock_state();
+        Ok(state.find_app(app_id)?.state.as_str().to_string())
+    }
+    fn get_app_memory(&self, app_id: &str) -> Result<u32, DabError> {
+        let mut state = self.lock_state();
+        match state.find_app(app_id)?.state {
+            DABAppState::Foreground => Ok(180_000),
+            DABAppState::Background => Ok(60_000),
+            DABAppState::Stopped => Err(DabError::Err400(format!("{} is not running", app_id))),
+        }
+    }
+    fn get_app_cpu(&self, app_id: &str) -> Result<u32, DabError> {
+        let mut state = self.lock_state();
+        let jitter = (SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            % 5) as u32;
+        match state.find_app(app_id)?.state {
+            DABAppState::Foreground => Ok(20 + jitter),
+            DABAppState::Background => Ok(1),
+            DABAppState::Stopped => Err(DabError::Err400(format!("{} is not running", app_id))),
+        }
+    }
+
+    fn operations_list(&self, _request: OperationsListRequest) -> Result<String, DabError> {
+        let mut ResponseOperator = ListSupportedOperation::default();
+        for operation in [
+            "operations/list",
+            "applications/list",
+            "applications/launch",
+            "applications/launch-with-content",
+            "applications/get-state",
+            "applications/exit",
+            "device/info",
+            "system/restart",
+            "system/settings/list",
+            "system/settings/get",
+            "system/settings/set",
+            "input/key/list",
+            "input/key-press",
+            "input/long-key-press",
+            "output/image",
+            "device-telemetry/start",
+            "device-telemetry/stop",
+            "app-telemetry/start",
+            "app-telemetry/stop",
+            "health-check/get",
+            "voice/list",
+            "voice/set",
+            "version",
+            #[cfg(feature = "2_1")]
+            "system/power-mode/get",
+            #[cfg(feature = "2_1")]
+            "system/power-mode/set",
+        ] {
+            ResponseOperator.operations.push(operation.to_string());
+        }
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn applications_list(&self, _request: ApplicationListRequest) -> Result<String, DabError> {
+        let mut ResponseOperator = ListApplicationsResponse::default();
+        let state = self.lock_state();
+        for app in state.apps.iter() {
+            ResponseOperator.applications.push(Application {
+                appId: app.app_id.clone(),
+                ..Default::default()
+            });
+        }
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn applications_launch(&self, request: LaunchApplicationRequest) -> Result<String, DabError> {
+        if request.appId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'appId' parameter".to_string(),
+            ));
+        }
+        self.lock_state().bring_to_foreground(&request.appId)?;
+        Ok(serde_json::to_string(&LaunchApplicationResponse {}).unwrap())
+    }
+    fn applications_launch_with_content(
+        &self,
+        request: LaunchApplicationWithContentRequest,
+    ) -> Result<String, DabError> {
+        if request.appId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'appId' parameter".to_string(),
+            ));
+        }
+        if request.contentId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'contentId' parameter".to_string(),
+            ));
+        }
+        self.lock_state().bring_to_foreground(&request.appId)?;
+        Ok(serde_json::to_string(&LaunchApplicationWithContentResponse {}).unwrap())
+    }
+    fn applications_get_state(
+        &self,
+        request: GetApplicationStateRequest,
+    ) -> Result<String, DabError> {
+        if request.appId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'appId' parameter".to_string(),
+            ));
+        }
+        let mut state = self.lock_state();
+        let app = state.find_app(&request.appId)?;
+        let ResponseOperator = GetApplicationStateResponse {
+            state: app.state.as_str().to_string(),
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn applications_exit(&self, request: ExitApplicationRequest) -> Result<String, DabError> {
+        if request.appId.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'appId' parameter".to_string(),
+            ));
+        }
+        let mut state = self.lock_state();
+        let app = state.find_app(&request.appId)?;
+        // A stopped application stays stopped when asked to go to the background.
+        app.state = match (request.background.unwrap_or(false), app.state) {
+            (true, DABAppState::Stopped) | (false, _) => DABAppState::Stopped,
+            (true, _) => DABAppState::Background,
+        };
+        let ResponseOperator = ExitApplicationResponse {
+            state: app.state.as_str().to_string(),
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn device_info(&self, _request: DeviceInfoRequest) -> Result<String, DabError> {
+        let settings = &self.lock_state().settings;
+        let ResponseOperator = GetDeviceInformationResponse {
+            manufacturer: "DAB".to_string(),
+            model: "Simulator".to_string(),
+            serialNumber: "SIM0000000001".to_string(),
+            chipset: "virtual".to_string(),
+            firmwareVersion: env!("CARGO_PKG_VERSION").to_string(),
+            firmwareBuild: env!("VERGEN_GIT_SHA_SHORT").to_string(),
+            networkInterfaces: vec![NetworkInterface {
+                connected: true,
+                macAddress: "00:00:00:00:00:00".to_string(),
+                ipAddress: self.get_ip_address(),
+                dns: vec![],
+                r#type: NetworkInterfaceType::Ethernet,
+            }],
+            displayType: DisplayType::Native,
+            screenWidthPixels: settings.outputResolution.width,
+            screenHeightPixels: settings.outputResolution.height,
+            uptimeSince: self.started_at,
+            deviceId: DEVICE_ID.to_string(),
+            #[cfg(feature = "2_1")]
+            deviceType: Some("Simulator".to_string()),
+            #[cfg(feature = "2_1")]
+            modelName: Some("DAB Simulator".to_string()),
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn system_restart(&self, _request: RestartRequest) -> Result<String, DabError> {
+        for app in self.lock_state().apps.iter_mut() {
+            app.state = DABAppState::Stopped;
+        }
+        Ok(serde_json::to_string(&RestartResponse {}).unwrap())
+    }
+    fn system_settings_list(&self, _request: ListSystemSettingsRequest) -> Result<String, DabError> {
+        let ResponseOperator = ListSystemSettingsResponse {
+            language: LANGUAGES.iter().map(|l| l.to_string()).collect(),
+            outputResolution: RESOLUTIONS
+                .iter()
+                .map(|&(width, height)| OutputResolution {
+                    width,
+                    height,
+                    frequency: 60.0,
+                })
+                .collect(),
+            memc: false,
+            cec: true,
+            lowLatencyMode: false,
+            matchContentFrameRate: MATCH_CONTENT_FRAME_RATES.into(),
+            hdrOutputMode: HDR_OUTPUT_MODES.into(),
+            pictureMode: vec![],
+            audioOutputMode: AUDIO_OUTPUT_MODES.into(),
+            audioOutputSource: AUDIO_OUTPUT_SOURCES.into(),
+            videoInputSource: vec![],
+            audioVolume: AUDIO_VOLUME_RANGE,
+            mute: true,
+            textToSpeech: true,
+            #[cfg(feature = "2_1")]
+            closedCaptions: false,
+            #[cfg(feature = "2_1")]
+            captionsLanguage: vec![],
+            #[cfg(feature = "2_1")]
+            audioDescription: false,
+            #[cfg(feature = "2_1")]
+            highContrastText: false,
+            #[cfg(feature = "2_1")]
+            screenMagnification: false,
+            #[cfg(feature = "2_1")]
+            textToSpeechVoice: vec![],
+            #[cfg(feature = "2_1")]
+            textToSpeechRate: None,
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn system_settings_get(&self, _request: GetSystemSettingsRequest) -> Result<String, DabError> {
+        Ok(serde_json::to_string(&self.lock_state().settings).unwrap())
+    }
+    fn system_settings_set(&self, request: SetSystemSettingsRequest) -> Result<String, DabError> {
+        // Validate everything first, so a rejected request leaves the settings untouched.
+        if let Some(language) = &request.language {
+            if !LANGUAGES.contains(&language.as_str()) {
+                return Err(DabError::Err400(format!(
+                    "Language '{}' is not supported",
+                    language
+                )));
+            }
+        }
+        if let Some(resolution) = &request.outputResolution {
+            if !RESOLUTIONS.contains(&(resolution.width, resolution.height)) {
+                return Err(DabError::Err400("Unsupported video format".to_string()));
+            }
+        }
+        if let Some(volume) = request.audioVolume {
+            if !(AUDIO_VOLUME_RANGE.min..=AUDIO_VOLUME_RANGE.max).contains(&volume) {
+                return Err(DabError::Err400("Unsupported audio volume value".to_string()));
+            }
+        }
+        check_listed("matchContentFrameRate", &request.matchContentFrameRate, &MATCH_CONTENT_FRAME_RATES)?;
+        check_listed("hdrOutputMode", &request.hdrOutputMode, &HDR_OUTPUT_MODES)?;
+        check_listed("audioOutputMode", &request.audioOutputMode, &AUDIO_OUTPUT_MODES)?;
+        check_listed("audioOutputSource", &request.audioOutputSource, &AUDIO_OUTPUT_SOURCES)?;
+        for (setting, unsupported) in [
+            ("memc", request.memc.is_some()),
+            ("lowLatencyMode", request.lowLatencyMode.is_some()),
+            ("pictureMode", request.pictureMode.is_some()),
+            ("videoInputSource", request.videoInputSource.is_some()),
+            #[cfg(feature = "2_1")]
+            ("closedCaptions", request.closedCaptions.is_some()),
+            #[cfg(feature = "2_1")]
+            ("captionsLanguage", request.captionsLanguage.is_some()),
+            #[cfg(feature = "2_1")]
+            ("audioDescription", request.audioDescription.is_some()),
+            #[cfg(feature = "2_1")]
+            ("highContrastText", request.highContrastText.is_some()),
+            #[cfg(feature = "2_1")]
+            ("screenMagnification", request.screenMagnification.is_some()),
+            #[cfg(feature = "2_1")]
+            ("textToSpeechVoice", request.textToSpeechVoice.is_some()),
+            #[cfg(feature = "2_1")]
+            ("textToSpeechRate", request.textToSpeechRate.is_some()),
+        ] {
+            if unsupported {
+                return Err(DabError::Err400(format!(
+                    "Setting '{}' is not supported",
+                    setting
+                )));
+            }
+        }
+
+        let settings = &mut self.lock_state().settings;
+        if let Some(language) = request.language {
+            settings.language = language;
+        }
+        if let Some(resolution) = request.outputResolution {
+            settings.outputResolution = resolution;
+        }
+        if let Some(cec) = request.cec {
+            settings.cec = cec;
+        }
+        if let Some(match_content_frame_rate) = request.matchContentFrameRate {
+            settings.matchContentFrameRate = match_content_frame_rate;
+        }
+        if let Some(hdr_output_mode) = request.hdrOutputMode {
+            settings.hdrOutputMode = hdr_output_mode;
+        }
+        if let Some(audio_output_mode) = request.audioOutputMode {
+            settings.audioOutputMode = audio_output_mode;
+        }
+        if let Some(audio_output_source) = request.audioOutputSource {
+            settings.audioOutputSource = audio_output_source;
+        }
+        if let Some(volume) = request.audioVolume {
+            settings.audioVolume = volume;
+        }
+        if let Some(mute) = request.mute {
+            settings.mute = mute;
+        }
+        if let Some(text_to_speech) = request.textToSpeech {
+            settings.textToSpeech = text_to_speech;
+        }
+        Ok(serde_json::to_string(settings).unwrap())
+    }
+    fn input_key_list(&self, _request: KeyListRequest) -> Result<String, DabError> {
+        let ResponseOperator = KeyList {
+            keyCodes: KEYMAP.iter().map(|k| k.to_string()).collect(),
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn input_key_press(&self, request: KeyPressRequest) -> Result<String, DabError> {
+        if request.keyCode.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'keyCode' parameter".to_string(),
+            ));
+        }
+        self.lock_state().press_key(&request.keyCode)?;
+        Ok(serde_json::to_string(&KeyPressResponse {}).unwrap())
+    }
+    fn input_long_key_press(&self, request: LongKeyPressRequest) -> Result<String, DabError> {
+        if request.keyCode.is_empty() {
+            return Err(DabError::Err400(
+                "request missing 'keyCode' parameter".to_string(),
+            ));
+        }
+        if request.durationMs == 0 {
+            return Err(DabError::Err400(
+                "request missing 'durationMs' parameter".to_string(),
+            ));
+        }
+        self.lock_state().press_key(&request.keyCode)?;
+        Ok(serde_json::to_string(&LongKeyPressResponse {}).unwrap())
+    }
+    fn output_image(&self, request: CaptureScreenshotRequest) -> Result<String, DabError> {
+        let foreground_app = self
+            .lock_state()
+            .foreground_app()
+            .map(|app| app.app_id.clone());
+        let png = render_framebuffer(foreground_app.as_deref())?;
+        let ResponseOperator = screenshot::process(&png, &request, self.upload_dir.as_deref())?;
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn health_check_get(&self, _request: HealthCheckRequest) -> Result<String, DabError> {
+        Ok(serde_json::to_string(&HealthCheckResponse { healthy: true }).unwrap())
+    }
+    fn voice_list(&self, _request: VoiceListRequest) -> Result<String, DabError> {
+        let state = self.lock_state();
+        let ResponseOperator = ListVoiceSystemsResponse {
+            voiceSystems: state
+                .voice_systems
+                .iter()
+                .map(|voice| VoiceSystem {
+                    name: voice.name.clone(),
+                    enabled: voice.enabled,
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn voice_set(&self, request: SetVoiceSystemRequest) -> Result<String, DabError> {
+        let mut state = self.lock_state();
+        let voice = state
+            .voice_systems
+            .iter_mut()
+            .find(|voice| voice.name == request.voiceSystem.name)
+            .ok_or_else(|| {
+                DabError::Err400(format!(
+                    "Voice system {} is not supported",
+                    request.voiceSystem.name
+                ))
+            })?;
+        voice.enabled = request.voiceSystem.enabled;
+        let ResponseOperator = SetVoiceSystemResponse {
+            voiceSystem: request.voiceSystem,
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    fn version(&self, _request: VersionRequest) -> Result<String, DabError> {
+        let ResponseOperator = Version {
+            versions: self.get_supported_versions(),
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    #[cfg(feature = "2_1")]
+    fn system_power_mode_get(&self, _request: GetPowerModeRequest) -> Result<String, DabError> {
+        let ResponseOperator = GetPowerModeResponse {
+            powerMode: self.lock_state().power_mode,
+        };
+        Ok(serde_json::to_string(&ResponseOperator).unwrap())
+    }
+    #[cfg(feature = "2_1")]
+    fn system_power_mode_set(&self, request: SetPowerModeRequest) -> Result<String, DabError> {
+        self.lock_state().power_mode = request.powerMode;
+        Ok(serde_json::to_string(&SetPowerModeResponse {}).unwrap())
+    }
+}
@@ -0,0 +1,161 @@
+// Runs the DAB operations against the simulated device.
+
+use super::{SimulatorPlatform, KEYMAP, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::dab::call_function;
+use crate::dab::structs::{DabError, RequestTypes};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+
+fn call(platform: &SimulatorPlatform, request_type: RequestTypes, request: Value) -> Result<Value, DabError> {
+    call_function(platform, request.to_string(), request_type)
+        .map(|response| serde_json::from_str(&response).unwrap())
+}
+
+fn app_state(platform: &SimulatorPlatform, app_id: &str) -> String {
+    let response = call(platform, RequestTypes::ApplicationGetStateRequest, json!({"appId": app_id})).unwrap();
+    response["state"].as_str().unwrap().to_string()
+}
+
+fn settings(platform: &SimulatorPlatform) -> Value {
+    call(platform, RequestTypes::SystemSettingsGetRequest, json!({})).unwrap()
+}
+
+#[test]
+fn applications_move_between_foreground_and_background() {
+    let platform = SimulatorPlatform::new();
+    assert_eq!(app_state(&platform, "YouTube"), "STOPPED");
+
+    call(&platform, RequestTypes::ApplicationLaunchRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(app_state(&platform, "YouTube"), "FOREGROUND");
+
+    // Only one application is in the foreground.
+    call(&platform, RequestTypes::ApplicationLaunchWithContentRequest, json!({"appId": "Netflix", "contentId": "80100172"})).unwrap();
+    assert_eq!(app_state(&platform, "Netflix"), "FOREGROUND");
+    assert_eq!(app_state(&platform, "YouTube"), "BACKGROUND");
+
+    let response = call(&platform, RequestTypes::ApplicationExitRequest, json!({"appId": "Netflix", "background": true})).unwrap();
+    assert_eq!(response, json!({"state": "BACKGROUND"}));
+    let response = call(&platform, RequestTypes::ApplicationExitRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_eq!(response, json!({"state": "STOPPED"}));
+
+    // A stopped application isn't started by sending it to the background.
+    let response = call(&platform, RequestTypes::ApplicationExitRequest, json!({"appId": "PrimeVideo", "background": true})).unwrap();
+    assert_eq!(response, json!({"state": "STOPPED"}));
+
+    call(&platform, RequestTypes::SystemRestartRequest, json!({})).unwrap();
+    assert_eq!(app_state(&platform, "Netflix"), "STOPPED");
+}
+
+#[test]
+fn applications_reject_unknown_apps() {
+    let platform = SimulatorPlatform::new();
+
+    let result = call(&platform, RequestTypes::ApplicationLaunchRequest, json!({"appId": "Hulu"}));
+    assert!(matches!(result, Err(DabError::Err400(_))));
+    let result = call(&platform, RequestTypes::ApplicationGetStateRequest, json!({"appId": ""}));
+    assert!(matches!(result, Err(DabError::Err400(_))));
+    let result = call(&platform, RequestTypes::ApplicationLaunchWithContentRequest, json!({"appId": "YouTube"}));
+    assert!(matches!(result, Err(DabError::Err400(_))));
+}
+
+#[test]
+fn settings_set_updates_the_settings() {
+    let platform = SimulatorPlatform::new();
+
+    let request = json!({"language": "fr-FR", "outputResolution": {"width": 3840, "height": 2160, "frequency": 60.0}, "mute": true});
+    let response = call(&platform, RequestTypes::SystemSettingsSetRequest, request).unwrap();
+    assert_eq!(response["language"], "fr-FR");
+    assert_eq!(settings(&platform), response);
+    assert_eq!(response["outputResolution"]["width"], 3840);
+    assert_eq!(response["mute"], true);
+}
+
+#[test]
+fn settings_set_rejects_unsupported_values_without_changes() {
+    let platform = SimulatorPlatform::new();
+    let before = settings(&platform);
+
+    for request in [
+        json!({"language": "fr-FR", "audioVolume": 101}),
+        json!({"language": "xx-XX"}),
+        json!({"outputResolution": {"width": 640, "height": 480, "frequency": 60.0}}),
+        json!({"language": "fr-FR", "lowLatencyMode": true}),
+        json!({"language": "fr-FR", "hdrOutputMode": "AlwaysHdr"}),
+        json!({"matchContentFrameRate": "Disabled"}),
+        json!({"audioOutputMode": "PassThrough"}),
+        json!({"audioOutputSource": "Optical"}),
+    ] {
+        let result = call(&platform, RequestTypes::SystemSettingsSetRequest, request.clone());
+        assert!(matches!(result, Err(DabError::Err400(_))), "{}", request);
+    }
+    assert_eq!(settings(&platform), before);
+}
+
+#[test]
+fn keymap_keys_act_on_the_device() {
+    let platform = SimulatorPlatform::new();
+
+    let response = call(&platform, RequestTypes::InputKeyListRequest, json!({})).unwrap();
+    assert_eq!(response["keyCodes"].as_array().unwrap().len(), KEYMAP.len());
+
+    let volume = settings(&platform)["audioVolume"].as_u64().unwrap();
+    call(&platform, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_VOLUME_UP"})).unwrap();
+    assert_eq!(settings(&platform)["audioVolume"], volume + 1);
+    call(&platform, RequestTypes::InputLongKeyPressRequest, json!({"keyCode": "KEY_MUTE", "durationMs": 500})).unwrap();
+    assert_eq!(settings(&platform)["mute"], true);
+
+    call(&platform, RequestTypes::ApplicationLaunchRequest, json!({"appId": "YouTube"})).unwrap();
+    call(&platform, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_HOME"})).unwrap();
+    assert_eq!(app_state(&platform, "YouTube"), "BACKGROUND");
+
+    let result = call(&platform, RequestTypes::InputKeyPressRequest, json!({"keyCode": "KEY_TELEPORT"}));
+    assert!(matches!(result, Err(DabError::Err400(_))));
+}
+
+#[test]
+fn output_image_renders_the_foreground_app() {
+    let platform = SimulatorPlatform::new();
+    let capture = |platform: &SimulatorPlatform| {
+        let response = call(platform, RequestTypes::OutputImageRequest, json!({})).unwrap();
+        assert_eq!(response["width"], SCREEN_WIDTH);
+        assert_eq!(response["height"], SCREEN_HEIGHT);
+        let (_, data) = response["outputImage"].as_str().unwrap().split_once(";base64,").unwrap();
+        let image = image::load_from_memory(&general_purpose::STANDARD.decode(data).unwrap()).unwrap();
+        image.to_rgb8().get_pixel(0, 0).0
+    };
+
+    let home = capture(&platform);
+    call(&platform, RequestTypes::ApplicationLaunchRequest, json!({"appId": "YouTube"})).unwrap();
+    assert_ne!(capture(&platform), home);
+
+    let response = call(&platform, RequestTypes::OutputImageRequest, json!({"maxWidth": 640})).unwrap();
+    assert_eq!(response["width"], 640);
+    assert_eq!(response["height"], 360);
+}
+
+#[test]
+fn version_reports_the_supported_versions() {
+    let platform = SimulatorPlatform::new();
+
+    let response = call(&platform, RequestTypes::VersionRequest, json!({})).unwrap();
+    let versions = response["versions"].as_array().unwrap();
+    assert!(versions.contains(&json!("2.0")));
+    assert_eq!(versions.contains(&json!("2.1")), cfg!(feature = "2_1"));
+}
+
+#[cfg(feature = "2_1")]
+#[test]
+fn power_mode_set_is_reported_by_get() {
+    let platform = SimulatorPlatform::new();
+
+    let response = call(&platform, RequestTypes::SystemPowerModeGetRequest, json!({})).unwrap();
+    assert_eq!(response, json!({"powerMode": "On"}));
+    call(&platform, RequestTypes::SystemPowerModeSetRequest, json!({"powerMode": "Standby"})).unwrap();
+    let response = call(&platform, RequestTypes::SystemPowerModeGetRequest, json!({})).unwrap();
+    assert_eq!(response, json!({"powerMode": "Standby"}));
+
+    let response = call(&platform, RequestTypes::OperationsListRequest, json!({})).unwrap();
+    let operations = response["operations"].as_array().unwrap();
+    assert!(operations.contains(&json!("system/power-mode/get")));
+    assert!(operations.contains(&json!("system/power-mode/set")));
+}